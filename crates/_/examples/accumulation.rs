@@ -0,0 +1,128 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::{fs::File, path::Path, time::Instant};
+
+struct State {
+    context: DrawContext,
+    canvas: Option<Canvas>,
+    timer: Instant,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            context: Default::default(),
+            canvas: None,
+            timer: Instant::now(),
+        }
+    }
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+        graphics.main_camera.screen_alignment = 0.5.into();
+
+        self.context.shaders.insert(
+            "image".into(),
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+                .unwrap(),
+        );
+
+        self.context.textures.insert(
+            "ferris".into(),
+            load_texture(graphics, "resources/ferris.png"),
+        );
+        self.context
+            .textures
+            .insert("black".into(), graphics.pixel_texture([0, 0, 0]).unwrap());
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        let time = self.timer.elapsed().as_secs_f32();
+        let screen_size = graphics.main_camera.screen_size;
+
+        // Freshly created canvas has undefined content, so it has to be
+        // cleared once before we start accumulating frames into it.
+        let mut fresh = false;
+        if self.canvas.is_none() {
+            self.canvas = Canvas::from_screen(vec![GlowTextureFormat::Rgba], graphics)
+                .ok()
+                .map(|canvas| canvas.color([0.25, 0.25, 0.25, 1.0]));
+            fresh = true;
+        }
+        if let Some(canvas) = &mut self.canvas {
+            let size = (canvas.surface().width(), canvas.surface().height());
+            let _ = canvas.match_to_screen(graphics);
+            fresh = fresh || size != (canvas.surface().width(), canvas.surface().height());
+        }
+
+        self.context.begin_frame(graphics);
+
+        if let Some(canvas) = &self.canvas {
+            // Canvas content is kept between frames, so we skip clearing it.
+            canvas.activate_with(
+                &mut self.context,
+                graphics,
+                PrepareFrameOptions::default().clear(fresh),
+            );
+            self.context.push_shader(&ShaderRef::name("image"));
+            self.context.push_blending(GlowBlending::Alpha);
+
+            // Translucent overlay slowly fades out previously accumulated frames.
+            Sprite::single(SpriteTexture::new(
+                "u_image".into(),
+                TextureRef::name("black"),
+            ))
+            .tint([1.0, 1.0, 1.0, 0.05].into())
+            .size(screen_size)
+            .pivot(0.5.into())
+            .draw(&mut self.context, graphics);
+
+            Sprite::single(SpriteTexture::new(
+                "u_image".into(),
+                TextureRef::name("ferris"),
+            ))
+            .pivot(0.5.into())
+            .position([time.cos() * 300.0, (time * 2.0).sin() * 150.0].into())
+            .draw(&mut self.context, graphics);
+
+            Canvas::deactivate(&mut self.context, graphics);
+        }
+
+        self.context.push_shader(&ShaderRef::name("image"));
+        self.context.push_blending(GlowBlending::Alpha);
+
+        // Composite sprite covers whole screen and accounts for flipped Canvas content.
+        if let Some(sprite) = self.canvas.as_ref().and_then(|canvas| {
+            canvas.composite_sprite(0, "u_image".into(), ShaderRef::name("image"), graphics)
+        }) {
+            sprite.draw(&mut self.context, graphics);
+        }
+
+        self.context.end_frame().unwrap();
+    }
+}
+
+fn main() {
+    App::<Vertex>::default().run(State::default());
+}
+
+fn load_texture(graphics: &Graphics<Vertex>, path: impl AsRef<Path>) -> Texture {
+    let file = File::open(path).unwrap();
+    let decoder = png::Decoder::new(file);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let bytes = &buf[..info.buffer_size()];
+    graphics
+        .texture(
+            info.width,
+            info.height,
+            1,
+            GlowTextureFormat::Rgba,
+            Some(bytes),
+        )
+        .unwrap()
+}
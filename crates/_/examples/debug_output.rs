@@ -0,0 +1,45 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use vek::{Rgba, Vec2};
+
+#[derive(Default)]
+struct State {
+    context: DrawContext,
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+
+        // Driver messages get printed to console only in debug builds.
+        // Where KHR_debug is not available (e.g. WebGL2) this is a no-op.
+        if cfg!(debug_assertions) && graphics.enable_debug_output(DebugSeverity::Notification) {
+            // Benign message that goes through the same callback as the
+            // driver warnings, to confirm debug output works.
+            graphics.debug_message(DebugSeverity::Low, "Debug output enabled");
+        }
+
+        self.context.shaders.insert(
+            "color".into(),
+            graphics
+                .shader(Shader::COLORED_VERTEX_2D, Shader::PASS_FRAGMENT)
+                .unwrap(),
+        );
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        self.context.begin_frame(graphics);
+
+        PrimitivesEmitter::default()
+            .shader(ShaderRef::name("color"))
+            .emit_regular_polygon(6, Vec2::new(200.0, 200.0), 100.0)
+            .tint(Rgba::red())
+            .draw(&mut self.context, graphics);
+
+        self.context.end_frame().unwrap();
+    }
+}
+
+fn main() {
+    App::<Vertex>::new(AppConfig::default().title("Debug output")).run(State::default());
+}
@@ -16,8 +16,9 @@ struct State {
     // Tile sets store mappings from ID to texture region.
     tileset: TileSet,
     // We also store particle system instance that stores particle
-    // data used for emitting particles to render.
-    particles: ParticleSystem<ParticlesProcessor, ParticleData, ()>,
+    // data used for emitting particles to render. Its config holds
+    // forces applied to particles during processing.
+    particles: ParticleSystem<ParticlesProcessor, ParticleData, ParticleForces>,
     // This tells the angular location of particles spawner.
     particles_phase: f32,
     // Timer used for fixed step frame particle system simulation.
@@ -40,7 +41,13 @@ impl Default for State {
             })
             .shader(ShaderRef::name("image"))
             .mappings((0..11).map(|index| (index, TileSetItem::default().page(index as f32)))),
-            particles: ParticleSystem::new((), 100),
+            particles: ParticleSystem::new(
+                ParticleForces::default()
+                    .gravity(Vec2::new(0.0, 50.0))
+                    .drag(0.5)
+                    .field(ParticleForceField::new(Vec2::zero(), -300.0, 150.0)),
+                100,
+            ),
             particles_phase: 0.0,
             timer: Instant::now(),
         }
@@ -203,7 +210,7 @@ impl AppState<Vertex> for State {
             .emit(self.particles.emit())
             .draw(&mut self.context, graphics);
 
-        self.context.end_frame();
+        self.context.end_frame().unwrap();
     }
 }
 
@@ -247,10 +254,11 @@ struct ParticlesProcessor;
 
 // Particle processor tells particle system how to process particle
 // data and how to emit particle instance out of them.
-impl ParticleSystemProcessor<ParticleData, ()> for ParticlesProcessor {
-    fn process(_: &(), mut data: ParticleData) -> Option<ParticleData> {
+impl ParticleSystemProcessor<ParticleData, ParticleForces> for ParticlesProcessor {
+    fn process(forces: &ParticleForces, mut data: ParticleData) -> Option<ParticleData> {
         data.lifetime -= DELTA_TIME;
         if data.lifetime >= 0.0 {
+            data.velocity = forces.apply(data.position, data.velocity, DELTA_TIME);
             data.position += data.velocity * DELTA_TIME;
             data.color.a = data.lifetime / data.lifetime_max;
             Some(data)
@@ -259,7 +267,7 @@ impl ParticleSystemProcessor<ParticleData, ()> for ParticlesProcessor {
         }
     }
 
-    fn emit(_: &(), data: &ParticleData) -> Option<ParticleInstance> {
+    fn emit(_: &ParticleForces, data: &ParticleData) -> Option<ParticleInstance> {
         Some(ParticleInstance {
             tint: data.color,
             transform: Transform {
@@ -96,7 +96,7 @@ impl AppState<Vertex> for State {
             .draw(&mut self.context, graphics);
         }
 
-        self.context.end_frame();
+        self.context.end_frame().unwrap();
     }
 }
 
@@ -444,15 +444,20 @@ impl AppState<Vertex> for State {
             &ShaderRef::name("text"),
         );
 
-        self.draw.end_frame();
+        self.draw.end_frame().unwrap();
         self.input.maintain();
     }
 
-    fn on_event(&mut self, event: Event<()>, _: &mut Window) -> bool {
+    fn on_event_with(
+        &mut self,
+        event: Event<()>,
+        _: &mut Window,
+        _: &mut Graphics<Vertex>,
+        _: &mut AppControl,
+    ) {
         if let Event::WindowEvent { event, .. } = event {
             self.input.on_event(&event);
         }
-        true
     }
 }
 
@@ -0,0 +1,28 @@
+use spitfire_glow::prelude::*;
+
+struct State;
+
+impl AppState<Vertex3d> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex3d>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+
+        if let Some(version) = graphics.gl_version() {
+            println!(
+                "* Obtained GL {}.{} (embedded: {}): {}",
+                version.major, version.minor, version.is_embedded, version.vendor_info
+            );
+        }
+    }
+}
+
+fn main() {
+    // Request specific GL version and profile. App panics if driver can't
+    // provide at least requested version. On web it always uses WebGL2.
+    App::<Vertex3d>::new(
+        AppConfig::default()
+            .title("GL version")
+            .gl_version(3, 3)
+            .gl_profile(AppGlProfile::Core),
+    )
+    .run(State);
+}
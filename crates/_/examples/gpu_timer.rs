@@ -0,0 +1,64 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::collections::VecDeque;
+use vek::{Rgba, Vec2};
+
+const SAMPLES: usize = 60;
+
+#[derive(Default)]
+struct State {
+    context: DrawContext,
+    samples: VecDeque<f64>,
+    frame: usize,
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+
+        self.context.shaders.insert(
+            "color".into(),
+            graphics
+                .shader(Shader::COLORED_VERTEX_2D, Shader::PASS_FRAGMENT)
+                .unwrap(),
+        );
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.begin_gpu_timer();
+        self.context.begin_frame(graphics);
+
+        for index in 0..1000 {
+            let x = (index % 40) as f32 * 20.0 + 10.0;
+            let y = (index / 40) as f32 * 20.0 + 10.0;
+            PrimitivesEmitter::default()
+                .shader(ShaderRef::name("color"))
+                .emit_regular_polygon(16, Vec2::new(x, y), 8.0)
+                .tint(Rgba::new(x / 800.0, y / 500.0, 0.5, 1.0))
+                .draw(&mut self.context, graphics);
+        }
+
+        self.context.end_frame().unwrap();
+        // Submit batches before stopping timer, so they get measured.
+        graphics.draw().unwrap();
+
+        // Result is one frame late and can be missing when GPU is behind or
+        // timer queries are not supported at all (e.g. on WebGL2).
+        if let Some(milliseconds) = graphics.end_gpu_timer() {
+            if self.samples.len() >= SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(milliseconds);
+        }
+        self.frame += 1;
+        if self.frame >= SAMPLES && !self.samples.is_empty() {
+            self.frame = 0;
+            let average = self.samples.iter().sum::<f64>() / self.samples.len() as f64;
+            println!("* GPU frame time: {:.3} ms", average);
+        }
+    }
+}
+
+fn main() {
+    App::<Vertex>::new(AppConfig::default().title("GPU timer")).run(State::default());
+}
@@ -109,7 +109,7 @@ impl AppState<Vertex> for State {
             &ShaderRef::name("text"),
         );
 
-        self.draw.end_frame();
+        self.draw.end_frame().unwrap();
     }
 }
 
@@ -0,0 +1,80 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::{fs::File, io::BufWriter, path::Path};
+
+const SIZE: u32 = 256;
+
+fn main() {
+    // Headless graphics have no window nor screen to present to, which makes
+    // them useful for baking assets or testing rendering on servers.
+    let mut headless = HeadlessGraphics::<Vertex>::new(SIZE, SIZE).unwrap();
+    let graphics = &mut headless.graphics;
+    graphics.color = [0.0, 0.0, 0.0, 0.0];
+    graphics.main_camera.screen_alignment = 0.5.into();
+
+    let mut context = DrawContext::default();
+    context.shaders.insert(
+        "image".into(),
+        graphics
+            .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+            .unwrap(),
+    );
+    context.textures.insert(
+        "ferris".into(),
+        load_texture(graphics, "resources/ferris.png"),
+    );
+
+    // There is no default framebuffer we could read from, so we render
+    // into canvas and read pixels while it is still active.
+    let canvas = Canvas::simple(SIZE, SIZE, GlowTextureFormat::Rgba, graphics)
+        .unwrap()
+        .color([0.0, 0.0, 0.0, 0.0]);
+    context.begin_frame(graphics);
+    canvas.activate(&mut context, graphics, true);
+    context.push_shader(&ShaderRef::name("image"));
+    context.push_blending(GlowBlending::Alpha);
+    Sprite::single(SpriteTexture::new(
+        "u_image".into(),
+        TextureRef::name("ferris"),
+    ))
+    .pivot(0.5.into())
+    .draw(&mut context, graphics);
+    context.end_frame().unwrap();
+    graphics.draw().unwrap();
+    let mut pixels = graphics.read_pixels(0, 0, SIZE, SIZE).unwrap();
+    Graphics::<Vertex>::flip_pixels_vertically(SIZE, SIZE, &mut pixels);
+    context.begin_frame(graphics);
+    Canvas::deactivate(&mut context, graphics);
+    context.end_frame().unwrap();
+
+    let path = std::env::temp_dir().join("spitfire_headless.png");
+    save_png(&path, &pixels);
+    println!("* Saved: {:?}", path);
+}
+
+fn load_texture(graphics: &Graphics<Vertex>, path: impl AsRef<Path>) -> Texture {
+    let file = File::open(path).unwrap();
+    let decoder = png::Decoder::new(file);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let bytes = &buf[..info.buffer_size()];
+    graphics
+        .texture(
+            info.width,
+            info.height,
+            1,
+            GlowTextureFormat::Rgba,
+            Some(bytes),
+        )
+        .unwrap()
+}
+
+fn save_png(path: impl AsRef<Path>, pixels: &[u8]) {
+    let file = BufWriter::new(File::create(path).unwrap());
+    let mut encoder = png::Encoder::new(file, SIZE, SIZE);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(pixels).unwrap();
+}
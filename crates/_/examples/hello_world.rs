@@ -128,7 +128,6 @@ impl AppState<Vertex> for State {
         let fonts_texture = self.fonts_texture.as_mut().unwrap();
         let ferris_texture = self.ferris_texture.clone().unwrap();
         let ferris_vertices = texture_quad(&ferris_texture);
-        text_renderer.clear();
 
         let mut uniforms = HashMap::default();
         uniforms.insert(
@@ -220,14 +219,33 @@ impl AppState<Vertex> for State {
         layout.append(&self.fonts, &text);
         text_renderer.include(&self.fonts, &layout);
 
+        // Upload only atlas parts that changed, unless new page was added.
         let [width, height, depth] = text_renderer.atlas_size();
-        fonts_texture.upload(
-            width as _,
-            height as _,
-            depth as _,
-            GlowTextureFormat::Monochromatic,
-            Some(text_renderer.image()),
-        );
+        if fonts_texture.depth() != depth as u32 {
+            fonts_texture
+                .upload(
+                    width as _,
+                    height as _,
+                    depth as _,
+                    GlowTextureFormat::Monochromatic,
+                    Some(text_renderer.image()),
+                )
+                .unwrap();
+            while text_renderer.take_dirty_rect().is_some() {}
+        }
+        while let Some((page, rect)) = text_renderer.take_dirty_rect() {
+            fonts_texture
+                .upload_region(
+                    rect.min_x(),
+                    rect.min_y(),
+                    page as _,
+                    rect.width(),
+                    rect.height(),
+                    GlowTextureFormat::Monochromatic,
+                    &text_renderer.image_region(page, rect),
+                )
+                .unwrap();
+        }
 
         graphics.stream.batch(GraphicsBatch {
             shader: self.text_shader.clone(),
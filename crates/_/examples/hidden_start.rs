@@ -0,0 +1,69 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::{fs::File, path::Path};
+
+#[derive(Default)]
+struct State {
+    context: DrawContext,
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+        graphics.main_camera.screen_alignment = 0.5.into();
+
+        self.context.shaders.insert(
+            "image".into(),
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+                .unwrap(),
+        );
+
+        self.context.textures.insert(
+            "ferris".into(),
+            load_texture(graphics, "resources/ferris.png"),
+        );
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        self.context.begin_frame(graphics);
+        self.context.push_shader(&ShaderRef::name("image"));
+        self.context.push_blending(GlowBlending::Alpha);
+
+        Sprite::single(SpriteTexture::new(
+            "u_image".into(),
+            TextureRef::name("ferris"),
+        ))
+        .pivot(0.5.into())
+        .draw(&mut self.context, graphics);
+
+        self.context.end_frame().unwrap();
+    }
+}
+
+fn main() {
+    // Window starts hidden and App shows it only after first frame
+    // gets drawn and presented, so instead of briefly flashing with
+    // unpainted (usually white or black) content, window appears
+    // already filled with first frame. On web this has no effect.
+    App::<Vertex>::new(AppConfig::default().title("Hidden start").visible(false))
+        .run(State::default());
+}
+
+fn load_texture(graphics: &Graphics<Vertex>, path: impl AsRef<Path>) -> Texture {
+    let file = File::open(path).unwrap();
+    let decoder = png::Decoder::new(file);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let bytes = &buf[..info.buffer_size()];
+    graphics
+        .texture(
+            info.width,
+            info.height,
+            1,
+            GlowTextureFormat::Rgba,
+            Some(bytes),
+        )
+        .unwrap()
+}
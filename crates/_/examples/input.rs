@@ -5,7 +5,7 @@ use glutin::{
 use spitfire_draw::prelude::*;
 use spitfire_glow::prelude::*;
 use spitfire_input::*;
-use std::{fs::File, path::Path, time::Instant};
+use std::{fs::File, path::Path};
 use vek::{Quaternion, Rgba, Vec2};
 
 struct Player {
@@ -99,33 +99,26 @@ impl Player {
         }
     }
 
-    fn update(&mut self) {
-        if !self.input_camera_attached_to_ferris.get().is_hold() {
-            // By getting combinator values we get each of combined inputs state
-            // at once and let combinator process them into single useful value.
-            let input_move = Vec2::from(self.input_move.get())
-                .try_normalized()
-                .unwrap_or_default();
-            let input_rotate = self.input_rotate.get();
+    fn update(&mut self, graphics: &mut Graphics<Vertex>) {
+        // By getting combinator values we get each of combined inputs state
+        // at once and let combinator process them into single useful value.
+        let input_move = Vec2::from(self.input_move.get())
+            .try_normalized()
+            .unwrap_or_default();
+        let input_rotate = self.input_rotate.get();
 
+        if self.input_camera_attached_to_ferris.get().is_hold() {
+            graphics.main_camera.transform.position += input_move * self.speed;
+            graphics.main_camera.transform.orientation = graphics.main_camera.transform.orientation
+                * Quaternion::rotation_z(input_rotate * 5.0_f32.to_radians());
+        } else {
             self.sprite.transform.position += input_move * self.speed;
             self.sprite.transform.orientation = self.sprite.transform.orientation
                 * Quaternion::rotation_z(input_rotate * 5.0_f32.to_radians());
         }
     }
 
-    fn draw(&self, draw: &mut DrawContext, graphics: &mut Graphics<Vertex>, ticked: bool) {
-        if ticked && self.input_camera_attached_to_ferris.get().is_hold() {
-            let input_move = Vec2::from(self.input_move.get())
-                .try_normalized()
-                .unwrap_or_default();
-            let input_rotate = self.input_rotate.get();
-
-            graphics.main_camera.transform.position += input_move * self.speed;
-            graphics.main_camera.transform.orientation = graphics.main_camera.transform.orientation
-                * Quaternion::rotation_z(input_rotate * 5.0_f32.to_radians());
-        }
-
+    fn draw(&self, draw: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
         self.sprite.draw(draw, graphics);
     }
 }
@@ -133,7 +126,6 @@ impl Player {
 struct State {
     draw: DrawContext,
     input: InputContext,
-    tick: Instant,
     player: Player,
     input_exit: InputActionRef,
 }
@@ -158,17 +150,12 @@ impl State {
         Self {
             draw: Default::default(),
             input,
-            tick: Instant::now(),
             player,
             input_exit,
         }
     }
 
-    fn update(&mut self) {
-        self.player.update();
-    }
-
-    fn draw(&mut self, graphics: &mut Graphics<Vertex>, ticked: bool) {
+    fn draw(&mut self, graphics: &mut Graphics<Vertex>) {
         Sprite::default()
             .shader(ShaderRef::name("color"))
             .size(1000.0.into())
@@ -176,7 +163,7 @@ impl State {
             .tint(Rgba::blue())
             .draw(&mut self.draw, graphics);
 
-        self.player.draw(&mut self.draw, graphics, ticked);
+        self.player.draw(&mut self.draw, graphics);
     }
 }
 
@@ -205,21 +192,19 @@ impl AppState<Vertex> for State {
         );
     }
 
-    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
-        // We loosely simulate fixed update tick rate.
-        let ticked = self.tick.elapsed().as_millis() > 16;
-        if ticked {
-            self.tick = Instant::now();
-            self.update();
-        }
+    // Fixed updates are called by app at fixed timestep set in app config.
+    fn on_fixed_update(&mut self, graphics: &mut Graphics<Vertex>, _: &mut AppControl) {
+        self.player.update(graphics);
+    }
 
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
         self.draw.begin_frame(graphics);
         self.draw.push_shader(&ShaderRef::name("image"));
         self.draw.push_blending(GlowBlending::Alpha);
 
-        self.draw(graphics, ticked);
+        self.draw(graphics);
 
-        self.draw.end_frame();
+        self.draw.end_frame().unwrap();
         // After frame ends, we need to maintain inputs stack to make its
         // mappings properly change states from pressed/released into
         // idle/hold, otherwise inputs would have only pressed/released
@@ -227,19 +212,29 @@ impl AppState<Vertex> for State {
         self.input.maintain();
     }
 
-    fn on_event(&mut self, event: Event<()>, _: &mut Window) -> bool {
-        if let Event::WindowEvent { event, .. } = event {
-            // Here we apply received input changes for stack to update.
-            self.input.on_event(&event);
+    fn on_event_with(
+        &mut self,
+        event: Event<()>,
+        _: &mut Window,
+        _: &mut Graphics<Vertex>,
+        control: &mut AppControl,
+    ) {
+        // Here we apply received input changes for stack to update.
+        match event {
+            Event::WindowEvent { event, .. } => self.input.on_event(&event),
+            Event::DeviceEvent { event, .. } => self.input.on_device_event(&event),
+            _ => {}
         }
 
         // Here we read our application exit input and exit if pressed.
-        !self.input_exit.get().is_pressed()
+        if self.input_exit.get().is_pressed() {
+            control.exit();
+        }
     }
 }
 
 fn main() {
-    App::<Vertex>::default().run(State::new());
+    App::<Vertex>::new(AppConfig::default().fixed_timestep(Some(1.0 / 60.0))).run(State::new());
 }
 
 fn load_texture(graphics: &Graphics<Vertex>, path: impl AsRef<Path>) -> Texture {
@@ -0,0 +1,133 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::{collections::HashMap, fs::File, path::Path};
+
+const LIGHTMAP_SIZE: u32 = 64;
+
+#[derive(Default)]
+struct State {
+    shader: Option<Shader>,
+    base: Option<Texture>,
+    lightmap: Option<Texture>,
+}
+
+impl AppState<Vertex2UV> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex2UV>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+        graphics.main_camera.screen_alignment = 0.5.into();
+
+        // Base texture is sampled with first UV set and multiplied by
+        // lightmap sampled with second UV set.
+        self.shader = Some(
+            graphics
+                .shader(
+                    Shader::DUAL_TEXTURED_VERTEX_2D,
+                    Shader::DUAL_TEXTURED_MULTIPLY_FRAGMENT,
+                )
+                .unwrap(),
+        );
+        self.base = Some(load_texture(graphics, "resources/grass.png"));
+        self.lightmap = Some(
+            graphics
+                .texture(
+                    LIGHTMAP_SIZE,
+                    LIGHTMAP_SIZE,
+                    1,
+                    GlowTextureFormat::Rgb,
+                    Some(&lightmap()),
+                )
+                .unwrap(),
+        );
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex2UV>) {
+        let mut uniforms = HashMap::default();
+        uniforms.insert(
+            "u_projection_view".into(),
+            GlowUniformValue::M4(graphics.main_camera.world_matrix().into_col_array()),
+        );
+        uniforms.insert("u_image".into(), GlowUniformValue::I1(0));
+        uniforms.insert("u_image2".into(), GlowUniformValue::I1(1));
+
+        graphics.stream.batch(GraphicsBatch {
+            shader: self.shader.clone(),
+            uniforms,
+            textures: vec![
+                (self.base.clone().unwrap(), GlowTextureFiltering::Nearest),
+                (self.lightmap.clone().unwrap(), GlowTextureFiltering::Linear),
+            ],
+            ..Default::default()
+        });
+
+        // Both UV sets are independent - here lightmap is mapped with offset,
+        // so light spot lands near top left corner of base texture.
+        let size = 256.0;
+        let offset = 0.25;
+        graphics.stream.quad([
+            Vertex2UV {
+                position: [-size, -size],
+                uv: [0.0, 0.0, 0.0],
+                uv2: [offset, offset, 0.0],
+                ..Default::default()
+            },
+            Vertex2UV {
+                position: [size, -size],
+                uv: [1.0, 0.0, 0.0],
+                uv2: [1.0 + offset, offset, 0.0],
+                ..Default::default()
+            },
+            Vertex2UV {
+                position: [size, size],
+                uv: [1.0, 1.0, 0.0],
+                uv2: [1.0 + offset, 1.0 + offset, 0.0],
+                ..Default::default()
+            },
+            Vertex2UV {
+                position: [-size, size],
+                uv: [0.0, 1.0, 0.0],
+                uv2: [offset, 1.0 + offset, 0.0],
+                ..Default::default()
+            },
+        ]);
+    }
+}
+
+fn main() {
+    App::<Vertex2UV>::new(AppConfig::default().title("Lightmap")).run(State::default());
+}
+
+// Warm light spot in the middle, fading into cold darkness at edges.
+fn lightmap() -> Vec<u8> {
+    let half = LIGHTMAP_SIZE as f32 * 0.5;
+    (0..LIGHTMAP_SIZE)
+        .flat_map(|y| (0..LIGHTMAP_SIZE).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let dx = (x as f32 + 0.5 - half) / half;
+            let dy = (y as f32 + 0.5 - half) / half;
+            let light = (1.0 - (dx * dx + dy * dy).sqrt()).clamp(0.0, 1.0);
+            [
+                (255.0 * (0.1 + light * 0.9)) as u8,
+                (255.0 * (0.1 + light * 0.8)) as u8,
+                (255.0 * (0.2 + light * 0.5)) as u8,
+            ]
+        })
+        .collect()
+}
+
+fn load_texture(graphics: &Graphics<Vertex2UV>, path: impl AsRef<Path>) -> Texture {
+    let file = File::open(path).unwrap();
+    let decoder = png::Decoder::new(file);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let bytes = &buf[..info.buffer_size()];
+    graphics
+        .texture(
+            info.width,
+            info.height,
+            1,
+            GlowTextureFormat::Rgba,
+            Some(bytes),
+        )
+        .unwrap()
+}
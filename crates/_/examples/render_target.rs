@@ -0,0 +1,111 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::collections::HashMap;
+
+const TARGET_SIZE: u32 = 256;
+
+#[derive(Default)]
+struct State {
+    color_shader: Option<Shader>,
+    sprite_shader: Option<Shader>,
+    target: Option<Surface>,
+    phase: f32,
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+        graphics.main_camera.screen_alignment = 0.5.into();
+
+        self.color_shader = Some(
+            graphics
+                .shader(Shader::COLORED_VERTEX_2D, Shader::PASS_FRAGMENT)
+                .unwrap(),
+        );
+        self.sprite_shader = Some(
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+                .unwrap(),
+        );
+
+        // Render target is a surface with single color texture.
+        let mut target = graphics
+            .render_target(TARGET_SIZE, TARGET_SIZE, GlowTextureFormat::Rgba)
+            .unwrap();
+        target.set_color([0.0, 0.0, 0.5, 1.0]);
+        self.target = Some(target);
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        let target = self.target.clone().unwrap();
+        self.phase += 0.02;
+
+        // Stream gets rendered into target with projection matching its size.
+        let camera = Camera {
+            screen_alignment: 0.5.into(),
+            screen_size: (TARGET_SIZE as f32).into(),
+            ..Default::default()
+        };
+        graphics.stream.batch(GraphicsBatch {
+            shader: self.color_shader.clone(),
+            uniforms: uniforms(&camera),
+            ..Default::default()
+        });
+        let corners = [0.0, 2.0, 4.0].map(|offset| {
+            let angle = self.phase + offset;
+            Vertex {
+                position: [angle.cos() * 100.0, angle.sin() * 100.0],
+                color: [angle.cos() * 0.5 + 0.5, angle.sin() * 0.5 + 0.5, 1.0, 1.0],
+                ..Default::default()
+            }
+        });
+        graphics.stream.triangle(corners);
+        graphics.draw_to(&target, true).unwrap();
+
+        // Then its texture can be sampled like any other texture.
+        let texture = target.attachments()[0].texture.clone();
+        graphics.stream.batch(GraphicsBatch {
+            shader: self.sprite_shader.clone(),
+            uniforms: uniforms(&graphics.main_camera),
+            textures: vec![(texture, GlowTextureFiltering::Linear)],
+            ..Default::default()
+        });
+        let size = TARGET_SIZE as f32;
+        graphics.stream.quad([
+            Vertex {
+                position: [-size, -size],
+                uv: [0.0, 1.0, 0.0],
+                ..Default::default()
+            },
+            Vertex {
+                position: [size, -size],
+                uv: [1.0, 1.0, 0.0],
+                ..Default::default()
+            },
+            Vertex {
+                position: [size, size],
+                uv: [1.0, 0.0, 0.0],
+                ..Default::default()
+            },
+            Vertex {
+                position: [-size, size],
+                uv: [0.0, 0.0, 0.0],
+                ..Default::default()
+            },
+        ]);
+    }
+}
+
+fn uniforms(camera: &Camera) -> HashMap<std::borrow::Cow<'static, str>, GlowUniformValue> {
+    let mut result = HashMap::default();
+    result.insert(
+        "u_projection_view".into(),
+        GlowUniformValue::M4(camera.world_matrix().into_col_array()),
+    );
+    result.insert("u_image".into(), GlowUniformValue::I1(0));
+    result
+}
+
+fn main() {
+    App::<Vertex>::new(AppConfig::default().title("Render target")).run(State::default());
+}
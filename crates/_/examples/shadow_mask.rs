@@ -0,0 +1,154 @@
+use spitfire_draw::prelude::*;
+use spitfire_glow::prelude::*;
+use std::{fs::File, path::Path};
+
+const DARKEN_FRAGMENT: &str = r#"#version 300 es
+precision highp float;
+precision highp int;
+precision highp sampler2DArray;
+in vec4 v_color;
+in vec3 v_uv;
+out vec4 o_color;
+uniform sampler2DArray u_image;
+
+void main() {
+    float occlusion = texture(u_image, v_uv).x;
+    o_color = vec4(v_color.xyz, v_color.w * occlusion);
+}
+"#;
+
+#[derive(Default)]
+struct State {
+    context: DrawContext,
+    mask: Option<Canvas>,
+}
+
+impl AppState<Vertex> for State {
+    fn on_init(&mut self, graphics: &mut Graphics<Vertex>) {
+        graphics.color = [0.25, 0.25, 0.25, 1.0];
+        graphics.main_camera.screen_alignment = 0.5.into();
+
+        self.context.shaders.insert(
+            "image".into(),
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+                .unwrap(),
+        );
+        // Coverage shader writes only silhouettes of occluders into the mask.
+        self.context.shaders.insert(
+            "coverage".into(),
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::COVERAGE_FRAGMENT)
+                .unwrap(),
+        );
+        // Darken shader reads mask red channel as occlusion factor.
+        self.context.shaders.insert(
+            "darken".into(),
+            graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, DARKEN_FRAGMENT)
+                .unwrap(),
+        );
+
+        self.context.textures.insert(
+            "ferris".into(),
+            load_texture(graphics, "resources/ferris.png"),
+        );
+        self.context.textures.insert(
+            "grass".into(),
+            load_texture(graphics, "resources/grass.png"),
+        );
+    }
+
+    fn on_redraw(&mut self, graphics: &mut Graphics<Vertex>) {
+        // Mask follows screen size, so we recreate it when window resizes.
+        let width = graphics.main_camera.screen_size.x as u32;
+        let height = graphics.main_camera.screen_size.y as u32;
+        if self
+            .mask
+            .as_ref()
+            .map(|mask| mask.surface().width() != width || mask.surface().height() != height)
+            .unwrap_or(true)
+        {
+            self.mask = Canvas::mask(width.max(1), height.max(1), graphics).ok();
+        }
+
+        self.context.begin_frame(graphics);
+
+        // Occluders are drawn into the mask first, slightly offset to act as shadows.
+        if let Some(mask) = &self.mask {
+            mask.with(&mut self.context, graphics, true, |context, graphics| {
+                context.push_shader(&ShaderRef::name("coverage"));
+                context.push_blending(GlowBlending::Additive);
+                for x in [-200.0, 0.0, 200.0] {
+                    Sprite::single(SpriteTexture::new(
+                        "u_image".into(),
+                        TextureRef::name("ferris"),
+                    ))
+                    .pivot(0.5.into())
+                    .position([x + 20.0, 30.0].into())
+                    .draw(context, graphics);
+                }
+            });
+        }
+
+        self.context.push_shader(&ShaderRef::name("image"));
+        self.context.push_blending(GlowBlending::Alpha);
+
+        Sprite::single(SpriteTexture::new(
+            "u_image".into(),
+            TextureRef::name("grass"),
+        ))
+        .pivot(0.5.into())
+        .scale(4.0.into())
+        .draw(&mut self.context, graphics);
+
+        // Darkening pass samples mask and multiplies scene by occlusion.
+        if let Some(mask) = &self.mask {
+            self.context.push_shader(&ShaderRef::name("darken"));
+            Sprite::single(
+                mask.sprite_texture(0, "u_image".into(), GlowTextureFiltering::Linear)
+                    .unwrap(),
+            )
+            .tint([0.0, 0.0, 0.0, 0.6].into())
+            .pivot(0.5.into())
+            // We apply negative scale in Y axis to account for flipped Canvas content.
+            .scale([1.0, -1.0].into())
+            .draw(&mut self.context, graphics);
+            self.context.pop_shader();
+        }
+
+        for x in [-200.0, 0.0, 200.0] {
+            Sprite::single(SpriteTexture::new(
+                "u_image".into(),
+                TextureRef::name("ferris"),
+            ))
+            .pivot(0.5.into())
+            .position([x, 0.0].into())
+            .draw(&mut self.context, graphics);
+        }
+
+        self.context.end_frame().unwrap();
+    }
+}
+
+fn main() {
+    App::<Vertex>::default().run(State::default());
+}
+
+fn load_texture(graphics: &Graphics<Vertex>, path: impl AsRef<Path>) -> Texture {
+    let file = File::open(path).unwrap();
+    let decoder = png::Decoder::new(file);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    let bytes = &buf[..info.buffer_size()];
+    graphics
+        .texture(
+            info.width,
+            info.height,
+            1,
+            GlowTextureFormat::Rgba,
+            Some(bytes),
+        )
+        .unwrap()
+}
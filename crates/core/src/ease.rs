@@ -0,0 +1,199 @@
+use std::f32::consts::PI;
+
+pub type EaseFunction = fn(f32) -> f32;
+
+pub fn lerp(from: f32, to: f32, factor: f32) -> f32 {
+    from + (to - from) * factor
+}
+
+/// Returns factor at which `value` lies between `from` and `to`.
+pub fn inverse_lerp(from: f32, to: f32, value: f32) -> f32 {
+    if from == to {
+        0.0
+    } else {
+        (value - from) / (to - from)
+    }
+}
+
+/// Maps `value` from source range into target range.
+pub fn remap(value: f32, from: [f32; 2], to: [f32; 2]) -> f32 {
+    lerp(to[0], to[1], inverse_lerp(from[0], from[1], value))
+}
+
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+pub fn quad_in(t: f32) -> f32 {
+    t * t
+}
+
+pub fn quad_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn quad_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) * 0.5
+    }
+}
+
+pub fn cubic_in(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn cubic_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+    }
+}
+
+pub fn sine_in(t: f32) -> f32 {
+    1.0 - (t * PI * 0.5).cos()
+}
+
+pub fn sine_out(t: f32) -> f32 {
+    (t * PI * 0.5).sin()
+}
+
+pub fn sine_in_out(t: f32) -> f32 {
+    -((t * PI).cos() - 1.0) * 0.5
+}
+
+pub fn expo_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        2.0f32.powf(10.0 * t - 10.0)
+    }
+}
+
+pub fn expo_out(t: f32) -> f32 {
+    if t >= 1.0 {
+        1.0
+    } else {
+        1.0 - 2.0f32.powf(-10.0 * t)
+    }
+}
+
+pub fn expo_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        2.0f32.powf(20.0 * t - 10.0) * 0.5
+    } else {
+        (2.0 - 2.0f32.powf(-20.0 * t + 10.0)) * 0.5
+    }
+}
+
+const BACK_C1: f32 = 1.70158;
+const BACK_C2: f32 = BACK_C1 * 1.525;
+const BACK_C3: f32 = BACK_C1 + 1.0;
+
+pub fn back_in(t: f32) -> f32 {
+    BACK_C3 * t * t * t - BACK_C1 * t * t
+}
+
+pub fn back_out(t: f32) -> f32 {
+    1.0 + BACK_C3 * (t - 1.0).powi(3) + BACK_C1 * (t - 1.0).powi(2)
+}
+
+pub fn back_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (2.0 * t).powi(2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2) * 0.5
+    } else {
+        ((2.0 * t - 2.0).powi(2) * ((BACK_C2 + 1.0) * (t * 2.0 - 2.0) + BACK_C2) + 2.0) * 0.5
+    }
+}
+
+const ELASTIC_C4: f32 = (2.0 * PI) / 3.0;
+const ELASTIC_C5: f32 = (2.0 * PI) / 4.5;
+
+pub fn elastic_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        -(2.0f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * ELASTIC_C4).sin()
+    }
+}
+
+pub fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * ELASTIC_C4).sin() + 1.0
+    }
+}
+
+pub fn elastic_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        -(2.0f32.powf(20.0 * t - 10.0) * ((20.0 * t - 11.125) * ELASTIC_C5).sin()) * 0.5
+    } else {
+        2.0f32.powf(-20.0 * t + 10.0) * ((20.0 * t - 11.125) * ELASTIC_C5).sin() * 0.5 + 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundaries() {
+        let curves: [(&str, EaseFunction); 19] = [
+            ("linear", linear),
+            ("quad_in", quad_in),
+            ("quad_out", quad_out),
+            ("quad_in_out", quad_in_out),
+            ("cubic_in", cubic_in),
+            ("cubic_out", cubic_out),
+            ("cubic_in_out", cubic_in_out),
+            ("sine_in", sine_in),
+            ("sine_out", sine_out),
+            ("sine_in_out", sine_in_out),
+            ("expo_in", expo_in),
+            ("expo_out", expo_out),
+            ("expo_in_out", expo_in_out),
+            ("back_in", back_in),
+            ("back_out", back_out),
+            ("back_in_out", back_in_out),
+            ("elastic_in", elastic_in),
+            ("elastic_out", elastic_out),
+            ("elastic_in_out", elastic_in_out),
+        ];
+        for (name, curve) in curves {
+            assert!(curve(0.0).abs() < 1.0e-5, "{} at 0: {}", name, curve(0.0));
+            assert!(
+                (curve(1.0) - 1.0).abs() < 1.0e-5,
+                "{} at 1: {}",
+                name,
+                curve(1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(lerp(2.0, 4.0, 0.5), 3.0);
+        assert_eq!(inverse_lerp(2.0, 4.0, 3.0), 0.5);
+        assert_eq!(inverse_lerp(2.0, 2.0, 3.0), 0.0);
+        assert_eq!(remap(5.0, [0.0, 10.0], [100.0, 200.0]), 150.0);
+    }
+}
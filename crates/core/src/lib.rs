@@ -1,26 +1,64 @@
+pub mod ease;
+
 use bytemuck::{Pod, Zeroable};
 use std::{ops::Range, vec::Drain};
 
-#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+/// Integer type of triangle indices. Streams with `u16` indices halve index
+/// data size, but can address at most `u16::MAX + 1` vertices.
+pub trait TriangleIndex: Pod + Eq + Default + std::fmt::Debug + TryFrom<usize> + Into<u32> {}
+
+impl TriangleIndex for u16 {}
+impl TriangleIndex for u32 {}
+
+const INDEX_OVERFLOW: &str = "Triangle index exceeds range of stream index type";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(C)]
-pub struct Triangle {
-    pub a: u32,
-    pub b: u32,
-    pub c: u32,
+pub struct Triangle<I = u32> {
+    pub a: I,
+    pub b: I,
+    pub c: I,
 }
 
-impl Default for Triangle {
+// Safety: `repr(C)` struct of three fields of the same `Pod` type has no padding.
+unsafe impl<I: TriangleIndex> Zeroable for Triangle<I> {}
+unsafe impl<I: TriangleIndex> Pod for Triangle<I> {}
+
+impl<I: TriangleIndex> Default for Triangle<I> {
     fn default() -> Self {
-        Self { a: 0, b: 1, c: 2 }
+        Self::try_new(0, 1, 2).expect(INDEX_OVERFLOW)
     }
 }
 
-impl Triangle {
-    pub fn offset(mut self, offset: usize) -> Self {
-        self.a += offset as u32;
-        self.b += offset as u32;
-        self.c += offset as u32;
-        self
+impl<I: TriangleIndex> Triangle<I> {
+    /// Returns `None` when any index doesn't fit in index type.
+    pub fn try_new(a: usize, b: usize, c: usize) -> Option<Self> {
+        Some(Self {
+            a: I::try_from(a).ok()?,
+            b: I::try_from(b).ok()?,
+            c: I::try_from(c).ok()?,
+        })
+    }
+
+    /// # Panics
+    /// When any index would not fit in index type.
+    pub fn offset(self, offset: usize) -> Self {
+        self.checked_offset(offset).expect(INDEX_OVERFLOW)
+    }
+
+    /// Same as [`Self::offset`], but returns `None` when any index would
+    /// not fit in index type.
+    pub fn checked_offset(self, offset: usize) -> Option<Self> {
+        self.try_map(|index| index.checked_add(offset))
+    }
+
+    fn try_map(self, f: impl Fn(usize) -> Option<usize>) -> Option<Self> {
+        let index = |value: I| I::try_from(f(value.into() as usize)?).ok();
+        Some(Self {
+            a: index(self.a)?,
+            b: index(self.b)?,
+            c: index(self.c)?,
+        })
     }
 }
 
@@ -31,14 +69,19 @@ pub struct VertexStreamToken {
     batches: usize,
 }
 
-pub struct VertexStream<V: Pod, B> {
+/// Stream of vertices, triangles and batches of them.
+///
+/// Index type `I` defaults to `u32`. Pushing geometry panics when vertex
+/// index doesn't fit in index type, so `u16` streams can't hold more than
+/// `u16::MAX + 1` vertices.
+pub struct VertexStream<V: Pod, B, I: TriangleIndex = u32> {
     vertices: Vec<V>,
-    triangles: Vec<Triangle>,
+    triangles: Vec<Triangle<I>>,
     batches: Vec<(B, Range<usize>)>,
     resize_count: usize,
 }
 
-impl<V: Pod, B> Default for VertexStream<V, B> {
+impl<V: Pod, B, I: TriangleIndex> Default for VertexStream<V, B, I> {
     fn default() -> Self {
         Self {
             vertices: Vec::with_capacity(1024),
@@ -49,7 +92,7 @@ impl<V: Pod, B> Default for VertexStream<V, B> {
     }
 }
 
-impl<V: Pod, B> VertexStream<V, B> {
+impl<V: Pod, B, I: TriangleIndex> VertexStream<V, B, I> {
     pub fn new(resize_count: usize) -> Self {
         Self {
             vertices: Vec::with_capacity(resize_count),
@@ -84,11 +127,10 @@ impl<V: Pod, B> VertexStream<V, B> {
             result.extend_vertices(self.vertices.drain(vertices..));
             result.extend_triangles(
                 false,
-                self.triangles.drain(triangles..).map(|mut triangle| {
-                    triangle.a -= vertices as u32;
-                    triangle.b -= vertices as u32;
-                    triangle.c -= vertices as u32;
+                self.triangles.drain(triangles..).map(|triangle| {
                     triangle
+                        .try_map(|index| index.checked_sub(vertices))
+                        .expect(INDEX_OVERFLOW)
                 }),
             );
             result.extend_batches(self.batches.drain(batches..).map(|(batch, mut range)| {
@@ -114,6 +156,18 @@ impl<V: Pod, B> VertexStream<V, B> {
         self
     }
 
+    /// Applies `f` to vertices streamed since `token` was taken, e.g. to
+    /// move already streamed geometry. Triangles and batches are untouched.
+    pub fn transform_range(
+        &mut self,
+        token: VertexStreamToken,
+        f: impl FnMut(&mut V),
+    ) -> &mut Self {
+        let start = token.vertices.min(self.vertices.len());
+        self.vertices[start..].iter_mut().for_each(f);
+        self
+    }
+
     pub fn triangle(&mut self, vertices: [V; 3]) -> &mut Self {
         self.ensure_capacity();
         let offset = self.vertices.len();
@@ -124,17 +178,14 @@ impl<V: Pod, B> VertexStream<V, B> {
 
     pub fn triangle_fan(&mut self, vertices: impl IntoIterator<Item = V>) -> &mut Self {
         self.ensure_capacity();
-        let start = self.vertices.len() as u32;
+        let start = self.vertices.len();
         self.vertices.extend(vertices);
-        let end = self.vertices.len() as u32;
+        let end = self.vertices.len();
         let count = (end - start).saturating_sub(2);
         let mut offset = start + 1;
         for _ in 0..count {
-            self.triangles.push(Triangle {
-                a: start,
-                b: offset,
-                c: offset + 1,
-            });
+            self.triangles
+                .push(Triangle::try_new(start, offset, offset + 1).expect(INDEX_OVERFLOW));
             offset += 1;
         }
         self
@@ -142,26 +193,19 @@ impl<V: Pod, B> VertexStream<V, B> {
 
     pub fn triangle_strip(&mut self, vertices: impl IntoIterator<Item = V>) -> &mut Self {
         self.ensure_capacity();
-        let start = self.vertices.len() as u32;
+        let start = self.vertices.len();
         self.vertices.extend(vertices);
-        let end = self.vertices.len() as u32;
+        let end = self.vertices.len();
         let count = (end - start).saturating_sub(2);
         let mut offset = start;
         let mut flip = false;
         for _ in 0..count {
-            self.triangles.push(if flip {
-                Triangle {
-                    a: offset + 1,
-                    b: offset,
-                    c: offset + 2,
-                }
+            let triangle = if flip {
+                Triangle::try_new(offset + 1, offset, offset + 2)
             } else {
-                Triangle {
-                    a: offset,
-                    b: offset + 1,
-                    c: offset + 2,
-                }
-            });
+                Triangle::try_new(offset, offset + 1, offset + 2)
+            };
+            self.triangles.push(triangle.expect(INDEX_OVERFLOW));
             offset += 1;
             flip = !flip;
         }
@@ -173,16 +217,61 @@ impl<V: Pod, B> VertexStream<V, B> {
         let offset = self.vertices.len();
         self.vertices.extend(vertices);
         self.triangles
-            .push(Triangle { a: 0, b: 1, c: 2 }.offset(offset));
+            .push(Triangle::try_new(offset, offset + 1, offset + 2).expect(INDEX_OVERFLOW));
         self.triangles
-            .push(Triangle { a: 2, b: 3, c: 0 }.offset(offset));
+            .push(Triangle::try_new(offset + 2, offset + 3, offset).expect(INDEX_OVERFLOW));
         self
     }
 
+    /// Pushes lines for batches drawn as line list, with indices relative to
+    /// given vertices. Indices are stored in triples, so lines get padded
+    /// with zero length lines to keep line pairs aligned within batch.
+    pub fn line_list(
+        &mut self,
+        vertices: impl IntoIterator<Item = V>,
+        lines: impl IntoIterator<Item = [u32; 2]>,
+    ) -> &mut Self {
+        let mut indices = lines.into_iter().flatten().collect::<Vec<_>>();
+        if let Some(last) = indices.last().copied() {
+            // Pad to 6 indices, lowest count divisible by both 2 and 3.
+            indices.resize(indices.len().div_ceil(6) * 6, last);
+        }
+        self.index_list(vertices, indices)
+    }
+
+    /// Pushes points for batches drawn as point list, with indices relative
+    /// to given vertices. Indices are stored in triples, so last point gets
+    /// repeated to fill incomplete triple.
+    pub fn point_list(
+        &mut self,
+        vertices: impl IntoIterator<Item = V>,
+        points: impl IntoIterator<Item = u32>,
+    ) -> &mut Self {
+        let mut indices = points.into_iter().collect::<Vec<_>>();
+        if let Some(last) = indices.last().copied() {
+            indices.resize(indices.len().div_ceil(3) * 3, last);
+        }
+        self.index_list(vertices, indices)
+    }
+
+    fn index_list(
+        &mut self,
+        vertices: impl IntoIterator<Item = V>,
+        indices: Vec<u32>,
+    ) -> &mut Self {
+        self.extend(
+            vertices,
+            indices.chunks_exact(3).map(|chunk| {
+                Triangle::try_new(chunk[0] as usize, chunk[1] as usize, chunk[2] as usize)
+                    .expect(INDEX_OVERFLOW)
+            }),
+        )
+    }
+
     pub fn extend(
         &mut self,
         vertices: impl IntoIterator<Item = V>,
-        triangles: impl IntoIterator<Item = Triangle>,
+        triangles: impl IntoIterator<Item = Triangle<I>>,
     ) -> &mut Self {
         self.ensure_capacity();
         let offset = self.vertices.len();
@@ -207,7 +296,7 @@ impl<V: Pod, B> VertexStream<V, B> {
     pub unsafe fn extend_triangles(
         &mut self,
         relative: bool,
-        iter: impl IntoIterator<Item = Triangle>,
+        iter: impl IntoIterator<Item = Triangle<I>>,
     ) -> &Self {
         if relative {
             let offset = self.vertices.len();
@@ -285,13 +374,31 @@ impl<V: Pod, B> VertexStream<V, B> {
         self.batch(data);
     }
 
+    /// Merges neighbor batches with equal data and contiguous ranges, e.g.
+    /// ones left after appending streams.
+    pub fn coalesce_batches(&mut self)
+    where
+        B: PartialEq,
+    {
+        self.batch_end();
+        self.batches
+            .dedup_by(|(data, range), (prev_data, prev_range)| {
+                if data == prev_data && prev_range.end == range.start {
+                    prev_range.end = range.end;
+                    true
+                } else {
+                    false
+                }
+            });
+    }
+
     pub fn batch_end(&mut self) {
         if let Some(last) = self.batches.last_mut() {
             last.1.end = self.triangles.len();
         }
     }
 
-    pub fn render<R: VertexStreamRenderer<V, B>>(
+    pub fn render<R: VertexStreamRenderer<V, B, I>>(
         &mut self,
         renderer: &mut R,
     ) -> Result<(), R::Error> {
@@ -303,16 +410,33 @@ impl<V: Pod, B> VertexStream<V, B> {
         &self.vertices
     }
 
-    pub fn triangles(&self) -> &[Triangle] {
+    pub fn triangles(&self) -> &[Triangle<I>] {
         &self.triangles
     }
 
+    /// Axis-aligned bounding box of all vertices as
+    /// `[min_x, min_y, max_x, max_y]`, or `None` when stream is empty.
+    /// Vertex layout is opaque, so position is read with `extract`.
+    pub fn bounds(&self, extract: impl Fn(&V) -> [f32; 2]) -> Option<[f32; 4]> {
+        self.vertices
+            .iter()
+            .map(extract)
+            .fold(None, |result, [x, y]| {
+                Some(match result {
+                    Some([min_x, min_y, max_x, max_y]) => {
+                        [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                    }
+                    None => [x, y, x, y],
+                })
+            })
+    }
+
     pub fn batches(&self) -> &[(B, Range<usize>)] {
         &self.batches
     }
 
     #[allow(clippy::type_complexity)]
-    pub fn drain(&mut self) -> (Drain<V>, Drain<Triangle>, Drain<(B, Range<usize>)>) {
+    pub fn drain(&mut self) -> (Drain<V>, Drain<Triangle<I>>, Drain<(B, Range<usize>)>) {
         self.batch_end();
         (
             self.vertices.drain(..),
@@ -331,16 +455,177 @@ impl<V: Pod, B> VertexStream<V, B> {
     }
 }
 
-pub trait VertexStreamRenderer<V: Pod, B> {
+/// Recycles vertex streams to reuse their allocated capacity.
+///
+/// Pool is plain owned data, so keep one per thread to avoid locking.
+pub struct VertexStreamPool<V: Pod, B, I: TriangleIndex = u32> {
+    streams: Vec<VertexStream<V, B, I>>,
+    resize_count: usize,
+}
+
+impl<V: Pod, B, I: TriangleIndex> Default for VertexStreamPool<V, B, I> {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl<V: Pod, B, I: TriangleIndex> VertexStreamPool<V, B, I> {
+    pub fn new(resize_count: usize) -> Self {
+        Self {
+            streams: Default::default(),
+            resize_count,
+        }
+    }
+
+    /// Gives recycled stream if any available, or creates new one.
+    pub fn acquire(&mut self) -> VertexStream<V, B, I> {
+        self.streams
+            .pop()
+            .unwrap_or_else(|| VertexStream::new(self.resize_count))
+    }
+
+    /// Clears stream and stores it for reuse, keeping its capacity.
+    pub fn release(&mut self, mut stream: VertexStream<V, B, I>) {
+        stream.clear();
+        self.streams.push(stream);
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.streams.clear();
+    }
+}
+
+pub trait VertexStreamRenderer<V: Pod, B, I: TriangleIndex = u32> {
     type Error;
 
-    fn render(&mut self, stream: &mut VertexStream<V, B>) -> Result<(), Self::Error>;
+    fn render(&mut self, stream: &mut VertexStream<V, B, I>) -> Result<(), Self::Error>;
 }
 
-impl<V: Pod, B> VertexStreamRenderer<V, B> for () {
+impl<V: Pod, B, I: TriangleIndex> VertexStreamRenderer<V, B, I> for () {
     type Error = ();
 
-    fn render(&mut self, _: &mut VertexStream<V, B>) -> Result<(), Self::Error> {
+    fn render(&mut self, _: &mut VertexStream<V, B, I>) -> Result<(), Self::Error> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Triangle, VertexStream};
+
+    #[test]
+    fn test_coalesce_batches() {
+        let mut stream = VertexStream::<[f32; 2], u8>::default();
+        for data in [1, 1, 2] {
+            let mut other = VertexStream::<[f32; 2], u8>::default();
+            other.batch(data);
+            other.triangle([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+            other.batch_end();
+            stream.append(&mut other);
+        }
+        let mut other = VertexStream::<[f32; 2], u8>::default();
+        other.batch(2);
+        other.quad([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        other.batch(2);
+        other.triangle([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        other.batch(1);
+        other.triangle([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        other.batch_end();
+        stream.append_cloned(&other);
+        assert_eq!(stream.batches().len(), 6);
+
+        stream.coalesce_batches();
+        assert_eq!(stream.batches(), &[(1, 0..2), (2, 2..6), (1, 6..7)]);
+    }
+
+    #[test]
+    fn test_u16_indices() {
+        let triangle = Triangle::<u16>::default();
+        assert_eq!(
+            triangle.checked_offset(65533),
+            Some(Triangle {
+                a: 65533,
+                b: 65534,
+                c: 65535
+            })
+        );
+        assert!(triangle.checked_offset(65534).is_none());
+        assert!(Triangle::<u16>::try_new(0, 1, 65536).is_none());
+        let triangle = Triangle::<u32>::default();
+        assert_eq!(triangle.checked_offset(10).map(|t| t.c), Some(12));
+        assert!(triangle.checked_offset(u32::MAX as usize - 1).is_none());
+        assert!(triangle.checked_offset(usize::MAX).is_none());
+
+        let mut stream = VertexStream::<[f32; 2], (), u16>::default();
+        stream.triangle_fan(vec![[0.0, 0.0]; 65536]);
+        assert_eq!(stream.triangles().last().map(|t| t.c), Some(65535));
+        let mut stream = VertexStream::<[f32; 2], (), u16>::default();
+        stream.triangle([[0.0, 0.0]; 3]);
+        let mut other = VertexStream::<[f32; 2], (), u16>::default();
+        other.quad([[0.0, 0.0]; 4]);
+        let token = stream.token();
+        stream.append_cloned(&other);
+        let extracted = unsafe { stream.extract(token) };
+        assert_eq!(extracted.triangles(), other.triangles());
+    }
+
+    #[test]
+    #[should_panic(expected = "Triangle index exceeds range of stream index type")]
+    fn test_u16_indices_overflow() {
+        let mut stream = VertexStream::<[f32; 2], (), u16>::default();
+        stream.triangle_fan(vec![[0.0, 0.0]; 65536]);
+        stream.triangle([[0.0, 0.0]; 3]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut stream = VertexStream::<[f32; 2], ()>::default();
+        assert_eq!(stream.bounds(|vertex| *vertex), None);
+        stream.triangle([[1.0, 2.0], [-3.0, 4.0], [5.0, -6.0]]);
+        stream.quad([[0.0, 0.0], [10.0, 0.0], [10.0, 1.0], [0.0, 1.0]]);
+        assert_eq!(
+            stream.bounds(|vertex| *vertex),
+            Some([-3.0, -6.0, 10.0, 4.0])
+        );
+    }
+
+    #[test]
+    fn test_line_and_point_lists() {
+        let mut stream = VertexStream::<[f32; 2], ()>::default();
+        stream.triangle([[0.0, 0.0]; 3]);
+        stream.line_list([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]], [[0, 1], [1, 2]]);
+        assert_eq!(
+            &stream.triangles()[1..],
+            &[Triangle { a: 3, b: 4, c: 4 }, Triangle { a: 5, b: 5, c: 5 },]
+        );
+        stream.point_list([[0.0, 0.0], [1.0, 0.0]], [0, 1]);
+        assert_eq!(stream.triangles()[3], Triangle { a: 6, b: 7, c: 7 });
+        stream.line_list([[0.0, 0.0]], []);
+        assert_eq!(stream.triangles().len(), 4);
+        assert_eq!(stream.vertices().len(), 9);
+    }
+
+    #[test]
+    fn test_transform_range() {
+        let mut stream = VertexStream::<[f32; 2], ()>::default();
+        stream.triangle([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        let token = stream.token();
+        stream.quad([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+        let triangles = stream.triangles().to_vec();
+        stream.transform_range(token, |vertex| vertex[0] += 10.0);
+        assert_eq!(stream.vertices()[..3], [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]]);
+        assert_eq!(
+            stream.vertices()[3..],
+            [[10.0, 0.0], [11.0, 0.0], [11.0, 1.0], [10.0, 1.0]]
+        );
+        assert_eq!(stream.triangles(), triangles.as_slice());
+    }
+}
@@ -0,0 +1,63 @@
+//! Lives in its own test binary, because counting allocations needs custom
+//! global allocator, which would affect every other test of the crate.
+
+use spitfire_core::VertexStreamPool;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|count| count.get())
+}
+
+#[test]
+fn test_vertex_stream_pool() {
+    let mut pool = VertexStreamPool::<[f32; 2], ()>::new(16);
+    let frame = |pool: &mut VertexStreamPool<[f32; 2], ()>| {
+        let mut streams = (0..8).map(|_| pool.acquire()).collect::<Vec<_>>();
+        for stream in &mut streams {
+            stream.batch(());
+            for index in 0..10 {
+                let value = index as f32;
+                stream.quad([[value, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, value]]);
+            }
+        }
+        for stream in streams.drain(..) {
+            pool.release(stream);
+        }
+    };
+
+    frame(&mut pool);
+    frame(&mut pool);
+    let warmup = allocations();
+    frame(&mut pool);
+    let warm = allocations();
+    frame(&mut pool);
+    frame(&mut pool);
+    let steady = allocations();
+    assert_eq!(pool.len(), 8);
+    // Only collecting streams into temporary vector allocates after warmup.
+    assert!(warm - warmup <= 1);
+    assert!(steady - warm <= 2);
+}
@@ -1,14 +1,27 @@
 use crate::{
     context::DrawContext,
-    sprite::SpriteTexture,
-    utils::{TextureRef, Vertex},
+    sprite::{Sprite, SpriteTexture},
+    utils::{ShaderRef, TextureRef, Vertex},
 };
 use spitfire_glow::{
-    graphics::{Graphics, Surface},
+    error::GlowError,
+    graphics::{Graphics, PrepareFrameOptions, Surface},
     renderer::{GlowTextureFiltering, GlowTextureFormat},
 };
 use std::borrow::Cow;
+use vek::Rect;
 
+/// Render target that frames get drawn into, to be composited later.
+///
+/// Drawing into canvas is bracketed by [`Self::activate`] and
+/// [`Self::deactivate`] (or done within [`Self::with`]), which flush what
+/// was drawn so far and restart [`DrawContext`] frame for new target.
+/// Canvases created with [`Self::from_screen`] should be kept in sync with
+/// window size by calling [`Self::match_to_screen`] every frame.
+///
+/// Multi-pass effects like bloom or blur chain canvases: each pass draws
+/// [`Self::composite_sprite`] of previous canvas with pass shader into the
+/// next one, and last one gets composited on screen.
 pub struct Canvas {
     surface: Surface,
 }
@@ -19,7 +32,7 @@ impl Canvas {
         height: u32,
         format: GlowTextureFormat,
         graphics: &Graphics<Vertex>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, GlowError> {
         Ok(Self {
             surface: graphics.surface(vec![graphics
                 .texture(width, height, 1, format, None)?
@@ -27,6 +40,19 @@ impl Canvas {
         })
     }
 
+    /// Creates single channel canvas for occlusion/shadow masks.
+    ///
+    /// Mask uses `GlowTextureFormat::Monochromatic` texture (`R8` on desktop),
+    /// so only red channel is stored and it gets cleared to zero. Draw into it
+    /// with `Shader::COVERAGE_FRAGMENT` to write coverage and later sample red
+    /// channel of its texture as occlusion factor.
+    pub fn mask(width: u32, height: u32, graphics: &Graphics<Vertex>) -> Result<Self, GlowError> {
+        Ok(
+            Self::simple(width, height, GlowTextureFormat::Monochromatic, graphics)?
+                .color([0.0, 0.0, 0.0, 0.0]),
+        )
+    }
+
     pub fn from_surface(surface: Surface) -> Self {
         Self { surface }
     }
@@ -34,7 +60,7 @@ impl Canvas {
     pub fn from_screen(
         texture_formats: Vec<GlowTextureFormat>,
         graphics: &Graphics<Vertex>,
-    ) -> Result<Self, String> {
+    ) -> Result<Self, GlowError> {
         let width = graphics.main_camera.screen_size.x as _;
         let height = graphics.main_camera.screen_size.y as _;
         Ok(Self {
@@ -57,7 +83,7 @@ impl Canvas {
         self
     }
 
-    pub fn match_to_screen(&mut self, graphics: &Graphics<Vertex>) -> Result<(), String> {
+    pub fn match_to_screen(&mut self, graphics: &Graphics<Vertex>) -> Result<(), GlowError> {
         let width = graphics.main_camera.screen_size.x as _;
         let height = graphics.main_camera.screen_size.y as _;
         if self.surface.width() != width || self.surface.height() != height {
@@ -83,15 +109,28 @@ impl Canvas {
         graphics: &mut Graphics<Vertex>,
         clear: bool,
     ) {
-        context.end_frame();
+        self.activate_with(
+            context,
+            graphics,
+            PrepareFrameOptions::default().clear(clear),
+        );
+    }
+
+    pub fn activate_with(
+        &self,
+        context: &mut DrawContext,
+        graphics: &mut Graphics<Vertex>,
+        options: PrepareFrameOptions,
+    ) {
+        let _ = context.end_frame();
         let _ = graphics.draw();
         let _ = graphics.push_surface(self.surface.clone());
-        let _ = graphics.prepare_frame(clear);
+        let _ = graphics.prepare_frame_with(options);
         context.begin_frame(graphics);
     }
 
     pub fn deactivate(context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
-        context.end_frame();
+        let _ = context.end_frame();
         let _ = graphics.draw();
         let _ = graphics.pop_surface();
         let _ = graphics.prepare_frame(false);
@@ -131,4 +170,23 @@ impl Canvas {
             filtering,
         })
     }
+
+    /// Screen space sprite covering whole screen with attachment texture,
+    /// drawn with given shader. Canvas content is stored upside down, so
+    /// sprite region flips it back.
+    pub fn composite_sprite(
+        &self,
+        index: usize,
+        sampler: Cow<'static, str>,
+        shader: ShaderRef,
+        graphics: &Graphics<Vertex>,
+    ) -> Option<Sprite> {
+        Some(
+            Sprite::single(self.sprite_texture(index, sampler, GlowTextureFiltering::Linear)?)
+                .shader(shader)
+                .region_page(Rect::new(0.0, 1.0, 1.0, -1.0), 0.0)
+                .size(graphics.main_camera.screen_size)
+                .screen_space(true),
+        )
+    }
 }
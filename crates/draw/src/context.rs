@@ -1,24 +1,32 @@
-use crate::utils::{FontMap, ResourceRef, ShaderRef, TextureRef, Vertex};
+use crate::{
+    text::TextLayoutCache,
+    utils::{FontMap, ResourceRef, ShaderRef, TextureRef, Vertex},
+};
 use spitfire_fontdue::TextRenderer;
 use spitfire_glow::{
+    error::GlowError,
     graphics::{Graphics, Shader, Texture},
     renderer::{GlowBlending, GlowTextureFormat},
 };
 use std::{borrow::Cow, collections::HashMap};
-use vek::{Rgba, Transform};
+use vek::{Rect, Rgba, Transform};
 
 #[derive(Default, Clone)]
 pub struct DrawContext {
     pub shaders: HashMap<Cow<'static, str>, Shader>,
     pub textures: HashMap<Cow<'static, str>, Texture>,
     pub fonts: FontMap,
+    /// Shared by all text drawables within frame, so they accumulate glyphs
+    /// into single atlas that gets uploaded to fonts texture once at frame end.
     pub text_renderer: TextRenderer<Rgba<f32>>,
+    text_cache: TextLayoutCache,
     pass_shader: Option<Shader>,
     empty_texture: Option<Texture>,
     fonts_texture: Option<Texture>,
     shaders_stack: Vec<Shader>,
     transform_stack: Vec<Transform<f32, f32, f32>>,
     blending_stack: Vec<GlowBlending>,
+    scissor_stack: Vec<Rect<i32, i32>>,
 }
 
 impl DrawContext {
@@ -38,19 +46,73 @@ impl DrawContext {
         self.shaders_stack.clear();
         self.transform_stack.clear();
         self.blending_stack.clear();
+        self.scissor_stack.clear();
     }
 
-    pub fn end_frame(&mut self) {
+    pub fn end_frame(&mut self) -> Result<(), GlowError> {
+        let Some(fonts_texture) = self.fonts_texture.as_mut() else {
+            return Ok(());
+        };
+        // Upload only atlas parts that changed, unless its size changed.
         let [width, height, depth] = self.text_renderer.atlas_size();
-        if let Some(fonts_texture) = self.fonts_texture.as_mut() {
+        if fonts_texture.width() != width as u32
+            || fonts_texture.height() != height as u32
+            || fonts_texture.depth() != depth as u32
+        {
             fonts_texture.upload(
                 width as _,
                 height as _,
                 depth as _,
                 GlowTextureFormat::Monochromatic,
                 Some(self.text_renderer.image()),
-            );
+            )?;
+            while self.text_renderer.take_dirty_rect().is_some() {}
+        }
+        while let Some((page, rect)) = self.text_renderer.take_dirty_rect() {
+            fonts_texture.upload_region(
+                rect.min_x(),
+                rect.min_y(),
+                page as _,
+                rect.width(),
+                rect.height(),
+                GlowTextureFormat::Monochromatic,
+                &self.text_renderer.image_region(page, rect),
+            )?;
         }
+        Ok(())
+    }
+
+    /// Lists names of loaded shaders, textures and fonts, in that order.
+    pub fn resource_manifest(&self) -> Vec<String> {
+        let mut shaders = self
+            .shaders
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let mut textures = self
+            .textures
+            .keys()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        shaders.sort();
+        textures.sort();
+        shaders
+            .into_iter()
+            .chain(textures)
+            .chain(self.fonts.keys().iter().map(|name| name.to_string()))
+            .collect()
+    }
+
+    /// Drops shaders, textures and fonts not listed by name. GPU resources
+    /// get released once no other handles to them are alive.
+    pub fn retain_resources(&mut self, names: &[&str]) {
+        Self::retain_named(&mut self.shaders, names);
+        Self::retain_named(&mut self.textures, names);
+        self.fonts.retain(|name, _| names.contains(&name));
+    }
+
+    fn retain_named<T>(resources: &mut HashMap<Cow<'static, str>, T>, names: &[&str]) {
+        resources.retain(|name, _| names.contains(&name.as_ref()));
     }
 
     pub fn shader(&self, reference: Option<&ShaderRef>) -> Option<Shader> {
@@ -90,6 +152,28 @@ impl DrawContext {
         self.fonts_texture.clone()
     }
 
+    pub fn text_renderer(&self) -> &TextRenderer<Rgba<f32>> {
+        &self.text_renderer
+    }
+
+    pub fn text_renderer_mut(&mut self) -> &mut TextRenderer<Rgba<f32>> {
+        &mut self.text_renderer
+    }
+
+    pub fn text_cache(&self) -> &TextLayoutCache {
+        &self.text_cache
+    }
+
+    pub fn text_cache_mut(&mut self) -> &mut TextLayoutCache {
+        &mut self.text_cache
+    }
+
+    /// Cached text layouts already get dropped whenever [`Self::fonts`]
+    /// change, so this is only needed to free their memory.
+    pub fn clear_text_cache(&mut self) {
+        self.text_cache.clear();
+    }
+
     pub fn push_shader(&mut self, shader: &ShaderRef) {
         match shader {
             ResourceRef::Name(name) => {
@@ -159,4 +243,208 @@ impl DrawContext {
         self.pop_blending();
         result
     }
+
+    /// Pushes scissor rect in framebuffer coordinates (origin at bottom left),
+    /// intersected with current top one, so nested regions never exceed parent.
+    pub fn push_scissor(&mut self, rect: Rect<i32, i32>) {
+        let rect = match self.scissor_stack.last() {
+            Some(parent) => {
+                let left = rect.x.max(parent.x);
+                let bottom = rect.y.max(parent.y);
+                let right = (rect.x + rect.w).min(parent.x + parent.w);
+                let top = (rect.y + rect.h).min(parent.y + parent.h);
+                Rect::new(left, bottom, (right - left).max(0), (top - bottom).max(0))
+            }
+            None => rect,
+        };
+        self.scissor_stack.push(rect);
+    }
+
+    pub fn pop_scissor(&mut self) -> Option<Rect<i32, i32>> {
+        self.scissor_stack.pop()
+    }
+
+    pub fn top_scissor(&self) -> Option<Rect<i32, i32>> {
+        self.scissor_stack.last().copied()
+    }
+
+    pub fn with_scissor<R>(&mut self, rect: Rect<i32, i32>, mut f: impl FnMut() -> R) -> R {
+        self.push_scissor(rect);
+        let result = f();
+        self.pop_scissor();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DrawContext;
+    use fontdue::Font;
+    use std::{borrow::Cow, collections::HashMap};
+    use vek::Rect;
+
+    #[test]
+    fn test_retain_resources() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        for name in ["menu", "hud", "level1", "level2"] {
+            context.fonts.insert(name, font.clone());
+        }
+        assert_eq!(
+            context.resource_manifest(),
+            vec!["menu", "hud", "level1", "level2"]
+        );
+
+        context.retain_resources(&["hud", "level2", "missing"]);
+        assert_eq!(context.resource_manifest(), vec!["hud", "level2"]);
+        assert!(context.fonts.get("menu").is_none());
+        assert!(context.fonts.get("level1").is_none());
+        assert!(context.fonts.get("hud").is_some());
+    }
+
+    #[test]
+    fn test_retain_named() {
+        let mut resources = ["sprite", "unused", "hero"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (Cow::Borrowed(name), index))
+            .collect::<HashMap<_, _>>();
+        DrawContext::retain_named(&mut resources, &["hero", "sprite", "missing"]);
+        let mut retained = resources.into_iter().collect::<Vec<_>>();
+        retained.sort();
+        assert_eq!(
+            retained,
+            vec![(Cow::Borrowed("hero"), 2), (Cow::Borrowed("sprite"), 0)]
+        );
+        let mut resources = HashMap::from([(Cow::Borrowed("hero"), 0)]);
+        DrawContext::retain_named(&mut resources, &[]);
+        assert!(resources.is_empty());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_retain_gpu_resources() {
+        use crate::utils::Vertex;
+        use glow::HasContext;
+        use spitfire_glow::{
+            graphics::Shader, headless::HeadlessGraphics, renderer::GlowTextureFormat,
+        };
+
+        let headless = HeadlessGraphics::<Vertex>::new(1, 1).expect("GL context");
+        let graphics = &headless.graphics;
+        let mut context = DrawContext::default();
+        for name in ["sprite", "unused_shader"] {
+            let shader = graphics
+                .shader(Shader::TEXTURED_VERTEX_2D, Shader::TEXTURED_FRAGMENT)
+                .unwrap();
+            context.shaders.insert(name.into(), shader);
+        }
+        for name in ["hero", "unused_texture", "shared_texture"] {
+            let texture = graphics
+                .texture(1, 1, 1, GlowTextureFormat::Rgba, Some(&[255; 4]))
+                .unwrap();
+            context.textures.insert(name.into(), texture);
+        }
+        let hero = context.textures["hero"].handle();
+        let sprite = context.shaders["sprite"].handle();
+        let unused_texture = context.textures["unused_texture"].handle();
+        let unused_shader = context.shaders["unused_shader"].handle();
+        let shared_texture = context.textures["shared_texture"].clone();
+
+        context.retain_resources(&["sprite", "hero"]);
+        assert_eq!(context.resource_manifest(), vec!["sprite", "hero"]);
+        let gl = graphics.context().unwrap();
+        unsafe {
+            assert!(gl.is_texture(hero));
+            assert!(gl.is_program(sprite));
+            assert!(!gl.is_texture(unused_texture));
+            assert!(!gl.is_program(unused_shader));
+            assert!(gl.is_texture(shared_texture.handle()));
+        }
+    }
+
+    #[test]
+    fn test_text_shared_renderer() {
+        use crate::text::Text;
+
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("roboto", font);
+        let layouts = [
+            Text::default().font("roboto").text("Hello"),
+            Text::default().font("roboto").size(16.0).text("World"),
+        ]
+        .map(|text| text.cached_layout(&context).unwrap().0);
+        for layout in &layouts {
+            context
+                .text_renderer
+                .include(context.fonts.values(), layout);
+        }
+        assert_eq!(context.text_renderer.atlas_size()[2], 1);
+        for glyph in layouts.iter().flat_map(|layout| layout.glyphs()) {
+            assert!(context.text_renderer.glyph(&glyph.key).is_some());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_text_shared_atlas() {
+        use crate::{
+            text::Text,
+            utils::{Drawable, Vertex},
+        };
+        use spitfire_glow::headless::HeadlessGraphics;
+        use vek::Vec2;
+
+        let mut headless = HeadlessGraphics::<Vertex>::new(64, 64).expect("GL context");
+        let graphics = &mut headless.graphics;
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("roboto", font);
+
+        context.begin_frame(graphics);
+        let atlas = context.fonts_texture().unwrap();
+        Text::default()
+            .font("roboto")
+            .text("Hello")
+            .draw(&mut context, graphics);
+        Text::default()
+            .font("roboto")
+            .size(16.0)
+            .text("World")
+            .position(Vec2::new(0.0, 40.0))
+            .draw(&mut context, graphics);
+        let handles = graphics
+            .stream
+            .batches()
+            .iter()
+            .flat_map(|(batch, _)| batch.textures.iter().map(|(texture, _)| texture.handle()))
+            .collect::<Vec<_>>();
+        assert!(!handles.is_empty());
+        assert!(handles.iter().all(|handle| *handle == atlas.handle()));
+        context.end_frame().unwrap();
+        assert_eq!(context.fonts_texture().unwrap().handle(), atlas.handle());
+    }
+
+    #[test]
+    fn test_scissor_stack() {
+        let mut context = DrawContext::default();
+        assert_eq!(context.top_scissor(), None);
+        context.push_scissor(Rect::new(10, 10, 100, 50));
+        context.push_scissor(Rect::new(50, 0, 100, 30));
+        assert_eq!(context.top_scissor(), Some(Rect::new(50, 10, 60, 20)));
+        context.push_scissor(Rect::new(200, 200, 10, 10));
+        assert_eq!(context.top_scissor().unwrap().w, 0);
+        assert_eq!(context.top_scissor().unwrap().h, 0);
+        context.pop_scissor();
+        context.pop_scissor();
+        assert_eq!(context.top_scissor(), Some(Rect::new(10, 10, 100, 50)));
+        context.pop_scissor();
+        assert_eq!(context.top_scissor(), None);
+    }
 }
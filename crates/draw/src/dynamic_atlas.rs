@@ -0,0 +1,306 @@
+use crate::utils::Vertex;
+use etagere::{size2, AllocId, AtlasAllocator};
+use spitfire_glow::{
+    error::GlowError,
+    graphics::{Graphics, Texture},
+    renderer::GlowTextureFormat,
+};
+use std::{collections::HashMap, hash::Hash};
+use vek::Rect;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicAtlasRegion {
+    /// Pixels area occupied within page.
+    pub rect: Rect<u32, u32>,
+    pub page: usize,
+    /// Normalized texture coordinates of `rect`.
+    pub uvs: Rect<f32, f32>,
+}
+
+/// Packs RGBA tiles generated at runtime into texture array pages, same way
+/// [`spitfire_fontdue::TextRenderer`] packs glyphs. When all allowed pages
+/// are full, least recently used tiles get evicted to make space, so regions
+/// should be queried with [`Self::get`] every frame instead of being cached.
+#[derive(Clone)]
+pub struct DynamicAtlas<K: Eq + Hash + Clone> {
+    pub max_pages: Option<usize>,
+    page_size: [usize; 2],
+    pages: Vec<AtlasAllocator>,
+    image: Vec<u8>,
+    allocations: HashMap<K, (DynamicAtlasRegion, AllocId, usize)>,
+    usage_counter: usize,
+    texture: Option<Texture>,
+    dirty: bool,
+}
+
+impl<K: Eq + Hash + Clone> Default for DynamicAtlas<K> {
+    fn default() -> Self {
+        Self::new(1024, 1024)
+    }
+}
+
+impl<K: Eq + Hash + Clone> DynamicAtlas<K> {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            max_pages: None,
+            page_size: [width, height],
+            pages: Default::default(),
+            image: Default::default(),
+            allocations: Default::default(),
+            usage_counter: 0,
+            texture: None,
+            dirty: false,
+        }
+    }
+
+    pub fn max_pages(mut self, value: Option<usize>) -> Self {
+        self.max_pages = value;
+        self
+    }
+
+    /// [width, height, pages]
+    pub fn atlas_size(&self) -> [usize; 3] {
+        [self.page_size[0], self.page_size[1], self.pages.len()]
+    }
+
+    /// RGBA pixels of all pages.
+    pub fn image(&self) -> &[u8] {
+        &self.image
+    }
+
+    pub fn texture(&self) -> Option<Texture> {
+        self.texture.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.allocations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.allocations.contains_key(key)
+    }
+
+    /// Returns region of tile and marks it as recently used.
+    pub fn get(&mut self, key: &K) -> Option<DynamicAtlasRegion> {
+        let (region, _, last_used) = self.allocations.get_mut(key)?;
+        self.usage_counter += 1;
+        *last_used = self.usage_counter;
+        Some(*region)
+    }
+
+    /// Packs RGBA tile under given key, replacing tile already stored there.
+    /// Returns `None` if tile doesn't fit into page or there is no space left
+    /// even after evicting other tiles.
+    pub fn insert(
+        &mut self,
+        key: K,
+        width: usize,
+        height: usize,
+        data: &[u8],
+    ) -> Option<DynamicAtlasRegion> {
+        if width == 0
+            || height == 0
+            || width > self.page_size[0]
+            || height > self.page_size[1]
+            || data.len() < width * height * 4
+        {
+            return None;
+        }
+        self.remove(&key);
+        let (page, id, x, y) = self.allocate(width, height)?;
+        let [page_width, page_height] = self.page_size;
+        for row in 0..height {
+            let start = ((page * page_height + y + row) * page_width + x) * 4;
+            self.image[start..(start + width * 4)]
+                .copy_from_slice(&data[(row * width * 4)..((row + 1) * width * 4)]);
+        }
+        let rect = Rect::new(x as u32, y as u32, width as u32, height as u32);
+        let region = DynamicAtlasRegion {
+            rect,
+            page,
+            uvs: Rect::new(
+                x as f32 / page_width as f32,
+                y as f32 / page_height as f32,
+                width as f32 / page_width as f32,
+                height as f32 / page_height as f32,
+            ),
+        };
+        self.usage_counter += 1;
+        self.allocations
+            .insert(key, (region, id, self.usage_counter));
+        self.dirty = true;
+        Some(region)
+    }
+
+    pub fn remove(&mut self, key: &K) -> bool {
+        if let Some((region, id, _)) = self.allocations.remove(key) {
+            self.free(region, id);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for page in &mut self.pages {
+            page.clear();
+        }
+        self.image.fill(0);
+        self.allocations.clear();
+        self.dirty = true;
+    }
+
+    /// Reads back RGBA pixels of region.
+    pub fn read(&self, region: DynamicAtlasRegion) -> Vec<u8> {
+        let [page_width, page_height] = self.page_size;
+        let x = region.rect.x as usize;
+        let width = region.rect.w as usize;
+        (region.rect.y as usize..(region.rect.y + region.rect.h) as usize)
+            .flat_map(|y| {
+                let start = ((region.page * page_height + y) * page_width + x) * 4;
+                self.image[start..(start + width * 4)].iter().copied()
+            })
+            .collect()
+    }
+
+    /// Uploads pages to texture if anything changed since last call.
+    pub fn maintain(&mut self, graphics: &Graphics<Vertex>) -> Result<(), GlowError> {
+        if !self.dirty && self.texture.is_some() {
+            return Ok(());
+        }
+        let [width, height, depth] = self.atlas_size();
+        let depth = depth.max(1);
+        self.image.resize(width * height * depth * 4, 0);
+        if let Some(texture) = self.texture.as_mut() {
+            texture.upload(
+                width as _,
+                height as _,
+                depth as _,
+                GlowTextureFormat::Rgba,
+                Some(&self.image),
+            )?;
+        } else {
+            self.texture = Some(graphics.texture(
+                width as _,
+                height as _,
+                depth as _,
+                GlowTextureFormat::Rgba,
+                Some(&self.image),
+            )?);
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn allocate(&mut self, width: usize, height: usize) -> Option<(usize, AllocId, usize, usize)> {
+        let size = size2(width as i32, height as i32);
+        let allocation = self
+            .pages
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, atlas)| Some((page, atlas.allocate(size)?)));
+        if let Some((page, allocation)) = allocation {
+            let origin = allocation.rectangle.min;
+            return Some((page, allocation.id, origin.x as _, origin.y as _));
+        }
+        let limit_reached = self
+            .max_pages
+            .map(|limit| self.pages.len() >= limit)
+            .unwrap_or_default();
+        if !limit_reached {
+            let [w, h] = self.page_size;
+            let mut atlas = AtlasAllocator::new(size2(w as _, h as _));
+            let allocation = atlas.allocate(size)?;
+            let page = self.pages.len();
+            self.pages.push(atlas);
+            self.image.resize(w * h * self.pages.len() * 4, 0);
+            let origin = allocation.rectangle.min;
+            return Some((page, allocation.id, origin.x as _, origin.y as _));
+        }
+        let mut candidates = self
+            .allocations
+            .iter()
+            .map(|(key, (region, id, last_used))| (*last_used, key.clone(), *region, *id))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(last_used, ..)| *last_used);
+        for (_, key, region, id) in candidates {
+            self.allocations.remove(&key);
+            self.free(region, id);
+            if let Some(allocation) = self.pages[region.page].allocate(size) {
+                let origin = allocation.rectangle.min;
+                return Some((region.page, allocation.id, origin.x as _, origin.y as _));
+            }
+        }
+        None
+    }
+
+    fn free(&mut self, region: DynamicAtlasRegion, id: AllocId) {
+        self.pages[region.page].deallocate(id);
+        let [page_width, page_height] = self.page_size;
+        for y in region.rect.y..(region.rect.y + region.rect.h) {
+            let start = ((region.page * page_height + y as usize) * page_width
+                + region.rect.x as usize)
+                * 4;
+            self.image[start..(start + region.rect.w as usize * 4)].fill(0);
+        }
+        self.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynamicAtlas;
+
+    fn tile(width: usize, height: usize, value: u8) -> Vec<u8> {
+        (0..(width * height))
+            .flat_map(|index| [value, index as u8, 0, 255])
+            .collect()
+    }
+
+    #[test]
+    fn test_dynamic_atlas() {
+        let mut atlas = DynamicAtlas::<u32>::new(64, 64);
+        let sizes = [(10, 20), (16, 16), (30, 8), (5, 5), (20, 30)];
+        let regions = sizes
+            .iter()
+            .enumerate()
+            .map(|(index, (w, h))| {
+                atlas
+                    .insert(index as u32, *w, *h, &tile(*w, *h, index as u8 + 1))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(atlas.atlas_size(), [64, 64, 1]);
+        for (index, a) in regions.iter().enumerate() {
+            assert_eq!(a.rect.w as usize, sizes[index].0);
+            assert_eq!(a.rect.h as usize, sizes[index].1);
+            for b in regions.iter().skip(index + 1) {
+                let overlaps = a.rect.x < b.rect.x + b.rect.w
+                    && b.rect.x < a.rect.x + a.rect.w
+                    && a.rect.y < b.rect.y + b.rect.h
+                    && b.rect.y < a.rect.y + a.rect.h;
+                assert!(a.page != b.page || !overlaps);
+            }
+        }
+        for (index, (w, h)) in sizes.iter().enumerate() {
+            let region = atlas.get(&(index as u32)).unwrap();
+            assert_eq!(atlas.read(region), tile(*w, *h, index as u8 + 1));
+        }
+
+        let mut atlas = DynamicAtlas::<u32>::new(32, 32).max_pages(Some(1));
+        atlas.insert(0, 32, 16, &tile(32, 16, 1)).unwrap();
+        atlas.insert(1, 32, 16, &tile(32, 16, 2)).unwrap();
+        atlas.get(&0);
+        let region = atlas.insert(2, 32, 16, &tile(32, 16, 3)).unwrap();
+        assert!(atlas.contains(&0));
+        assert!(!atlas.contains(&1));
+        assert_eq!(atlas.read(region), tile(32, 16, 3));
+        assert_eq!(atlas.atlas_size(), [32, 32, 1]);
+        assert!(atlas.insert(3, 64, 8, &tile(64, 8, 4)).is_none());
+        assert_eq!(atlas.len(), 2);
+    }
+}
@@ -1,8 +1,10 @@
 pub mod canvas;
 pub mod context;
+pub mod dynamic_atlas;
 pub mod nine_slice_sprite;
 pub mod particles;
 pub mod primitives;
+pub mod software;
 pub mod sprite;
 pub mod text;
 pub mod tiles;
@@ -10,7 +12,7 @@ pub mod utils;
 
 pub mod prelude {
     pub use crate::{
-        canvas::*, context::*, nine_slice_sprite::*, particles::*, primitives::*, sprite::*,
-        text::*, tiles::*, utils::*,
+        canvas::*, context::*, dynamic_atlas::*, nine_slice_sprite::*, particles::*, primitives::*,
+        software::*, sprite::*, text::*, tiles::*, utils::*,
     };
 }
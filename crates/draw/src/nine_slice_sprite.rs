@@ -4,7 +4,7 @@ use crate::{
     utils::{Drawable, ShaderRef, Vertex},
 };
 use smallvec::SmallVec;
-use spitfire_core::Triangle;
+use spitfire_core::{Triangle, VertexStream};
 use spitfire_glow::{
     graphics::{Graphics, GraphicsBatch},
     renderer::{GlowBlending, GlowUniformValue},
@@ -78,6 +78,9 @@ pub struct NineSliceSprite {
     pub margins_source: NineSliceMargins,
     pub margins_target: NineSliceMargins,
     pub frame_only: bool,
+    /// Repeats edges and center in tiles of their source size instead of
+    /// stretching them. Source size is measured in first texture pixels.
+    pub tiling: bool,
     pub tint: Rgba<f32>,
     pub transform: Transform<f32, f32, f32>,
     pub size: Option<Vec2<f32>>,
@@ -97,6 +100,7 @@ impl Default for NineSliceSprite {
             margins_source: Default::default(),
             margins_target: Default::default(),
             frame_only: false,
+            tiling: false,
             tint: Rgba::white(),
             transform: Default::default(),
             size: Default::default(),
@@ -151,6 +155,11 @@ impl NineSliceSprite {
         self
     }
 
+    pub fn tiling(mut self, value: bool) -> Self {
+        self.tiling = value;
+        self
+    }
+
     pub fn tint(mut self, value: Rgba<f32>) -> Self {
         self.tint = value;
         self
@@ -202,6 +211,91 @@ impl NineSliceSprite {
     }
 }
 
+impl NineSliceSprite {
+    /// Splits `[position near, position far]` range into spans of
+    /// `(position from, position to, uv from, uv to)`, each covering
+    /// `[uv near, uv far]` range for `tile` long positions, with last span
+    /// cropped.
+    fn tile_spans(
+        [position_near, position_far]: [f32; 2],
+        [uv_near, uv_far]: [f32; 2],
+        tile: f32,
+    ) -> Vec<[f32; 4]> {
+        let length = position_far - position_near;
+        if tile <= 1.0e-3 || length <= 0.0 {
+            return vec![[position_near, position_far, uv_near, uv_far]];
+        }
+        let count = (length / tile - 1.0e-4).ceil().max(1.0) as usize;
+        (0..count)
+            .map(|index| {
+                let from = position_near + tile * index as f32;
+                let to = (from + tile).min(position_far);
+                let factor = (to - from) / tile;
+                [from, to, uv_near, uv_near + (uv_far - uv_near) * factor]
+            })
+            .collect()
+    }
+
+    /// Builds tiled geometry as grid of quads in local space.
+    #[allow(clippy::too_many_arguments)]
+    fn stream_tiled(
+        &self,
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        columns: [f32; 4],
+        rows: [f32; 4],
+        uv_columns: [f32; 4],
+        uv_rows: [f32; 4],
+        tile: Vec2<f32>,
+        color: [f32; 4],
+    ) {
+        let spans = |positions: [f32; 4], uvs: [f32; 4], tile: f32| {
+            [
+                vec![[positions[0], positions[1], uvs[0], uvs[1]]],
+                Self::tile_spans([positions[1], positions[2]], [uvs[1], uvs[2]], tile),
+                vec![[positions[2], positions[3], uvs[2], uvs[3]]],
+            ]
+        };
+        let columns = spans(columns, uv_columns, tile.x);
+        let rows = spans(rows, uv_rows, tile.y);
+        for (row_index, rows) in rows.iter().enumerate() {
+            for (column_index, columns) in columns.iter().enumerate() {
+                if self.frame_only && row_index == 1 && column_index == 1 {
+                    continue;
+                }
+                for [top, bottom, uv_top, uv_bottom] in rows {
+                    for [left, right, uv_left, uv_right] in columns {
+                        if left == right || top == bottom {
+                            continue;
+                        }
+                        stream.quad([
+                            Vertex {
+                                position: [*left, *top],
+                                uv: [*uv_left, *uv_top, self.page],
+                                color,
+                            },
+                            Vertex {
+                                position: [*right, *top],
+                                uv: [*uv_right, *uv_top, self.page],
+                                color,
+                            },
+                            Vertex {
+                                position: [*right, *bottom],
+                                uv: [*uv_right, *uv_bottom, self.page],
+                                color,
+                            },
+                            Vertex {
+                                position: [*left, *bottom],
+                                uv: [*uv_left, *uv_bottom, self.page],
+                                color,
+                            },
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl Drawable for NineSliceSprite {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
         let batch = GraphicsBatch {
@@ -233,7 +327,11 @@ impl Drawable for NineSliceSprite {
                 })
                 .collect(),
             blending: self.blending.unwrap_or_else(|| context.top_blending()),
-            scissor: None,
+            scissor: context.top_scissor(),
+            depth_test: None,
+            culling: None,
+            topology: Default::default(),
+            instances: None,
         };
         let transform = Mat4::from(context.top_transform()) * Mat4::from(self.transform);
         let size = self
@@ -265,7 +363,35 @@ impl Drawable for NineSliceSprite {
         let ttc = self.region.y + self.region.h * margins_source.top;
         let tbc = self.region.y + (1.0 - margins_source.bottom) * self.region.h;
         let tbf = self.region.y + self.region.h;
+        let tile = batch.textures.first().and_then(|(texture, _)| {
+            let tile = Vec2::new(
+                (trc - tlc) * texture.width() as f32,
+                (tbc - ttc) * texture.height() as f32,
+            );
+            (self.tiling && tile.x > 0.0 && tile.y > 0.0).then_some(tile)
+        });
         graphics.stream.batch_optimized(batch);
+        if let Some(tile) = tile {
+            graphics.stream.transformed(
+                |stream| {
+                    self.stream_tiled(
+                        stream,
+                        [plf, plc, prc, prf],
+                        [ptf, ptc, pbc, pbf],
+                        [tlf, tlc, trc, trf],
+                        [ttf, ttc, tbc, tbf],
+                        tile,
+                        color,
+                    );
+                },
+                |vertex| {
+                    let point = transform.mul_point(Vec2::from(vertex.position) - offset);
+                    vertex.position[0] = point.x;
+                    vertex.position[1] = point.y;
+                },
+            );
+            return;
+        }
         graphics.stream.transformed(
             |stream| unsafe {
                 stream.extend_triangles(
@@ -402,3 +528,64 @@ impl Drawable for NineSliceSprite {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiling() {
+        let spans = NineSliceSprite::tile_spans([10.0, 40.0], [0.25, 0.75], 10.0);
+        assert_eq!(
+            spans,
+            vec![
+                [10.0, 20.0, 0.25, 0.75],
+                [20.0, 30.0, 0.25, 0.75],
+                [30.0, 40.0, 0.25, 0.75],
+            ]
+        );
+        let spans = NineSliceSprite::tile_spans([10.0, 35.0], [0.25, 0.75], 10.0);
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[2], [30.0, 35.0, 0.25, 0.5]);
+        let spans = NineSliceSprite::tile_spans([10.0, 10.0], [0.25, 0.75], 10.0);
+        assert_eq!(spans, vec![[10.0, 10.0, 0.25, 0.75]]);
+
+        // Source is 4x4 pixels of 1 pixel margins, target is 8x6 pixels
+        // with the same margins, so middle repeats 3x2 times.
+        let sprite = NineSliceSprite::default().tiling(true);
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        sprite.stream_tiled(
+            &mut stream,
+            [0.0, 1.0, 7.0, 8.0],
+            [0.0, 1.0, 5.0, 6.0],
+            [0.0, 0.25, 0.75, 1.0],
+            [0.0, 0.25, 0.75, 1.0],
+            Vec2::new(2.0, 2.0),
+            [1.0; 4],
+        );
+        assert_eq!(stream.vertices().len(), 5 * 4 * 4);
+        let center = stream
+            .vertices()
+            .chunks(4)
+            .filter(|quad| quad[0].uv[0] == 0.25 && quad[0].uv[1] == 0.25)
+            .count();
+        assert_eq!(center, 6);
+        assert!(stream
+            .vertices()
+            .iter()
+            .all(|vertex| [0.0, 0.25, 0.75, 1.0].contains(&vertex.uv[0])));
+
+        let sprite = sprite.frame_only(true);
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        sprite.stream_tiled(
+            &mut stream,
+            [0.0, 1.0, 7.0, 8.0],
+            [0.0, 1.0, 5.0, 6.0],
+            [0.0, 0.25, 0.75, 1.0],
+            [0.0, 0.25, 0.75, 1.0],
+            Vec2::new(2.0, 2.0),
+            [1.0; 4],
+        );
+        assert_eq!(stream.vertices().len(), (5 * 4 - 6) * 4);
+    }
+}
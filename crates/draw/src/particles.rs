@@ -1,14 +1,20 @@
 use crate::{
     context::DrawContext,
     sprite::SpriteTexture,
-    utils::{Drawable, ShaderRef, Vertex},
+    utils::{Drawable, ResourceRef, ShaderRef, Vertex},
 };
+use bytemuck::{Pod, Zeroable};
 use smallvec::SmallVec;
+use spitfire_core::VertexStream;
 use spitfire_glow::{
     graphics::{Graphics, GraphicsBatch},
-    renderer::{GlowBlending, GlowUniformValue},
+    renderer::{
+        GlowBlending, GlowInstances, GlowUniformValue, GlowVertexAttrib, GlowVertexAttribs,
+    },
+};
+use std::{
+    borrow::Cow, cell::RefCell, collections::HashMap, iter::Map, marker::PhantomData, slice::Iter,
 };
-use std::{borrow::Cow, cell::RefCell, collections::HashMap, marker::PhantomData};
 use vek::{Mat4, Quaternion, Rect, Rgba, Transform, Vec2, Vec3};
 
 #[derive(Debug, Default, Clone)]
@@ -18,6 +24,10 @@ pub struct ParticleEmitter {
     pub uniforms: HashMap<Cow<'static, str>, GlowUniformValue>,
     pub blending: Option<GlowBlending>,
     pub screen_space: bool,
+    /// Shader used to draw all particles as single instanced batch, when
+    /// graphics supports instancing - see
+    /// [`spitfire_glow::graphics::Shader::TEXTURED_INSTANCED_VERTEX_2D`].
+    pub instanced_shader: Option<ShaderRef>,
 }
 
 impl ParticleEmitter {
@@ -53,12 +63,25 @@ impl ParticleEmitter {
         self
     }
 
+    pub fn instanced(mut self, shader: ShaderRef) -> Self {
+        self.instanced_shader = Some(shader);
+        self
+    }
+
     pub fn emit<I: IntoIterator<Item = ParticleInstance>>(&self, instances: I) -> ParticleDraw<I> {
         ParticleDraw {
             emitter: self,
             instances: RefCell::new(Some(instances)),
         }
     }
+
+    pub fn emit_from<'a, T, F: Fn(&T) -> ParticleInstance>(
+        &'a self,
+        items: &'a [T],
+        map: F,
+    ) -> ParticleDraw<'a, Map<Iter<'a, T>, F>> {
+        self.emit(items.iter().map(map))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -132,6 +155,109 @@ impl ParticleInstance {
     }
 }
 
+/// Per instance attributes of instanced particles batch.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ParticleInstanceData {
+    /// First row of 2D affine transform of unit quad.
+    pub transform_x: [f32; 3],
+    /// Second row of 2D affine transform of unit quad.
+    pub transform_y: [f32; 3],
+    pub region: [f32; 4],
+    pub tint: [f32; 4],
+    pub page: f32,
+}
+
+impl ParticleInstanceData {
+    pub fn new(instance: &ParticleInstance, parent: Mat4<f32>) -> Self {
+        let offset = instance.size * instance.pivot;
+        let matrix = parent
+            * Mat4::from(instance.transform)
+            * Mat4::translation_2d(-offset)
+            * Mat4::scaling_3d(Vec3::new(instance.size.x, instance.size.y, 1.0));
+        let cols = matrix.cols;
+        Self {
+            transform_x: [cols.x.x, cols.y.x, cols.w.x],
+            transform_y: [cols.x.y, cols.y.y, cols.w.y],
+            region: [
+                instance.region.x,
+                instance.region.y,
+                instance.region.w,
+                instance.region.h,
+            ],
+            tint: instance.tint.into_array(),
+            page: instance.page,
+        }
+    }
+}
+
+impl GlowVertexAttribs for ParticleInstanceData {
+    const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)] = &[
+        (
+            "i_transform_x",
+            GlowVertexAttrib::Float {
+                channels: 3,
+                normalized: false,
+            },
+        ),
+        (
+            "i_transform_y",
+            GlowVertexAttrib::Float {
+                channels: 3,
+                normalized: false,
+            },
+        ),
+        (
+            "i_region",
+            GlowVertexAttrib::Float {
+                channels: 4,
+                normalized: false,
+            },
+        ),
+        (
+            "i_tint",
+            GlowVertexAttrib::Float {
+                channels: 4,
+                normalized: false,
+            },
+        ),
+        (
+            "i_page",
+            GlowVertexAttrib::Float {
+                channels: 1,
+                normalized: false,
+            },
+        ),
+    ];
+}
+
+/// Streams unit quad that instanced shaders transform per instance.
+pub(crate) fn stream_unit_quad(stream: &mut VertexStream<Vertex, GraphicsBatch>) {
+    let color = [1.0, 1.0, 1.0, 1.0];
+    stream.quad([
+        Vertex {
+            position: [0.0, 0.0],
+            uv: [0.0, 0.0, 0.0],
+            color,
+        },
+        Vertex {
+            position: [1.0, 0.0],
+            uv: [1.0, 0.0, 0.0],
+            color,
+        },
+        Vertex {
+            position: [1.0, 1.0],
+            uv: [1.0, 1.0, 0.0],
+            color,
+        },
+        Vertex {
+            position: [0.0, 1.0],
+            uv: [0.0, 1.0, 0.0],
+            color,
+        },
+    ]);
+}
+
 pub struct ParticleDraw<'a, I: IntoIterator<Item = ParticleInstance>> {
     emitter: &'a ParticleEmitter,
     instances: RefCell<Option<I>>,
@@ -143,7 +269,16 @@ impl<I: IntoIterator<Item = ParticleInstance>> Drawable for ParticleDraw<'_, I>
             Some(instances) => instances,
             None => return,
         };
-        let batch = GraphicsBatch {
+        let instanced_shader = self
+            .emitter
+            .instanced_shader
+            .as_ref()
+            .filter(|_| graphics.supports_instancing())
+            .and_then(|shader| match shader {
+                ResourceRef::Name(name) => context.shaders.get(name).cloned(),
+                ResourceRef::Object(object) => Some(object.to_owned()),
+            });
+        let mut batch = GraphicsBatch {
             shader: context.shader(self.emitter.shader.as_ref()),
             uniforms: self
                 .emitter
@@ -183,15 +318,48 @@ impl<I: IntoIterator<Item = ParticleInstance>> Drawable for ParticleDraw<'_, I>
                 .emitter
                 .blending
                 .unwrap_or_else(|| context.top_blending()),
-            scissor: None,
+            scissor: context.top_scissor(),
+            depth_test: None,
+            culling: None,
+            topology: Default::default(),
+            instances: None,
         };
+        if let Some(shader) = instanced_shader {
+            let parent = Mat4::from(context.top_transform());
+            let data = instances
+                .into_iter()
+                .map(|instance| ParticleInstanceData::new(&instance, parent))
+                .collect::<Vec<_>>();
+            if data.is_empty() {
+                return;
+            }
+            batch.shader = Some(shader);
+            batch.instances = Some(GlowInstances::new(&data));
+            graphics.stream.batch(batch);
+            stream_unit_quad(&mut graphics.stream);
+            graphics.stream.batch_end();
+            return;
+        }
         graphics.stream.batch_optimized(batch);
-        let parent = Mat4::from(context.top_transform());
+        Self::stream_instances(
+            &mut graphics.stream,
+            Mat4::from(context.top_transform()),
+            instances,
+        );
+    }
+}
+
+impl<I: IntoIterator<Item = ParticleInstance>> ParticleDraw<'_, I> {
+    fn stream_instances(
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        parent: Mat4<f32>,
+        instances: I,
+    ) {
         for instance in instances {
             let transform = parent * Mat4::from(instance.transform);
             let offset = instance.size * instance.pivot;
             let color = instance.tint.into_array();
-            graphics.stream.transformed(
+            stream.transformed(
                 |stream| {
                     stream.quad([
                         Vertex {
@@ -238,6 +406,74 @@ impl<I: IntoIterator<Item = ParticleInstance>> Drawable for ParticleDraw<'_, I>
     }
 }
 
+/// Radial force applied to particles within its radius. Positive strength
+/// attracts particles, negative one repels them.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ParticleForceField {
+    pub position: Vec2<f32>,
+    pub strength: f32,
+    pub radius: f32,
+}
+
+impl ParticleForceField {
+    pub fn new(position: Vec2<f32>, strength: f32, radius: f32) -> Self {
+        Self {
+            position,
+            strength,
+            radius,
+        }
+    }
+
+    /// Acceleration at given position, falling off linearly to zero at radius.
+    pub fn acceleration(&self, position: Vec2<f32>) -> Vec2<f32> {
+        let direction = self.position - position;
+        let distance = direction.magnitude();
+        if distance <= f32::EPSILON || distance >= self.radius {
+            return Vec2::zero();
+        }
+        direction / distance * self.strength * (1.0 - distance / self.radius)
+    }
+}
+
+/// Common forces to call from [`ParticleSystemProcessor::process`], usually
+/// stored as (part of) particle system config.
+#[derive(Debug, Default, Clone)]
+pub struct ParticleForces {
+    pub gravity: Vec2<f32>,
+    /// Fraction of velocity lost per second.
+    pub drag: f32,
+    pub fields: Vec<ParticleForceField>,
+}
+
+impl ParticleForces {
+    pub fn gravity(mut self, value: Vec2<f32>) -> Self {
+        self.gravity = value;
+        self
+    }
+
+    pub fn drag(mut self, value: f32) -> Self {
+        self.drag = value;
+        self
+    }
+
+    pub fn field(mut self, value: ParticleForceField) -> Self {
+        self.fields.push(value);
+        self
+    }
+
+    pub fn acceleration(&self, position: Vec2<f32>) -> Vec2<f32> {
+        self.fields.iter().fold(self.gravity, |result, field| {
+            result + field.acceleration(position)
+        })
+    }
+
+    /// Returns velocity after applying forces over `delta_time`.
+    pub fn apply(&self, position: Vec2<f32>, velocity: Vec2<f32>, delta_time: f32) -> Vec2<f32> {
+        let velocity = velocity * (1.0 - self.drag * delta_time).max(0.0);
+        velocity + self.acceleration(position) * delta_time
+    }
+}
+
 pub trait ParticleSystemProcessor<D, C> {
     fn process(config: &C, data: D) -> Option<D>;
     fn emit(config: &C, data: &D) -> Option<ParticleInstance>;
@@ -274,6 +510,14 @@ impl<P: ParticleSystemProcessor<D, C>, D, C> ParticleSystem<P, D, C> {
         }
     }
 
+    /// Pushes up to `count` particles made by `factory`, limited by remaining
+    /// capacity. Returns number of particles actually added.
+    pub fn push_burst(&mut self, count: usize, mut factory: impl FnMut(usize) -> D) -> usize {
+        let count = count.min(self.source.capacity() - self.source.len());
+        self.source.extend((0..count).map(&mut factory));
+        count
+    }
+
     pub fn extend(&mut self, iter: impl IntoIterator<Item = D>) {
         self.source.extend(iter);
     }
@@ -311,3 +555,141 @@ impl<P: ParticleSystemProcessor<D, C>, D: std::fmt::Debug, C: std::fmt::Debug> s
             .finish_non_exhaustive()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        particles::{
+            ParticleDraw, ParticleEmitter, ParticleForceField, ParticleForces, ParticleInstance,
+            ParticleInstanceData, ParticleSystem, ParticleSystemProcessor,
+        },
+        utils::Vertex,
+    };
+    use spitfire_core::VertexStream;
+    use spitfire_glow::graphics::GraphicsBatch;
+    use vek::{Mat4, Vec2};
+
+    struct Body {
+        position: Vec2<f32>,
+        radius: f32,
+    }
+
+    #[test]
+    fn test_emit_from() {
+        let bodies = [
+            Body {
+                position: Vec2::new(0.0, 0.0),
+                radius: 1.0,
+            },
+            Body {
+                position: Vec2::new(10.0, 0.0),
+                radius: 2.0,
+            },
+            Body {
+                position: Vec2::new(0.0, 10.0),
+                radius: 3.0,
+            },
+        ];
+        let emitter = ParticleEmitter::default();
+        let draw = emitter.emit_from(&bodies, |body| {
+            ParticleInstance::default()
+                .position(body.position)
+                .size(Vec2::broadcast(body.radius * 2.0))
+                .pivot(0.5.into())
+        });
+        let instances = draw.instances.borrow_mut().take().unwrap();
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        ParticleDraw::stream_instances(&mut stream, Mat4::identity(), instances);
+
+        assert_eq!(stream.vertices().len(), bodies.len() * 4);
+        assert_eq!(stream.triangles().len(), bodies.len() * 2);
+        assert_eq!(stream.vertices()[4].position, [8.0, -2.0]);
+    }
+
+    #[test]
+    fn test_forces() {
+        let forces = ParticleForces::default().gravity(Vec2::new(0.0, 10.0));
+        let velocity = forces.apply(Vec2::zero(), Vec2::new(1.0, 0.0), 0.5);
+        assert_eq!(velocity, Vec2::new(1.0, 5.0));
+
+        let forces = ParticleForces::default().drag(0.5);
+        let velocity = forces.apply(Vec2::zero(), Vec2::new(4.0, 0.0), 1.0);
+        assert_eq!(velocity, Vec2::new(2.0, 0.0));
+        let velocity = forces.apply(Vec2::zero(), Vec2::new(4.0, 0.0), 10.0);
+        assert_eq!(velocity, Vec2::zero());
+
+        let attractor = ParticleForceField::new(Vec2::new(10.0, 0.0), 4.0, 20.0);
+        let repulsor = ParticleForceField::new(Vec2::new(-10.0, 0.0), -4.0, 5.0);
+        assert_eq!(attractor.acceleration(Vec2::zero()), Vec2::new(2.0, 0.0));
+        assert_eq!(attractor.acceleration(Vec2::new(10.0, 0.0)), Vec2::zero());
+        assert_eq!(attractor.acceleration(Vec2::new(40.0, 0.0)), Vec2::zero());
+        assert_eq!(
+            repulsor.acceleration(Vec2::new(-9.0, 0.0)),
+            Vec2::new(3.2, 0.0)
+        );
+        assert_eq!(repulsor.acceleration(Vec2::zero()), Vec2::zero());
+        let forces = ParticleForces::default()
+            .gravity(Vec2::new(0.0, 1.0))
+            .field(attractor)
+            .field(repulsor);
+        assert_eq!(forces.acceleration(Vec2::zero()), Vec2::new(2.0, 1.0));
+    }
+
+    struct CounterProcessor;
+
+    impl ParticleSystemProcessor<usize, ()> for CounterProcessor {
+        fn process(_: &(), data: usize) -> Option<usize> {
+            Some(data)
+        }
+
+        fn emit(_: &(), _: &usize) -> Option<ParticleInstance> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_push_burst() {
+        let mut system = ParticleSystem::<CounterProcessor, usize, ()>::new((), 10);
+        let capacity = system.source.capacity();
+        assert_eq!(system.push_burst(4, |index| index), 4);
+        assert_eq!(system.source, vec![0, 1, 2, 3]);
+        assert_eq!(
+            system.push_burst(capacity, |index| index * 10),
+            capacity - 4
+        );
+        assert_eq!(system.len(), capacity);
+        assert_eq!(system.source[4..7], [0, 10, 20]);
+        assert_eq!(system.push_burst(1, |index| index), 0);
+        assert_eq!(system.source.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_instance_data() {
+        let instance = ParticleInstance::default()
+            .position(Vec2::new(10.0, 20.0))
+            .size(Vec2::new(4.0, 2.0))
+            .pivot(Vec2::new(0.5, 0.5))
+            .scale(Vec2::new(2.0, 2.0));
+        let data = ParticleInstanceData::new(&instance, Mat4::identity());
+        assert_eq!(data.transform_x, [8.0, 0.0, 6.0]);
+        assert_eq!(data.transform_y, [0.0, 4.0, 18.0]);
+        assert_eq!(data.region, [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(data.tint, [1.0, 1.0, 1.0, 1.0]);
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        ParticleDraw::stream_instances(&mut stream, Mat4::identity(), [instance]);
+        for (vertex, unit) in
+            stream
+                .vertices()
+                .iter()
+                .zip([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]])
+        {
+            let point = [unit[0], unit[1], 1.0];
+            let dot = |row: [f32; 3]| row.iter().zip(point).map(|(a, b)| a * b).sum::<f32>();
+            assert_eq!(
+                vertex.position,
+                [dot(data.transform_x), dot(data.transform_y)]
+            );
+        }
+    }
+}
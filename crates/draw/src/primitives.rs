@@ -15,7 +15,7 @@ use std::{
     collections::HashMap,
     f32::consts::{PI, TAU},
 };
-use vek::{Mat4, Rect, Rgba, Vec2};
+use vek::{CubicBezier2, Mat4, QuadraticBezier2, Rect, Rgba, Vec2};
 
 #[derive(Debug, Default, Clone)]
 pub struct PrimitivesEmitter {
@@ -73,9 +73,62 @@ impl PrimitivesEmitter {
             tint: Rgba::white(),
             thickness: 1.0,
             looped: false,
+            joint: Default::default(),
+            miter_limit: 4.0,
         }
     }
 
+    /// Tessellates cubic Bezier curve of `[start, control, control, end]`
+    /// points into line strip of given number of segments.
+    pub fn emit_bezier(
+        &self,
+        points: [Vec2<f32>; 4],
+        segments: usize,
+    ) -> LinesDraw<Vec<Vec2<f32>>> {
+        let [start, ctrl0, ctrl1, end] = points;
+        let curve = CubicBezier2 {
+            start,
+            ctrl0,
+            ctrl1,
+            end,
+        };
+        self.emit_lines(Self::tessellate(segments, |t| curve.evaluate(t)))
+    }
+
+    /// Tessellates quadratic Bezier curve of `[start, control, end]` points
+    /// into line strip of given number of segments.
+    pub fn emit_quadratic_bezier(
+        &self,
+        points: [Vec2<f32>; 3],
+        segments: usize,
+    ) -> LinesDraw<Vec<Vec2<f32>>> {
+        let [start, ctrl, end] = points;
+        let curve = QuadraticBezier2 { start, ctrl, end };
+        self.emit_lines(Self::tessellate(segments, |t| curve.evaluate(t)))
+    }
+
+    /// Picks segments count of Bezier curve, so that line strip does not
+    /// deviate from curve by more than `tolerance`. Flat curves get single
+    /// segment, sharply bent ones get more.
+    pub fn bezier_segments(points: &[Vec2<f32>], tolerance: f32) -> usize {
+        let degree = points.len().saturating_sub(1) as f32;
+        let bend = points
+            .windows(3)
+            .map(|points| (points[0] - points[1] * 2.0 + points[2]).magnitude())
+            .fold(0.0, f32::max);
+        let segments = (degree * (degree - 1.0) * bend / (8.0 * tolerance.max(1.0e-4)))
+            .sqrt()
+            .ceil();
+        (segments as usize).max(1)
+    }
+
+    fn tessellate(segments: usize, f: impl Fn(f32) -> Vec2<f32>) -> Vec<Vec2<f32>> {
+        let segments = segments.max(1);
+        (0..=segments)
+            .map(|index| f(index as f32 / segments as f32))
+            .collect()
+    }
+
     pub fn emit_brush<I: IntoIterator<Item = (Vec2<f32>, f32, Rgba<f32>)>>(
         &self,
         vertices: I,
@@ -168,6 +221,59 @@ impl PrimitivesEmitter {
         }
     }
 
+    /// Filled rectangle with corners rounded by `radius`, which gets clamped
+    /// to half of the shorter rectangle side.
+    pub fn emit_rounded_rect(
+        &self,
+        rect: Rect<f32, f32>,
+        radius: f32,
+        corner_segments: usize,
+    ) -> RoundedRectDraw {
+        RoundedRectDraw {
+            emitter: self,
+            rect,
+            radius,
+            corner_segments,
+            region: Rect {
+                x: 0.0,
+                y: 0.0,
+                w: 1.0,
+                h: 1.0,
+            },
+            page: 0.0,
+            tint: Rgba::white(),
+        }
+    }
+
+    /// Filled circle slice between angles (in radians), or ring slice when
+    /// [`ArcDraw::inner_radius`] is set.
+    pub fn emit_arc(
+        &self,
+        position: Vec2<f32>,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        segments: usize,
+    ) -> ArcDraw {
+        ArcDraw {
+            emitter: self,
+            position,
+            radius,
+            inner_radius: 0.0,
+            start_angle,
+            end_angle,
+            segments,
+            region: Rect {
+                x: 0.0,
+                y: 0.0,
+                w: 1.0,
+                h: 1.0,
+            },
+            page: 0.0,
+            tint: Rgba::white(),
+        }
+    }
+
     fn stream_transformed(
         &self,
         context: &mut DrawContext,
@@ -203,7 +309,11 @@ impl PrimitivesEmitter {
                 })
                 .collect(),
             blending: self.blending.unwrap_or_else(|| context.top_blending()),
-            scissor: None,
+            scissor: context.top_scissor(),
+            depth_test: None,
+            culling: None,
+            topology: Default::default(),
+            instances: None,
         };
         graphics.stream.batch_optimized(batch);
         let transform = Mat4::from(context.top_transform());
@@ -215,6 +325,15 @@ impl PrimitivesEmitter {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoint {
+    /// Extends segment edges until they meet, falls back to bevel above miter limit.
+    #[default]
+    Miter,
+    Bevel,
+    Round,
+}
+
 pub struct LinesDraw<'a, I: IntoIterator<Item = Vec2<f32>>> {
     emitter: &'a PrimitivesEmitter,
     vertices: RefCell<Option<I>>,
@@ -223,6 +342,9 @@ pub struct LinesDraw<'a, I: IntoIterator<Item = Vec2<f32>>> {
     pub tint: Rgba<f32>,
     pub thickness: f32,
     pub looped: bool,
+    pub joint: LineJoint,
+    /// Maximum ratio of miter length to thickness.
+    pub miter_limit: f32,
 }
 
 impl<I: IntoIterator<Item = Vec2<f32>>> LinesDraw<'_, I> {
@@ -246,78 +368,191 @@ impl<I: IntoIterator<Item = Vec2<f32>>> LinesDraw<'_, I> {
         self.looped = value;
         self
     }
+
+    pub fn joint(mut self, value: LineJoint) -> Self {
+        self.joint = value;
+        self
+    }
+
+    pub fn miter_limit(mut self, value: f32) -> Self {
+        self.miter_limit = value;
+        self
+    }
+
+    fn stream_points(
+        &self,
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        mut points: Vec<Vec2<f32>>,
+    ) {
+        fn normal(tangent: Vec2<f32>) -> Vec2<f32> {
+            Vec2 {
+                x: tangent.y,
+                y: -tangent.x,
+            }
+            .try_normalized()
+            .unwrap_or_default()
+        }
+
+        points.dedup_by(|a, b| a.distance_squared(*b) < 1.0e-12);
+        if self.looped && points.len() > 2 {
+            if let [first, .., last] = points.as_slice() {
+                if first.distance_squared(*last) < 1.0e-12 {
+                    points.pop();
+                }
+            }
+        }
+        let count = points.len();
+        if count < 2 {
+            return;
+        }
+        let looped = self.looped && count > 2;
+        let steps = if looped { count + 1 } else { count };
+        let color = self.tint.into_array();
+        let region = self.region;
+        let page = self.page;
+        let vertex = |position: Vec2<f32>, u: f32, v: f32| Vertex {
+            position: position.into_array(),
+            uv: [region.x + region.w * u, region.y + region.h * v, page],
+            color,
+        };
+        let mut vertices = Vec::with_capacity(count * 4);
+        let mut triangles = Vec::with_capacity(count * 4);
+        // [(incoming left, incoming right, outgoing left, outgoing right)]
+        let mut pairs = Vec::with_capacity(count + 1);
+        // Looped lines visit first point again at the end, to close the seam.
+        // Every segment maps whole region, so points shared by two segments
+        // get separate incoming (end of region) and outgoing (start of region)
+        // vertices.
+        for index in 0..steps {
+            let point = points[index % count];
+            let has_incoming = index > 0;
+            let has_outgoing = index + 1 < steps;
+            let prev = if index > 0 {
+                Some(points[index - 1])
+            } else if looped {
+                Some(points[count - 1])
+            } else {
+                None
+            };
+            let next = if index + 1 < count {
+                Some(points[index + 1])
+            } else if looped {
+                Some(points[(index + 1) % count])
+            } else {
+                None
+            };
+            let incoming = prev.map(|prev| normal(point - prev));
+            let outgoing = next.map(|next| normal(next - point));
+            let (incoming, outgoing) = match (incoming, outgoing) {
+                (Some(incoming), Some(outgoing)) => (incoming, outgoing),
+                (Some(normal), None) | (None, Some(normal)) => (normal, normal),
+                (None, None) => continue,
+            };
+            let miter = (incoming + outgoing)
+                .try_normalized()
+                .map(|miter| (miter, miter.dot(outgoing)))
+                .filter(|(_, cos)| *cos > 1.0e-6 && 1.0 / *cos <= self.miter_limit);
+            let start = vertices.len() as u32;
+            // Straight continuations and line ends never need joint geometry.
+            let miter = miter.filter(|(_, cos)| self.joint == LineJoint::Miter || *cos > 0.99999);
+            match miter {
+                Some((miter, cos)) => {
+                    let offset = miter * self.thickness / cos;
+                    if has_incoming && has_outgoing {
+                        vertices.push(vertex(point - offset, 0.0, 1.0));
+                        vertices.push(vertex(point + offset, 1.0, 1.0));
+                        vertices.push(vertex(point - offset, 0.0, 0.0));
+                        vertices.push(vertex(point + offset, 1.0, 0.0));
+                        pairs.push((start, start + 1, start + 2, start + 3));
+                    } else {
+                        let v = if has_incoming { 1.0 } else { 0.0 };
+                        vertices.push(vertex(point - offset, 0.0, v));
+                        vertices.push(vertex(point + offset, 1.0, v));
+                        pairs.push((start, start + 1, start, start + 1));
+                    }
+                }
+                _ => {
+                    let incoming_offset = incoming * self.thickness;
+                    let outgoing_offset = outgoing * self.thickness;
+                    vertices.push(vertex(point - incoming_offset, 0.0, 1.0));
+                    vertices.push(vertex(point + incoming_offset, 1.0, 1.0));
+                    vertices.push(vertex(point - outgoing_offset, 0.0, 0.0));
+                    vertices.push(vertex(point + outgoing_offset, 1.0, 0.0));
+                    pairs.push((start, start + 1, start + 2, start + 3));
+                    let turn = incoming.x * outgoing.y - incoming.y * outgoing.x;
+                    // Seam point repeats first point geometry, so its gap is already filled.
+                    if turn.abs() > 1.0e-6 && !(looped && index == count) {
+                        let side = if turn > 0.0 { 1.0 } else { -1.0 };
+                        let (from, to, u) = if side > 0.0 {
+                            (start + 1, start + 3, 1.0)
+                        } else {
+                            (start, start + 2, 0.0)
+                        };
+                        let center = vertices.len() as u32;
+                        // Joint fill continues end of incoming segment region.
+                        vertices.push(vertex(point, 0.5, 1.0));
+                        if self.joint == LineJoint::Round {
+                            let angle = incoming.dot(outgoing).clamp(-1.0, 1.0).acos();
+                            let steps = (angle / (PI / 8.0)).ceil().max(1.0) as usize;
+                            let direction = turn.signum();
+                            let mut last = from;
+                            for step in 1..steps {
+                                let (sin, cos) =
+                                    (angle * step as f32 / steps as f32 * direction).sin_cos();
+                                let offset = Vec2 {
+                                    x: incoming.x * cos - incoming.y * sin,
+                                    y: incoming.x * sin + incoming.y * cos,
+                                } * self.thickness
+                                    * side;
+                                let current = vertices.len() as u32;
+                                vertices.push(vertex(point + offset, u, 1.0));
+                                triangles.push(Triangle {
+                                    a: center,
+                                    b: last,
+                                    c: current,
+                                });
+                                last = current;
+                            }
+                            triangles.push(Triangle {
+                                a: center,
+                                b: last,
+                                c: to,
+                            });
+                        } else {
+                            triangles.push(Triangle {
+                                a: center,
+                                b: from,
+                                c: to,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        for pair in pairs.windows(2) {
+            let (_, _, left, right) = pair[0];
+            let (next_left, next_right, _, _) = pair[1];
+            triangles.push(Triangle {
+                a: left,
+                b: right,
+                c: next_right,
+            });
+            triangles.push(Triangle {
+                a: next_right,
+                b: next_left,
+                c: left,
+            });
+        }
+        stream.extend(vertices, triangles);
+    }
 }
 
 impl<I: IntoIterator<Item = Vec2<f32>>> Drawable for LinesDraw<'_, I> {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
-        fn push(
-            stream: &mut VertexStream<Vertex, GraphicsBatch>,
-            region: Rect<f32, f32>,
-            page: f32,
-            color: [f32; 4],
-            prev: Vec2<f32>,
-            next: Vec2<f32>,
-            normal: Vec2<f32>,
-        ) {
-            stream.extend(
-                [
-                    Vertex {
-                        position: (prev - normal).into_array(),
-                        uv: [region.x, region.y, page],
-                        color,
-                    },
-                    Vertex {
-                        position: (prev + normal).into_array(),
-                        uv: [region.x + region.w, region.y, page],
-                        color,
-                    },
-                    Vertex {
-                        position: (next + normal).into_array(),
-                        uv: [region.x + region.w, region.y + region.h, page],
-                        color,
-                    },
-                    Vertex {
-                        position: (next - normal).into_array(),
-                        uv: [region.x, region.y + region.h, page],
-                        color,
-                    },
-                ],
-                [Triangle { a: 0, b: 1, c: 2 }, Triangle { a: 2, b: 3, c: 0 }],
-            );
-        }
-
         self.emitter
             .stream_transformed(context, graphics, |stream| {
                 if let Some(vertices) = self.vertices.borrow_mut().take() {
-                    let mut vertices = vertices.into_iter();
-                    let Some(mut prev) = vertices.next() else {
-                        return;
-                    };
-                    let start = prev;
-                    let color = self.tint.into_array();
-                    for next in vertices {
-                        let tangent = next - prev;
-                        let normal = Vec2 {
-                            x: tangent.y,
-                            y: -tangent.x,
-                        }
-                        .try_normalized()
-                        .unwrap_or_default()
-                            * self.thickness;
-                        push(stream, self.region, self.page, color, prev, next, normal);
-                        prev = next;
-                    }
-                    if self.looped {
-                        let tangent = start - prev;
-                        let normal = Vec2 {
-                            x: tangent.y,
-                            y: -tangent.x,
-                        }
-                        .try_normalized()
-                        .unwrap_or_default()
-                            * self.thickness;
-                        push(stream, self.region, self.page, color, prev, start, normal);
-                    }
+                    self.stream_points(stream, vertices.into_iter().collect());
                 }
             });
     }
@@ -543,3 +778,349 @@ impl Drawable for RegularPolygonDraw<'_> {
             });
     }
 }
+
+pub struct RoundedRectDraw<'a> {
+    emitter: &'a PrimitivesEmitter,
+    rect: Rect<f32, f32>,
+    radius: f32,
+    corner_segments: usize,
+    pub region: Rect<f32, f32>,
+    pub page: f32,
+    pub tint: Rgba<f32>,
+}
+
+impl RoundedRectDraw<'_> {
+    pub fn region_page(mut self, region: Rect<f32, f32>, page: f32) -> Self {
+        self.region = region;
+        self.page = page;
+        self
+    }
+
+    pub fn tint(mut self, value: Rgba<f32>) -> Self {
+        self.tint = value;
+        self
+    }
+
+    fn stream(&self, stream: &mut VertexStream<Vertex, GraphicsBatch>) {
+        let Rect { x, y, w, h } = self.rect;
+        let radius = self.radius.min(w.abs() * 0.5).min(h.abs() * 0.5).max(0.0);
+        let segments = if radius > 0.0 {
+            self.corner_segments.max(1)
+        } else {
+            0
+        };
+        let corners = [
+            Vec2::new(x + w - radius, y + h - radius),
+            Vec2::new(x + radius, y + h - radius),
+            Vec2::new(x + radius, y + radius),
+            Vec2::new(x + w - radius, y + radius),
+        ];
+        let color = self.tint.into_array();
+        stream.triangle_fan(
+            corners
+                .into_iter()
+                .enumerate()
+                .flat_map(|(corner, center)| {
+                    (0..=segments).map(move |index| {
+                        let angle =
+                            (corner as f32 + index as f32 / segments.max(1) as f32) * PI * 0.5;
+                        let (sin, cos) = angle.sin_cos();
+                        let position = center + Vec2::new(cos, sin) * radius;
+                        let u = if w != 0.0 { (position.x - x) / w } else { 0.0 };
+                        let v = if h != 0.0 { (position.y - y) / h } else { 0.0 };
+                        Vertex {
+                            position: position.into_array(),
+                            uv: [
+                                self.region.x + self.region.w * u,
+                                self.region.y + self.region.h * v,
+                                self.page,
+                            ],
+                            color,
+                        }
+                    })
+                }),
+        );
+    }
+}
+
+impl Drawable for RoundedRectDraw<'_> {
+    fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        self.emitter
+            .stream_transformed(context, graphics, |stream| self.stream(stream));
+    }
+}
+
+pub struct ArcDraw<'a> {
+    emitter: &'a PrimitivesEmitter,
+    position: Vec2<f32>,
+    radius: f32,
+    inner_radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+    segments: usize,
+    pub region: Rect<f32, f32>,
+    pub page: f32,
+    pub tint: Rgba<f32>,
+}
+
+impl ArcDraw<'_> {
+    pub fn region_page(mut self, region: Rect<f32, f32>, page: f32) -> Self {
+        self.region = region;
+        self.page = page;
+        self
+    }
+
+    pub fn tint(mut self, value: Rgba<f32>) -> Self {
+        self.tint = value;
+        self
+    }
+
+    /// Cuts hole of given radius, making ring slice out of pie slice.
+    pub fn inner_radius(mut self, value: f32) -> Self {
+        self.inner_radius = value;
+        self
+    }
+
+    fn stream(&self, stream: &mut VertexStream<Vertex, GraphicsBatch>) {
+        let segments = self.segments.max(1);
+        let color = self.tint.into_array();
+        let vertex = |angle: f32, radius: f32| {
+            let (y, x) = angle.sin_cos();
+            let offset = Vec2::new(x, y) * radius;
+            // Texture spans bounding square of outer circle.
+            let uv = (offset / self.radius.max(1.0e-6) + 1.0) * 0.5;
+            Vertex {
+                position: (self.position + offset).into_array(),
+                uv: [
+                    self.region.x + self.region.w * uv.x,
+                    self.region.y + self.region.h * uv.y,
+                    self.page,
+                ],
+                color,
+            }
+        };
+        let angles = (0..=segments).map(|index| {
+            self.start_angle + (self.end_angle - self.start_angle) * index as f32 / segments as f32
+        });
+        if self.inner_radius > 0.0 {
+            let inner_radius = self.inner_radius.min(self.radius);
+            stream.triangle_strip(
+                angles.flat_map(|angle| [vertex(angle, inner_radius), vertex(angle, self.radius)]),
+            );
+        } else {
+            stream.triangle_fan(
+                std::iter::once(vertex(0.0, 0.0))
+                    .chain(angles.map(|angle| vertex(angle, self.radius))),
+            );
+        }
+    }
+}
+
+impl Drawable for ArcDraw<'_> {
+    fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        self.emitter
+            .stream_transformed(context, graphics, |stream| self.stream(stream));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream_lines(draw: LinesDraw<Vec<Vec2<f32>>>) -> VertexStream<Vertex, GraphicsBatch> {
+        let mut stream = VertexStream::default();
+        let points = draw.vertices.borrow_mut().take().unwrap();
+        draw.stream_points(&mut stream, points);
+        stream
+    }
+
+    #[test]
+    fn test_lines_joints() {
+        let emitter = PrimitivesEmitter::default();
+        let path = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+        ];
+
+        let stream = stream_lines(emitter.emit_lines(path.clone()));
+        assert_eq!(stream.vertices().len(), 8);
+        assert_eq!(stream.triangles().len(), 4);
+        assert_eq!(stream.vertices()[2].position, [9.0, 1.0]);
+        assert_eq!(stream.vertices()[3].position, [11.0, -1.0]);
+        assert_eq!(stream.vertices()[4].position, [9.0, 1.0]);
+        assert_eq!(stream.vertices()[5].position, [11.0, -1.0]);
+        let corner = stream
+            .vertices()
+            .iter()
+            .filter(|vertex| Vec2::from(vertex.position).distance(Vec2::new(10.0, 0.0)) < 2.0)
+            .count();
+        assert_eq!(corner, 4);
+        // Every segment maps whole region along its length.
+        let vs = stream
+            .vertices()
+            .iter()
+            .map(|vertex| vertex.uv[1])
+            .collect::<Vec<_>>();
+        assert_eq!(vs, vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+
+        let stream = stream_lines(emitter.emit_lines(path.clone()).joint(LineJoint::Bevel));
+        assert_eq!(stream.vertices().len(), 9);
+        assert_eq!(stream.triangles().len(), 5);
+        let bevel = stream.triangles()[0];
+        assert_eq!(stream.vertices()[bevel.a as usize].position, [10.0, 0.0]);
+        assert_eq!(stream.vertices()[bevel.b as usize].position, [10.0, -1.0]);
+        assert_eq!(stream.vertices()[bevel.c as usize].position, [11.0, 0.0]);
+
+        let stream = stream_lines(emitter.emit_lines(path).joint(LineJoint::Round));
+        assert!(stream.triangles().len() > 5);
+        let count = stream.vertices().len();
+        for vertex in &stream.vertices()[6..(count - 2)] {
+            let distance = Vec2::from(vertex.position).distance(Vec2::new(10.0, 0.0));
+            assert!(distance < 1.0e-4 || (distance - 1.0).abs() < 1.0e-4);
+        }
+
+        let square = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ];
+        let stream = stream_lines(emitter.emit_lines(square).looped(true));
+        assert_eq!(stream.vertices().len(), 16);
+        assert_eq!(stream.triangles().len(), 8);
+        assert_eq!(
+            stream.vertices()[0].position,
+            stream.vertices()[14].position
+        );
+        assert_eq!(
+            stream.vertices()[1].position,
+            stream.vertices()[15].position
+        );
+        assert_eq!(stream.vertices()[0].uv[1], 0.0);
+        assert_eq!(stream.vertices()[14].uv[1], 1.0);
+    }
+
+    #[test]
+    fn test_bezier() {
+        let emitter = PrimitivesEmitter::default();
+        let points = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(0.0, 10.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 0.0),
+        ];
+        let draw = emitter.emit_bezier(points, 8).thickness(2.0);
+        let path = draw.vertices.borrow().clone().unwrap();
+        assert_eq!(path.len(), 9);
+        assert_eq!(path[0], points[0]);
+        assert_eq!(path[8], points[3]);
+        assert!((path[4] - Vec2::new(5.0, 7.5)).magnitude() < 1.0e-4);
+        let stream = stream_lines(draw);
+        assert_eq!(stream.vertices().len(), 32);
+
+        let path = emitter
+            .emit_quadratic_bezier([Vec2::new(0.0, 0.0), Vec2::new(5.0, 10.0), points[3]], 2)
+            .vertices
+            .borrow_mut()
+            .take()
+            .unwrap();
+        assert_eq!(path, vec![points[0], Vec2::new(5.0, 5.0), points[3]]);
+
+        let flat = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(3.0, 0.0),
+        ];
+        assert_eq!(PrimitivesEmitter::bezier_segments(&flat, 0.1), 1);
+        let coarse = PrimitivesEmitter::bezier_segments(&points, 1.0);
+        let fine = PrimitivesEmitter::bezier_segments(&points, 0.1);
+        assert!(coarse > 1);
+        assert!(fine > coarse);
+        let path = emitter
+            .emit_bezier(points, fine)
+            .vertices
+            .borrow_mut()
+            .take()
+            .unwrap();
+        for segment in path.windows(2) {
+            // Curve midpoint of each segment stays within tolerance.
+            let middle = (segment[0] + segment[1]) * 0.5;
+            let nearest = (0..=1000)
+                .map(|index| {
+                    let t = index as f32 / 1000.0;
+                    let curve = CubicBezier2 {
+                        start: points[0],
+                        ctrl0: points[1],
+                        ctrl1: points[2],
+                        end: points[3],
+                    };
+                    (curve.evaluate(t) - middle).magnitude()
+                })
+                .fold(f32::INFINITY, f32::min);
+            assert!(nearest <= 0.1 + 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn test_rounded_rect_and_arc() {
+        let emitter = PrimitivesEmitter::default();
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_rounded_rect(Rect::new(0.0, 0.0, 20.0, 10.0), 2.0, 3)
+            .stream(&mut stream);
+        assert_eq!(stream.vertices().len(), 16);
+        assert_eq!(stream.triangles().len(), 14);
+        for vertex in stream.vertices() {
+            let [x, y] = vertex.position;
+            assert!((-1.0e-4..=20.0001).contains(&x));
+            assert!((-1.0e-4..=10.0001).contains(&y));
+            assert!((0.0..=1.0001).contains(&vertex.uv[0]));
+        }
+        assert_eq!(stream.vertices()[0].position, [20.0, 8.0]);
+
+        // Radius bigger than half of rectangle turns it into capsule.
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_rounded_rect(Rect::new(0.0, 0.0, 20.0, 10.0), 100.0, 4)
+            .stream(&mut stream);
+        let top = stream
+            .vertices()
+            .iter()
+            .map(|vertex| vertex.position[1])
+            .fold(f32::INFINITY, f32::min);
+        assert!(top.abs() < 1.0e-4);
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_rounded_rect(Rect::new(0.0, 0.0, 20.0, 10.0), 0.0, 4)
+            .stream(&mut stream);
+        assert_eq!(stream.vertices().len(), 4);
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_arc(Vec2::new(5.0, 5.0), 2.0, 0.0, PI, 4)
+            .stream(&mut stream);
+        assert_eq!(stream.vertices().len(), 6);
+        assert_eq!(stream.triangles().len(), 4);
+        assert_eq!(stream.vertices()[0].position, [5.0, 5.0]);
+        assert_eq!(stream.vertices()[1].position, [7.0, 5.0]);
+        let end = Vec2::<f32>::from(stream.vertices()[5].position);
+        assert!((end - Vec2::new(3.0, 5.0)).magnitude() < 1.0e-4);
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_arc(Vec2::new(0.0, 0.0), 2.0, 0.0, TAU, 8)
+            .inner_radius(1.0)
+            .stream(&mut stream);
+        assert_eq!(stream.vertices().len(), 18);
+        assert_eq!(stream.triangles().len(), 16);
+        assert_eq!(stream.vertices()[0].position, [1.0, 0.0]);
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        emitter
+            .emit_arc(Vec2::new(0.0, 0.0), 2.0, 1.0, 1.0, 0)
+            .stream(&mut stream);
+        assert_eq!(stream.vertices().len(), 3);
+    }
+}
@@ -0,0 +1,346 @@
+use crate::utils::Vertex;
+use image::{Rgba as ImageRgba, RgbaImage};
+use spitfire_core::VertexStream;
+use spitfire_glow::{
+    graphics::{Camera, GraphicsBatch, Texture},
+    renderer::{
+        GlowBlendFactor, GlowBlending, GlowTextureFiltering, GlowTextureWrap, GlowTopology,
+        GlowWinding,
+    },
+};
+use vek::{Rgba, Vec2};
+
+/// Rasterizes vertex stream into image on CPU, without any GPU context.
+///
+/// Camera screen size is replaced with image size and its world matrix is
+/// used for every batch, so batch `u_projection_view` uniforms are ignored.
+/// Triangles are filled with vertex colors - see
+/// [`render_stream_to_image_with_textures`] for textured output.
+pub fn render_stream_to_image(
+    stream: &VertexStream<Vertex, GraphicsBatch>,
+    camera: &Camera,
+    size: Vec2<u32>,
+) -> RgbaImage {
+    render_stream_to_image_with_textures(stream, camera, size, |_| None)
+}
+
+/// Same as [`render_stream_to_image`], but samples first texture of each
+/// batch from CPU image provided by `textures` callback.
+///
+/// Limitations of software mode:
+/// - GPU textures can't be read back, so batches with texture that has no
+///   CPU image counterpart are rendered with vertex colors only.
+/// - Only first layer of texture is sampled and only first batch texture.
+/// - Mipmaps are not used, trilinear filtering samples as bilinear one.
+/// - Custom shaders are not executed - texels are always multiplied by
+///   vertex color, same as default colored and textured shaders do.
+/// - Depth test is ignored, triangles are drawn in stream order.
+/// - Only triangles are rasterized, line and point batches are skipped.
+/// - Instanced batches rasterize only their base geometry, once.
+pub fn render_stream_to_image_with_textures<'a>(
+    stream: &VertexStream<Vertex, GraphicsBatch>,
+    camera: &Camera,
+    size: Vec2<u32>,
+    textures: impl Fn(&Texture) -> Option<&'a RgbaImage>,
+) -> RgbaImage {
+    let mut result = RgbaImage::new(size.x, size.y);
+    if size.x == 0 || size.y == 0 {
+        return result;
+    }
+    let mut camera = *camera;
+    camera.screen_size = size.as_();
+    let matrix = camera.world_matrix();
+    let vertices = stream.vertices();
+    let triangles = stream.triangles();
+    for (batch, range) in stream.batches() {
+        if batch.topology != GlowTopology::Triangles {
+            continue;
+        }
+        let texture = batch.textures.first().and_then(|(texture, filtering)| {
+            Some((textures(texture)?, *filtering, texture.wrap()))
+        });
+        let scissor = batch.scissor.map(|rect| {
+            // Scissor uses framebuffer coordinates with origin at bottom.
+            let top = size.y as i32 - rect.y - rect.h;
+            [
+                rect.x.max(0),
+                top.max(0),
+                (rect.x + rect.w).min(size.x as i32),
+                (top + rect.h).min(size.y as i32),
+            ]
+        });
+        for triangle in &triangles[range.clone()] {
+            let points = [triangle.a, triangle.b, triangle.c].map(|index| {
+                let vertex = vertices[index as usize];
+                let position = matrix.mul_point(Vec2::from(vertex.position));
+                let position = Vec2::new(
+                    (position.x + 1.0) * 0.5 * size.x as f32,
+                    (1.0 - position.y) * 0.5 * size.y as f32,
+                );
+                (position, vertex)
+            });
+            if let Some(culling) = batch.culling {
+                // Screen space has Y axis pointing down, so winding flips.
+                let winding = if edge(points[0].0, points[1].0, points[2].0) < 0.0 {
+                    GlowWinding::CounterClockwise
+                } else {
+                    GlowWinding::Clockwise
+                };
+                if culling.culls(winding) {
+                    continue;
+                }
+            }
+            rasterize_triangle(points, size, scissor, |x, y, weights| {
+                let mut color = Rgba::<f32>::zero();
+                let mut uv = Vec2::<f32>::zero();
+                for ((_, vertex), weight) in points.iter().zip(weights) {
+                    color += Rgba::from(vertex.color) * weight;
+                    uv += Vec2::new(vertex.uv[0], vertex.uv[1]) * weight;
+                }
+                if let Some((image, filtering, wrap)) = texture {
+                    color *= sample(image, uv, filtering, wrap);
+                }
+                let pixel = result.get_pixel_mut(x, y);
+                *pixel = blend(*pixel, color, batch.blending);
+            });
+        }
+    }
+    result
+}
+
+fn rasterize_triangle(
+    points: [(Vec2<f32>, Vertex); 3],
+    size: Vec2<u32>,
+    scissor: Option<[i32; 4]>,
+    mut f: impl FnMut(u32, u32, [f32; 3]),
+) {
+    let [a, b, c] = points.map(|(position, _)| position);
+    let area = edge(a, b, c);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+    let [mut min_x, mut min_y, mut max_x, mut max_y] =
+        scissor.unwrap_or([0, 0, size.x as i32, size.y as i32]);
+    min_x = min_x.max(a.x.min(b.x).min(c.x).floor() as i32);
+    min_y = min_y.max(a.y.min(b.y).min(c.y).floor() as i32);
+    max_x = max_x.min(a.x.max(b.x).max(c.x).ceil() as i32);
+    max_y = max_y.min(a.y.max(b.y).max(c.y).ceil() as i32);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let point = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let weights =
+                [edge(b, c, point), edge(c, a, point), edge(a, b, point)].map(|value| value / area);
+            if weights.iter().all(|weight| *weight >= 0.0) {
+                f(x as u32, y as u32, weights);
+            }
+        }
+    }
+}
+
+fn edge(a: Vec2<f32>, b: Vec2<f32>, point: Vec2<f32>) -> f32 {
+    (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+}
+
+fn sample(
+    image: &RgbaImage,
+    uv: Vec2<f32>,
+    filtering: GlowTextureFiltering,
+    wrap: GlowTextureWrap,
+) -> Rgba<f32> {
+    let width = image.width();
+    let height = image.height();
+    if width == 0 || height == 0 {
+        return Rgba::zero();
+    }
+    let texel = |x: i32, y: i32| {
+        let x = wrap_coord(x, width as i32, wrap);
+        let y = wrap_coord(y, height as i32, wrap);
+        Rgba::<u8>::from(image.get_pixel(x, y).0).as_::<f32>() / 255.0
+    };
+    let x = uv.x * width as f32;
+    let y = uv.y * height as f32;
+    match filtering {
+        GlowTextureFiltering::Nearest => texel(x.floor() as i32, y.floor() as i32),
+        GlowTextureFiltering::Linear | GlowTextureFiltering::LinearMipmap => {
+            let x = x - 0.5;
+            let y = y - 0.5;
+            let fx = x - x.floor();
+            let fy = y - y.floor();
+            let x = x.floor() as i32;
+            let y = y.floor() as i32;
+            let top = Rgba::lerp(texel(x, y), texel(x + 1, y), fx);
+            let bottom = Rgba::lerp(texel(x, y + 1), texel(x + 1, y + 1), fx);
+            Rgba::lerp(top, bottom, fy)
+        }
+    }
+}
+
+fn wrap_coord(value: i32, size: i32, wrap: GlowTextureWrap) -> u32 {
+    let result = match wrap {
+        GlowTextureWrap::Clamp => value.clamp(0, size - 1),
+        GlowTextureWrap::Repeat => value.rem_euclid(size),
+        GlowTextureWrap::MirrorRepeat => {
+            let value = value.rem_euclid(size * 2);
+            if value < size {
+                value
+            } else {
+                size * 2 - 1 - value
+            }
+        }
+    };
+    result as u32
+}
+
+fn blend(destination: ImageRgba<u8>, source: Rgba<f32>, blending: GlowBlending) -> ImageRgba<u8> {
+    let destination = Rgba::<u8>::from(destination.0).as_::<f32>() / 255.0;
+    let result = match blending.factors() {
+        Some([src_rgb, dst_rgb, src_alpha, dst_alpha]) => {
+            let factor = |factor| blend_factor(factor, source, destination);
+            let color = source * factor(src_rgb) + destination * factor(dst_rgb);
+            let alpha = source.a * factor(src_alpha).a + destination.a * factor(dst_alpha).a;
+            Rgba::new(color.r, color.g, color.b, alpha)
+        }
+        None => source,
+    };
+    let result = (result.map(|value| value.clamp(0.0, 1.0)) * 255.0).round();
+    ImageRgba(result.as_::<u8>().into_array())
+}
+
+fn blend_factor(factor: GlowBlendFactor, source: Rgba<f32>, destination: Rgba<f32>) -> Rgba<f32> {
+    match factor {
+        GlowBlendFactor::Zero => Rgba::zero(),
+        GlowBlendFactor::One => Rgba::one(),
+        GlowBlendFactor::SrcColor => source,
+        GlowBlendFactor::OneMinusSrcColor => Rgba::one() - source,
+        GlowBlendFactor::DstColor => destination,
+        GlowBlendFactor::OneMinusDstColor => Rgba::one() - destination,
+        GlowBlendFactor::SrcAlpha => Rgba::broadcast(source.a),
+        GlowBlendFactor::OneMinusSrcAlpha => Rgba::broadcast(1.0 - source.a),
+        GlowBlendFactor::DstAlpha => Rgba::broadcast(destination.a),
+        GlowBlendFactor::OneMinusDstAlpha => Rgba::broadcast(1.0 - destination.a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{blend, render_stream_to_image, wrap_coord};
+    use crate::utils::Vertex;
+    use image::Rgba as ImageRgba;
+    use spitfire_core::VertexStream;
+    use spitfire_glow::renderer::GlowCulling;
+    use spitfire_glow::{
+        graphics::{Camera, GraphicsBatch},
+        renderer::{GlowBlendFactor, GlowBlending, GlowTextureWrap},
+    };
+    use vek::{Rgba, Vec2};
+
+    #[test]
+    fn test_render_stream_to_image() {
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        stream.batch(GraphicsBatch::default());
+        let color = [1.0, 0.0, 0.0, 1.0];
+        stream.quad([
+            Vertex {
+                position: [16.0, 16.0],
+                color,
+                ..Default::default()
+            },
+            Vertex {
+                position: [48.0, 16.0],
+                color,
+                ..Default::default()
+            },
+            Vertex {
+                position: [48.0, 48.0],
+                color,
+                ..Default::default()
+            },
+            Vertex {
+                position: [16.0, 48.0],
+                color,
+                ..Default::default()
+            },
+        ]);
+        stream.batch_end();
+
+        let image = render_stream_to_image(&stream, &Camera::default(), Vec2::new(64, 64));
+        assert_eq!(image.dimensions(), (64, 64));
+        assert_eq!(image.get_pixel(32, 32).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(16, 16).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(47, 47).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(8, 8).0, [0, 0, 0, 0]);
+        assert_eq!(image.get_pixel(48, 48).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_culling() {
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        stream.batch(GraphicsBatch {
+            culling: Some(GlowCulling::default()),
+            ..Default::default()
+        });
+        let vertex = |x, y, color| Vertex {
+            position: [x, y],
+            color,
+            ..Default::default()
+        };
+        // World space has Y axis pointing down, same as screen space.
+        let front = [0.0, 1.0, 0.0, 1.0];
+        stream.quad([
+            vertex(0.0, 0.0, front),
+            vertex(0.0, 32.0, front),
+            vertex(32.0, 32.0, front),
+            vertex(32.0, 0.0, front),
+        ]);
+        let back = [1.0, 0.0, 0.0, 1.0];
+        stream.quad([
+            vertex(32.0, 32.0, back),
+            vertex(64.0, 32.0, back),
+            vertex(64.0, 64.0, back),
+            vertex(32.0, 64.0, back),
+        ]);
+        stream.batch_end();
+
+        let image = render_stream_to_image(&stream, &Camera::default(), Vec2::new(64, 64));
+        assert_eq!(image.get_pixel(16, 16).0, [0, 255, 0, 255]);
+        assert_eq!(image.get_pixel(48, 48).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_wrap_coord() {
+        let wrap = |value, mode| wrap_coord(value, 4, mode);
+        assert_eq!(wrap(-1, GlowTextureWrap::Clamp), 0);
+        assert_eq!(wrap(5, GlowTextureWrap::Clamp), 3);
+        assert_eq!(wrap(-1, GlowTextureWrap::Repeat), 3);
+        assert_eq!(wrap(5, GlowTextureWrap::Repeat), 1);
+        assert_eq!(wrap(4, GlowTextureWrap::MirrorRepeat), 3);
+        assert_eq!(wrap(7, GlowTextureWrap::MirrorRepeat), 0);
+        assert_eq!(wrap(8, GlowTextureWrap::MirrorRepeat), 0);
+        assert_eq!(wrap(-1, GlowTextureWrap::MirrorRepeat), 0);
+    }
+
+    #[test]
+    fn test_blend() {
+        let destination = ImageRgba([0, 0, 255, 255]);
+        let source = Rgba::new(1.0, 0.0, 0.0, 0.5);
+        assert_eq!(
+            blend(destination, source, GlowBlending::None).0,
+            [255, 0, 0, 128]
+        );
+        assert_eq!(
+            blend(destination, source, GlowBlending::Alpha).0,
+            [128, 0, 128, 191]
+        );
+        assert_eq!(
+            blend(destination, source, GlowBlending::PremultipliedAlpha).0,
+            [255, 0, 128, 255]
+        );
+        let custom = GlowBlending::Custom {
+            src_rgb: GlowBlendFactor::SrcAlpha,
+            dst_rgb: GlowBlendFactor::OneMinusSrcAlpha,
+            src_alpha: GlowBlendFactor::One,
+            dst_alpha: GlowBlendFactor::OneMinusSrcAlpha,
+        };
+        assert_eq!(blend(destination, source, custom).0, [128, 0, 128, 255]);
+    }
+}
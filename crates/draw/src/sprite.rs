@@ -3,8 +3,9 @@ use crate::{
     utils::{Drawable, ShaderRef, TextureRef, Vertex},
 };
 use smallvec::SmallVec;
+use spitfire_core::VertexStream;
 use spitfire_glow::{
-    graphics::{Graphics, GraphicsBatch},
+    graphics::{Camera, Graphics, GraphicsBatch, Shader, Texture},
     renderer::{GlowBlending, GlowTextureFiltering, GlowUniformValue},
 };
 use std::{borrow::Cow, collections::HashMap};
@@ -32,6 +33,141 @@ impl SpriteTexture {
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpriteAnimationFrames {
+    /// Frames laid out row by row in uniform grid covering whole texture.
+    Grid {
+        columns: usize,
+        rows: usize,
+        count: usize,
+    },
+    Regions(Vec<Rect<f32, f32>>),
+}
+
+impl SpriteAnimationFrames {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Grid {
+                columns,
+                rows,
+                count,
+            } => (*count).min(columns * rows),
+            Self::Regions(regions) => regions.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn region(&self, index: usize) -> Option<Rect<f32, f32>> {
+        if index >= self.len() {
+            return None;
+        }
+        match self {
+            Self::Grid { columns, rows, .. } => {
+                let width = 1.0 / *columns as f32;
+                let height = 1.0 / *rows as f32;
+                Some(Rect::new(
+                    (index % columns) as f32 * width,
+                    (index / columns) as f32 * height,
+                    width,
+                    height,
+                ))
+            }
+            Self::Regions(regions) => regions.get(index).copied(),
+        }
+    }
+}
+
+/// Sprite sheet animation, selects region of frame for given time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteAnimation {
+    pub frames: SpriteAnimationFrames,
+    pub fps: f32,
+    /// When disabled, animation stops at last frame.
+    pub looping: bool,
+    pub page: f32,
+    /// Time accumulated with [`Self::update`].
+    pub elapsed: f32,
+}
+
+impl SpriteAnimation {
+    pub fn new(frames: SpriteAnimationFrames, fps: f32) -> Self {
+        Self {
+            frames,
+            fps,
+            looping: true,
+            page: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn grid(columns: usize, rows: usize, count: usize, fps: f32) -> Self {
+        Self::new(
+            SpriteAnimationFrames::Grid {
+                columns,
+                rows,
+                count,
+            },
+            fps,
+        )
+    }
+
+    pub fn regions(regions: impl IntoIterator<Item = Rect<f32, f32>>, fps: f32) -> Self {
+        Self::new(
+            SpriteAnimationFrames::Regions(regions.into_iter().collect()),
+            fps,
+        )
+    }
+
+    pub fn looping(mut self, value: bool) -> Self {
+        self.looping = value;
+        self
+    }
+
+    pub fn page(mut self, value: f32) -> Self {
+        self.page = value;
+        self
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+    }
+
+    pub fn duration(&self) -> f32 {
+        if self.fps > 0.0 {
+            self.frames.len() as f32 / self.fps
+        } else {
+            0.0
+        }
+    }
+
+    /// Tells if one-shot animation reached its end. Looping one never ends.
+    pub fn is_finished(&self, time: f32) -> bool {
+        !self.looping && time >= self.duration()
+    }
+
+    pub fn frame_index(&self, time: f32) -> usize {
+        let count = self.frames.len();
+        if count == 0 {
+            return 0;
+        }
+        let frame = (time * self.fps).floor().max(0.0) as usize;
+        if self.looping {
+            frame % count
+        } else {
+            frame.min(count - 1)
+        }
+    }
+
+    pub fn frame_region(&self, time: f32) -> Rect<f32, f32> {
+        self.frames
+            .region(self.frame_index(time))
+            .unwrap_or(Rect::new(0.0, 0.0, 1.0, 1.0))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Sprite {
     pub shader: Option<ShaderRef>,
@@ -94,6 +230,11 @@ impl Sprite {
         self
     }
 
+    /// Sets region and page of animation frame at given time.
+    pub fn animated(self, animation: &SpriteAnimation, time: f32) -> Self {
+        self.region_page(animation.frame_region(time), animation.page)
+    }
+
     pub fn tint(mut self, value: Rgba<f32>) -> Self {
         self.tint = value;
         self
@@ -147,6 +288,17 @@ impl Sprite {
 
 impl Drawable for Sprite {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        self.stream(context, &mut graphics.stream, &graphics.main_camera);
+    }
+}
+
+impl Sprite {
+    fn stream(
+        &self,
+        context: &DrawContext,
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        camera: &Camera,
+    ) {
         let batch = GraphicsBatch {
             shader: context.shader(self.shader.as_ref()),
             uniforms: self
@@ -157,9 +309,9 @@ impl Drawable for Sprite {
                     "u_projection_view".into(),
                     GlowUniformValue::M4(
                         if self.screen_space {
-                            graphics.main_camera.screen_matrix()
+                            camera.screen_matrix()
                         } else {
-                            graphics.main_camera.world_matrix()
+                            camera.world_matrix()
                         }
                         .into_col_array(),
                     ),
@@ -176,7 +328,11 @@ impl Drawable for Sprite {
                 })
                 .collect(),
             blending: self.blending.unwrap_or_else(|| context.top_blending()),
-            scissor: None,
+            scissor: context.top_scissor(),
+            depth_test: None,
+            culling: None,
+            topology: Default::default(),
+            instances: None,
         };
         let transform = Mat4::from(context.top_transform()) * Mat4::from(self.transform);
         let size = self
@@ -190,8 +346,8 @@ impl Drawable for Sprite {
             .unwrap_or_default();
         let offset = size * self.pivot;
         let color = self.tint.into_array();
-        graphics.stream.batch_optimized(batch);
-        graphics.stream.transformed(
+        stream.batch_optimized(batch);
+        stream.transformed(
             |stream| {
                 stream.quad([
                     Vertex {
@@ -228,3 +384,169 @@ impl Drawable for Sprite {
         );
     }
 }
+
+/// Collects sprites to draw them grouped by render state, so sprites that
+/// share shader, textures, uniforms and blending end up in single batch,
+/// even when pushed interleaved with other ones. Sprites keep their order
+/// within group, but groups are drawn in order of their first sprite, so
+/// overlapping sprites of different groups may swap draw order.
+#[derive(Debug, Default, Clone)]
+pub struct SpriteBatch {
+    pub sprites: Vec<Sprite>,
+}
+
+impl SpriteBatch {
+    pub fn sprite(mut self, value: Sprite) -> Self {
+        self.sprites.push(value);
+        self
+    }
+
+    pub fn push(&mut self, value: Sprite) {
+        self.sprites.push(value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    fn stream(
+        &self,
+        context: &DrawContext,
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        camera: &Camera,
+    ) {
+        let mut groups = Vec::<(SpriteBatchKey, Vec<&Sprite>)>::new();
+        for sprite in &self.sprites {
+            let key = SpriteBatchKey::new(sprite, context);
+            match groups.iter_mut().find(|(group, _)| *group == key) {
+                Some((_, sprites)) => sprites.push(sprite),
+                None => groups.push((key, vec![sprite])),
+            }
+        }
+        for (_, sprites) in groups {
+            for sprite in sprites {
+                sprite.stream(context, stream, camera);
+            }
+        }
+    }
+}
+
+impl Drawable for SpriteBatch {
+    fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
+        self.stream(context, &mut graphics.stream, &graphics.main_camera);
+    }
+}
+
+type SpriteBatchKeyTexture = (Cow<'static, str>, Option<Texture>, GlowTextureFiltering);
+
+#[derive(PartialEq)]
+struct SpriteBatchKey<'a> {
+    shader: Option<Shader>,
+    textures: SmallVec<[SpriteBatchKeyTexture; 4]>,
+    uniforms: &'a HashMap<Cow<'static, str>, GlowUniformValue>,
+    blending: GlowBlending,
+    screen_space: bool,
+}
+
+impl<'a> SpriteBatchKey<'a> {
+    fn new(sprite: &'a Sprite, context: &DrawContext) -> Self {
+        Self {
+            shader: context.shader(sprite.shader.as_ref()),
+            textures: sprite
+                .textures
+                .iter()
+                .map(|texture| {
+                    (
+                        texture.sampler.clone(),
+                        context.texture(Some(&texture.texture)),
+                        texture.filtering,
+                    )
+                })
+                .collect(),
+            uniforms: &sprite.uniforms,
+            blending: sprite.blending.unwrap_or_else(|| context.top_blending()),
+            screen_space: sprite.screen_space,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sprite_animation() {
+        let animation = SpriteAnimation::grid(4, 2, 6, 10.0);
+        assert_eq!(animation.frames.len(), 6);
+        assert_eq!(animation.frame_index(0.0), 0);
+        assert_eq!(animation.frame_index(0.15), 1);
+        assert_eq!(animation.frame_index(0.55), 5);
+        assert_eq!(animation.frame_index(0.65), 0);
+        assert_eq!(animation.frame_region(0.45), Rect::new(0.0, 0.5, 0.25, 0.5));
+        assert!(!animation.is_finished(10.0));
+
+        let animation = animation.looping(false).page(2.0);
+        assert_eq!(animation.frame_index(0.65), 5);
+        assert_eq!(
+            animation.frame_region(10.0),
+            Rect::new(0.25, 0.5, 0.25, 0.5)
+        );
+        assert!(animation.is_finished(0.6));
+        assert!(!animation.is_finished(0.55));
+
+        let sprite = Sprite::default().animated(&animation, 0.25);
+        assert_eq!(sprite.region, Rect::new(0.5, 0.0, 0.25, 0.5));
+        assert_eq!(sprite.page, 2.0);
+
+        let regions = [Rect::new(0.0, 0.0, 0.5, 0.5), Rect::new(0.5, 0.5, 0.5, 0.5)];
+        let mut animation = SpriteAnimation::regions(regions, 2.0);
+        animation.update(0.75);
+        assert_eq!(animation.frame_region(animation.elapsed), regions[1]);
+        let animation = SpriteAnimation::regions([], 2.0);
+        assert_eq!(animation.frame_region(1.0), Rect::new(0.0, 0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_sprite_batch() {
+        let context = DrawContext::default();
+        let camera = Camera {
+            screen_size: Vec2::new(800.0, 600.0),
+            ..Default::default()
+        };
+        let sprite = |sampler: &'static str| {
+            Sprite::single(SpriteTexture::new(sampler.into(), TextureRef::name("")))
+        };
+        let sprites = (0..200)
+            .map(|index| match index % 3 {
+                0 => sprite("u_a"),
+                1 => sprite("u_b"),
+                _ => sprite("u_a").blending(GlowBlending::Additive),
+            })
+            .collect::<Vec<_>>();
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        for sprite in &sprites {
+            sprite.stream(&context, &mut stream, &camera);
+        }
+        stream.batch_end();
+        assert_eq!(stream.batches().len(), 200);
+
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        SpriteBatch {
+            sprites: sprites.clone(),
+        }
+        .stream(&context, &mut stream, &camera);
+        stream.batch_end();
+        assert_eq!(stream.batches().len(), 3);
+        assert_eq!(stream.triangles().len(), 400);
+        assert_eq!(stream.batches()[0].1, 0..134);
+    }
+}
@@ -1,22 +1,231 @@
 use crate::{
     context::DrawContext,
-    utils::{Drawable, ShaderRef, Vertex},
+    utils::{Drawable, FontMap, ShaderRef, Vertex},
 };
 use fontdue::layout::{
     CoordinateSystem, HorizontalAlign, Layout, LayoutSettings, TextStyle, VerticalAlign,
 };
+use spitfire_fontdue::{TextDecoration, TextRenderer};
 use spitfire_glow::{
     graphics::{Graphics, GraphicsBatch},
     renderer::{GlowBlending, GlowTextureFiltering, GlowUniformValue},
 };
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 use vek::{Mat4, Quaternion, Rgba, Transform, Vec2, Vec3};
 
+/// Inline run of text with its own style. Unset properties fall back to
+/// ones of [`Text`] it belongs to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextSpan {
+    pub text: Cow<'static, str>,
+    pub font: Option<Cow<'static, str>>,
+    pub size: Option<f32>,
+    pub tint: Option<Rgba<f32>>,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn font(mut self, value: impl Into<Cow<'static, str>>) -> Self {
+        self.font = Some(value.into());
+        self
+    }
+
+    pub fn size(mut self, value: f32) -> Self {
+        self.size = Some(value);
+        self
+    }
+
+    pub fn tint(mut self, value: Rgba<f32>) -> Self {
+        self.tint = Some(value);
+        self
+    }
+}
+
+/// What happens with text laid out beyond its `width` and `height` box.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextOverflow {
+    #[default]
+    Visible,
+    /// Trailing characters that don't fit are dropped.
+    Clip,
+    /// Trailing characters that don't fit are replaced with ellipsis.
+    Ellipsis,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TextSpanKey {
+    text: Cow<'static, str>,
+    font: Option<Cow<'static, str>>,
+    size: Option<u32>,
+    tint: Option<[u32; 4]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TextLayoutKey {
+    font: Cow<'static, str>,
+    size: u32,
+    text: Cow<'static, str>,
+    spans: Vec<TextSpanKey>,
+    tint: [u32; 4],
+    horizontal_align: u8,
+    vertical_align: u8,
+    width: Option<u32>,
+    height: Option<u32>,
+    overflow: TextOverflow,
+}
+
+impl TextLayoutKey {
+    fn new(text: &Text) -> Self {
+        Self {
+            font: text.font.clone(),
+            size: text.size.to_bits(),
+            text: text.text.clone(),
+            spans: text
+                .spans
+                .iter()
+                .map(|span| TextSpanKey {
+                    text: span.text.clone(),
+                    font: span.font.clone(),
+                    size: span.size.map(f32::to_bits),
+                    tint: span.tint.map(|tint| tint.into_array().map(f32::to_bits)),
+                })
+                .collect(),
+            tint: text.tint.into_array().map(f32::to_bits),
+            horizontal_align: match text.horizontal_align {
+                HorizontalAlign::Left => 0,
+                HorizontalAlign::Center => 1,
+                HorizontalAlign::Right => 2,
+            },
+            vertical_align: match text.vertical_align {
+                VerticalAlign::Top => 0,
+                VerticalAlign::Middle => 1,
+                VerticalAlign::Bottom => 2,
+            },
+            width: text.width.map(f32::to_bits),
+            height: text.height.map(f32::to_bits),
+            overflow: text.overflow,
+        }
+    }
+}
+
+/// Shared text layout with its measured `[x, y, width, height]` bounds.
+pub type CachedTextLayout = (Rc<Layout<Rgba<f32>>>, [f32; 4]);
+
+type TextLayoutCacheEntry = (Rc<Layout<Rgba<f32>>>, [f32; 4], usize);
+
+/// Bounded cache of text layouts and their measured bounds, so static labels
+/// don't get laid out again every frame. When full, least recently used
+/// entry gets evicted. Layouts refer to fonts by their index, so all entries
+/// get dropped once [`FontMap::revision`] changes.
+#[derive(Clone)]
+pub struct TextLayoutCache {
+    capacity: usize,
+    entries: RefCell<HashMap<TextLayoutKey, TextLayoutCacheEntry>>,
+    usage_counter: Cell<usize>,
+    fonts_revision: Cell<usize>,
+}
+
+impl Default for TextLayoutCache {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl TextLayoutCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Default::default(),
+            usage_counter: Default::default(),
+            fonts_revision: Default::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Evicts least recently used entries above new capacity.
+    pub fn set_capacity(&mut self, value: usize) {
+        self.capacity = value;
+        let entries = self.entries.get_mut();
+        while entries.len() > value {
+            Self::evict(entries);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.get_mut().clear();
+    }
+
+    fn sync_fonts(&self, fonts: &FontMap) {
+        if self.fonts_revision.get() != fonts.revision() {
+            self.fonts_revision.set(fonts.revision());
+            self.entries.borrow_mut().clear();
+        }
+    }
+
+    fn get(&self, key: &TextLayoutKey) -> Option<CachedTextLayout> {
+        let mut entries = self.entries.borrow_mut();
+        let (layout, bounds, last_used) = entries.get_mut(key)?;
+        *last_used = self.next_usage();
+        Some((layout.clone(), *bounds))
+    }
+
+    fn insert(&self, key: TextLayoutKey, layout: Rc<Layout<Rgba<f32>>>, bounds: [f32; 4]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.borrow_mut();
+        while entries.len() >= self.capacity {
+            Self::evict(&mut entries);
+        }
+        entries.insert(key, (layout, bounds, self.next_usage()));
+    }
+
+    fn next_usage(&self) -> usize {
+        let result = self.usage_counter.get() + 1;
+        self.usage_counter.set(result);
+        result
+    }
+
+    fn evict(entries: &mut HashMap<TextLayoutKey, TextLayoutCacheEntry>) {
+        let key = entries
+            .iter()
+            .min_by_key(|(_, (_, _, last_used))| *last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = key {
+            entries.remove(&key);
+        }
+    }
+}
+
 pub struct Text {
     pub shader: Option<ShaderRef>,
     pub font: Cow<'static, str>,
     pub size: f32,
     pub text: Cow<'static, str>,
+    /// Appended after `text`.
+    pub spans: Vec<TextSpan>,
     pub tint: Rgba<f32>,
     pub horizontal_align: HorizontalAlign,
     pub vertical_align: VerticalAlign,
@@ -26,6 +235,8 @@ pub struct Text {
     pub transform: Transform<f32, f32, f32>,
     pub blending: Option<GlowBlending>,
     pub screen_space: bool,
+    pub decoration: TextDecoration,
+    pub overflow: TextOverflow,
 }
 
 impl Default for Text {
@@ -35,6 +246,7 @@ impl Default for Text {
             font: Default::default(),
             size: 32.0,
             text: Default::default(),
+            spans: Default::default(),
             tint: Rgba::white(),
             horizontal_align: HorizontalAlign::Left,
             vertical_align: VerticalAlign::Top,
@@ -44,6 +256,8 @@ impl Default for Text {
             transform: Default::default(),
             blending: Default::default(),
             screen_space: Default::default(),
+            decoration: Default::default(),
+            overflow: Default::default(),
         }
     }
 }
@@ -76,6 +290,16 @@ impl Text {
         self
     }
 
+    pub fn spans(mut self, value: Vec<TextSpan>) -> Self {
+        self.spans = value;
+        self
+    }
+
+    pub fn span(mut self, value: TextSpan) -> Self {
+        self.spans.push(value);
+        self
+    }
+
     pub fn tint(mut self, value: Rgba<f32>) -> Self {
         self.tint = value;
         self
@@ -140,30 +364,181 @@ impl Text {
         self.screen_space = value;
         self
     }
+
+    pub fn decoration(mut self, value: TextDecoration) -> Self {
+        self.decoration = value;
+        self
+    }
+
+    pub fn overflow(mut self, value: TextOverflow) -> Self {
+        self.overflow = value;
+        self
+    }
+
+    /// Lays out text and its spans. Returns `None` when font of text is
+    /// not loaded. Spans with fonts that are not loaded are skipped.
+    /// Unless overflow is visible, trailing characters get dropped until
+    /// laid out text fits into `width` and `height` box.
+    pub fn layout(&self, context: &DrawContext) -> Option<Layout<Rgba<f32>>> {
+        let index = context.fonts.index_of(&self.font)?;
+        let mut styles = Vec::with_capacity(self.spans.len() + 1);
+        if !self.text.is_empty() {
+            styles.push(TextStyle {
+                text: self.text.as_ref(),
+                px: self.size,
+                font_index: index,
+                user_data: self.tint,
+            });
+        }
+        for span in &self.spans {
+            let font_index = match span.font.as_ref() {
+                Some(font) => match context.fonts.index_of(font) {
+                    Some(index) => index,
+                    None => continue,
+                },
+                None => index,
+            };
+            styles.push(TextStyle {
+                text: span.text.as_ref(),
+                px: span.size.unwrap_or(self.size),
+                font_index,
+                user_data: span.tint.unwrap_or(self.tint),
+            });
+        }
+        let layout = self.layout_styles(context, &styles);
+        if self.overflow == TextOverflow::Visible || self.fits(&layout) {
+            return Some(layout);
+        }
+        let count = styles
+            .iter()
+            .map(|style| style.text.chars().count())
+            .sum::<usize>();
+        // Binary search for longest prefix that still fits into the box.
+        let (mut min, mut max) = (0, count);
+        let mut result = None;
+        while min < max {
+            let limit = (min + max) / 2;
+            let layout = self.layout_truncated(context, &styles, limit);
+            if self.fits(&layout) {
+                min = limit + 1;
+                result = Some(layout);
+            } else {
+                max = limit;
+            }
+        }
+        Some(result.unwrap_or_else(|| self.layout_styles(context, &[])))
+    }
+
+    fn layout_styles(
+        &self,
+        context: &DrawContext,
+        styles: &[TextStyle<Rgba<f32>>],
+    ) -> Layout<Rgba<f32>> {
+        let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
+        layout.reset(&LayoutSettings {
+            x: 0.0,
+            y: 0.0,
+            max_width: self.width,
+            max_height: self.height,
+            horizontal_align: self.horizontal_align,
+            vertical_align: self.vertical_align,
+            ..Default::default()
+        });
+        for style in styles {
+            layout.append(context.fonts.values(), style);
+        }
+        layout
+    }
+
+    /// Lays out first `limit` characters, followed by ellipsis if enabled.
+    fn layout_truncated(
+        &self,
+        context: &DrawContext,
+        styles: &[TextStyle<Rgba<f32>>],
+        mut limit: usize,
+    ) -> Layout<Rgba<f32>> {
+        let mut truncated = Vec::with_capacity(styles.len() + 1);
+        for style in styles {
+            if limit == 0 {
+                break;
+            }
+            let end = style
+                .text
+                .char_indices()
+                .nth(limit)
+                .map(|(index, _)| index)
+                .unwrap_or(style.text.len());
+            limit -= style.text[..end].chars().count();
+            truncated.push(TextStyle {
+                text: &style.text[..end],
+                ..*style
+            });
+        }
+        if self.overflow == TextOverflow::Ellipsis {
+            if let Some(last) = truncated.last_mut() {
+                last.text = last.text.trim_end();
+            }
+            if let Some(last) = truncated.last().or(styles.first()) {
+                let text = if context.fonts.values()[last.font_index].has_glyph('\u{2026}') {
+                    "\u{2026}"
+                } else {
+                    "..."
+                };
+                let ellipsis = TextStyle { text, ..*last };
+                truncated.push(ellipsis);
+            }
+        }
+        self.layout_styles(context, &truncated)
+    }
+
+    fn fits(&self, layout: &Layout<Rgba<f32>>) -> bool {
+        const EPSILON: f32 = 1.0e-3;
+        layout
+            .glyphs()
+            .iter()
+            .filter(|glyph| glyph.width > 0 && glyph.height > 0)
+            .all(|glyph| {
+                let width = self
+                    .width
+                    .map(|width| {
+                        glyph.x >= -EPSILON && glyph.x + glyph.width as f32 <= width + EPSILON
+                    })
+                    .unwrap_or(true);
+                let height = self
+                    .height
+                    .map(|height| {
+                        glyph.y >= -EPSILON && glyph.y + glyph.height as f32 <= height + EPSILON
+                    })
+                    .unwrap_or(true);
+                width && height
+            })
+    }
+
+    /// Same as [`Self::layout`] paired with [`Self::measure`] bounds, but
+    /// reused from [`DrawContext::text_cache`] while text stays the same.
+    pub fn cached_layout(&self, context: &DrawContext) -> Option<CachedTextLayout> {
+        let key = TextLayoutKey::new(self);
+        context.text_cache().sync_fonts(&context.fonts);
+        if let Some(result) = context.text_cache().get(&key) {
+            return Some(result);
+        }
+        let layout = self.layout(context)?;
+        let bounds = TextRenderer::measure(context.fonts.values(), &layout, Default::default());
+        let layout = Rc::new(layout);
+        context.text_cache().insert(key, layout.clone(), bounds);
+        Some((layout, bounds))
+    }
+
+    /// Measures local bounds as `[x, y, width, height]`, covering lines of
+    /// mixed sizes.
+    pub fn measure(&self, context: &DrawContext) -> Option<[f32; 4]> {
+        self.cached_layout(context).map(|(_, bounds)| bounds)
+    }
 }
 
 impl Drawable for Text {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
-        if let Some(index) = context.fonts.index_of(&self.font) {
-            let mut layout = Layout::new(CoordinateSystem::PositiveYDown);
-            layout.reset(&LayoutSettings {
-                x: 0.0,
-                y: 0.0,
-                max_width: self.width,
-                max_height: self.height,
-                horizontal_align: self.horizontal_align,
-                vertical_align: self.vertical_align,
-                ..Default::default()
-            });
-            layout.append(
-                context.fonts.values(),
-                &TextStyle {
-                    text: &self.text,
-                    px: self.size,
-                    font_index: index,
-                    user_data: self.tint,
-                },
-            );
+        if let Some((layout, _)) = self.cached_layout(context) {
             context
                 .text_renderer
                 .include(context.fonts.values(), &layout);
@@ -192,12 +567,21 @@ impl Drawable for Text {
                     vec![]
                 },
                 blending: GlowBlending::Alpha,
-                scissor: Default::default(),
+                scissor: context.top_scissor(),
+                depth_test: None,
+                culling: None,
+                topology: Default::default(),
+                instances: None,
             });
             let transform = Mat4::from(context.top_transform()) * Mat4::from(self.transform);
             graphics.stream.transformed(
                 |stream| {
                     context.text_renderer.render_to_stream(stream);
+                    context.text_renderer.render_decorations_to_stream(
+                        &layout,
+                        self.decoration,
+                        stream,
+                    );
                 },
                 |vertex| {
                     let point = transform.mul_point(Vec2::from(vertex.position));
@@ -208,3 +592,151 @@ impl Drawable for Text {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fontdue::Font;
+
+    #[test]
+    fn test_spans() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("roboto", font);
+        let red = Rgba::new(1.0, 0.0, 0.0, 1.0);
+
+        let text = Text::default()
+            .font("roboto")
+            .size(20.0)
+            .text("ab")
+            .span(TextSpan::new("cd").tint(red).size(40.0))
+            .span(TextSpan::new("ef").font("missing"))
+            .span(TextSpan::new("g"));
+        let layout = text.layout(&context).unwrap();
+        let glyphs = layout.glyphs();
+        assert_eq!(
+            glyphs.iter().map(|glyph| glyph.parent).collect::<String>(),
+            "abcdg"
+        );
+        assert_eq!(glyphs[0].user_data, Rgba::white());
+        assert_eq!(glyphs[2].user_data, red);
+        assert_eq!(glyphs[3].key.px, 40.0);
+        assert_eq!(glyphs[4].key.px, 20.0);
+
+        let small = Text::default()
+            .font("roboto")
+            .size(20.0)
+            .text("abcdg")
+            .measure(&context)
+            .unwrap();
+        let mixed = text.measure(&context).unwrap();
+        assert!(mixed[2] > small[2]);
+        assert!(mixed[3] > small[3]);
+        assert!(Text::default().font("missing").measure(&context).is_none());
+    }
+
+    #[test]
+    fn test_layout_cache() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("roboto", font);
+        context.text_cache_mut().set_capacity(2);
+        let text = |value: &'static str| Text::default().font("roboto").text(value);
+
+        let (a, bounds) = text("a").cached_layout(&context).unwrap();
+        let (b, _) = text("a").cached_layout(&context).unwrap();
+        assert!(Rc::ptr_eq(&a, &b));
+        assert_eq!(text("a").measure(&context), Some(bounds));
+        let (c, _) = text("a").size(16.0).cached_layout(&context).unwrap();
+        assert!(!Rc::ptr_eq(&a, &c));
+        assert_eq!(context.text_cache().len(), 2);
+
+        text("a").measure(&context);
+        text("b").measure(&context);
+        assert_eq!(context.text_cache().len(), 2);
+        let (d, _) = text("a").cached_layout(&context).unwrap();
+        assert!(Rc::ptr_eq(&a, &d));
+        assert!(text("missing").font("missing").measure(&context).is_none());
+        assert_eq!(context.text_cache().len(), 2);
+
+        context.clear_text_cache();
+        assert!(context.text_cache().is_empty());
+    }
+
+    #[test]
+    fn test_layout_cache_fonts_change() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("a", font.clone());
+        context.fonts.insert("b", font.clone());
+        let text = Text::default().font("b").text("b");
+
+        let (cached, _) = text.cached_layout(&context).unwrap();
+        assert_eq!(cached.glyphs()[0].font_index, 1);
+        context.retain_resources(&["b"]);
+        let (layout, _) = text.cached_layout(&context).unwrap();
+        assert!(!Rc::ptr_eq(&cached, &layout));
+        assert_eq!(layout.glyphs()[0].font_index, 0);
+        context
+            .text_renderer
+            .include(context.fonts.values(), &layout);
+
+        let revision = context.fonts.revision();
+        assert!(context.fonts.remove("missing").is_none());
+        context.fonts.retain(|_, _| true);
+        assert_eq!(context.fonts.revision(), revision);
+        context.fonts.insert("a", font);
+        assert_ne!(context.fonts.revision(), revision);
+        assert!(context
+            .text_cache()
+            .get(&TextLayoutKey::new(&text))
+            .is_some());
+        text.cached_layout(&context).unwrap();
+        assert_eq!(context.text_cache().len(), 1);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let mut context = DrawContext::default();
+        context.fonts.insert("roboto", font);
+        let text = |overflow| {
+            Text::default()
+                .font("roboto")
+                .size(20.0)
+                .text("Hello wonderful")
+                .span(TextSpan::new(" world"))
+                .width(100.0)
+                .height(24.0)
+                .overflow(overflow)
+        };
+        let fits = |layout: &Layout<Rgba<f32>>| {
+            layout.glyphs().iter().all(|glyph| {
+                glyph.x + glyph.width as f32 <= 100.0 && glyph.y + glyph.height as f32 <= 24.0
+            })
+        };
+
+        let visible = text(TextOverflow::Visible).layout(&context).unwrap();
+        assert_eq!(visible.glyphs().len(), 21);
+        assert!(!fits(&visible));
+
+        let clip = text(TextOverflow::Clip).layout(&context).unwrap();
+        assert!(fits(&clip));
+        assert!(clip.glyphs().len() < 21);
+        let clipped = clip
+            .glyphs()
+            .iter()
+            .map(|glyph| glyph.parent)
+            .collect::<String>();
+        assert!("Hello wonderful world".starts_with(&clipped));
+
+        let ellipsis = text(TextOverflow::Ellipsis).layout(&context).unwrap();
+        assert!(fits(&ellipsis));
+        assert_eq!(ellipsis.glyphs().last().unwrap().parent, '\u{2026}');
+        assert!(ellipsis.glyphs().len() <= clip.glyphs().len() + 1);
+    }
+}
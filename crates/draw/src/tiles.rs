@@ -1,12 +1,14 @@
 use crate::{
     context::DrawContext,
+    particles::{stream_unit_quad, ParticleInstance, ParticleInstanceData},
     sprite::SpriteTexture,
-    utils::{Drawable, ShaderRef, Vertex},
+    utils::{Drawable, ResourceRef, ShaderRef, Vertex},
 };
 use smallvec::SmallVec;
+use spitfire_core::VertexStream;
 use spitfire_glow::{
     graphics::{Graphics, GraphicsBatch},
-    renderer::{GlowBlending, GlowUniformValue},
+    renderer::{GlowBlending, GlowInstances, GlowUniformValue},
 };
 use std::{
     borrow::Cow,
@@ -23,6 +25,10 @@ pub struct TileSetItem {
     pub tint: Rgba<f32>,
     pub size: Vec2<usize>,
     pub offset: Vec2<isize>,
+    /// Animation frames used instead of `region` when not empty.
+    pub regions: SmallVec<[Rect<f32, f32>; 4]>,
+    /// Animation frames per second.
+    pub fps: f32,
 }
 
 impl Default for TileSetItem {
@@ -33,6 +39,8 @@ impl Default for TileSetItem {
             tint: Rgba::white(),
             size: Vec2::new(1, 1),
             offset: Default::default(),
+            regions: Default::default(),
+            fps: 0.0,
         }
     }
 }
@@ -62,6 +70,25 @@ impl TileSetItem {
         self.offset = value;
         self
     }
+
+    pub fn animation(
+        mut self,
+        regions: impl IntoIterator<Item = Rect<f32, f32>>,
+        fps: f32,
+    ) -> Self {
+        self.regions = regions.into_iter().collect();
+        self.fps = fps;
+        self
+    }
+
+    /// Region of animation frame at given time, same for every instance.
+    pub fn frame_region(&self, time: f32) -> Rect<f32, f32> {
+        if self.regions.is_empty() {
+            return self.region;
+        }
+        let frame = (time * self.fps).floor().max(0.0) as usize;
+        self.regions[frame % self.regions.len()]
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -71,6 +98,13 @@ pub struct TileSet {
     pub uniforms: HashMap<Cow<'static, str>, GlowUniformValue>,
     pub blending: Option<GlowBlending>,
     pub mappings: HashMap<usize, TileSetItem>,
+    /// Texels to shrink each tile region by from every side, relative to
+    /// first texture size, to avoid sampling neighbor tiles with linear filtering.
+    pub texel_inset: f32,
+    /// Shader used to draw all tiles as single instanced batch, when
+    /// graphics supports instancing - see
+    /// [`spitfire_glow::graphics::Shader::TEXTURED_INSTANCED_VERTEX_2D`].
+    pub instanced_shader: Option<ShaderRef>,
 }
 
 impl TileSet {
@@ -101,6 +135,16 @@ impl TileSet {
         self
     }
 
+    pub fn texel_inset(mut self, value: f32) -> Self {
+        self.texel_inset = value;
+        self
+    }
+
+    pub fn instanced(mut self, shader: ShaderRef) -> Self {
+        self.instanced_shader = Some(shader);
+        self
+    }
+
     pub fn mapping(mut self, id: usize, item: TileSetItem) -> Self {
         self.mappings.insert(id, item);
         self
@@ -117,6 +161,8 @@ pub struct TilesEmitter {
     pub transform: Transform<f32, f32, f32>,
     pub tile_size: Vec2<f32>,
     pub screen_space: bool,
+    /// Time in seconds used to select frames of animated tiles.
+    pub time: f32,
 }
 
 impl TilesEmitter {
@@ -155,6 +201,11 @@ impl TilesEmitter {
         self
     }
 
+    pub fn time(mut self, value: f32) -> Self {
+        self.time = value;
+        self
+    }
+
     pub fn emit<'a, I: IntoIterator<Item = TileInstance>>(
         &'a self,
         set: &'a TileSet,
@@ -188,7 +239,16 @@ pub struct TilesDraw<'a, I: IntoIterator<Item = TileInstance>> {
 
 impl<I: IntoIterator<Item = TileInstance>> Drawable for TilesDraw<'_, I> {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>) {
-        let batch = GraphicsBatch {
+        let instanced_shader = self
+            .tileset
+            .instanced_shader
+            .as_ref()
+            .filter(|_| graphics.supports_instancing())
+            .and_then(|shader| match shader {
+                ResourceRef::Name(name) => context.shaders.get(name).cloned(),
+                ResourceRef::Object(object) => Some(object.to_owned()),
+            });
+        let mut batch = GraphicsBatch {
             shader: context.shader(self.tileset.shader.as_ref()),
             uniforms: self
                 .tileset
@@ -228,55 +288,70 @@ impl<I: IntoIterator<Item = TileInstance>> Drawable for TilesDraw<'_, I> {
                 .tileset
                 .blending
                 .unwrap_or_else(|| context.top_blending()),
-            scissor: None,
+            scissor: context.top_scissor(),
+            depth_test: None,
+            culling: None,
+            topology: Default::default(),
+            instances: None,
         };
-        graphics.stream.batch_optimized(batch);
+        let uv_inset = context
+            .texture(
+                self.tileset
+                    .textures
+                    .first()
+                    .map(|texture| &texture.texture),
+            )
+            .map(|texture| {
+                Vec2::new(
+                    self.tileset.texel_inset / texture.width().max(1) as f32,
+                    self.tileset.texel_inset / texture.height().max(1) as f32,
+                )
+            })
+            .unwrap_or_default();
         let transform = Mat4::from(context.top_transform()) * Mat4::from(self.emitter.transform);
+        if let Some(shader) = instanced_shader {
+            let instances = match self.instances.borrow_mut().take() {
+                Some(instances) => instances,
+                None => return,
+            };
+            let data = instances
+                .into_iter()
+                .filter_map(|instance| {
+                    Self::tile_instance(
+                        self.tileset,
+                        self.emitter.tile_size,
+                        uv_inset,
+                        self.emitter.time,
+                        instance,
+                    )
+                })
+                .map(|instance| ParticleInstanceData::new(&instance, transform))
+                .collect::<Vec<_>>();
+            if data.is_empty() {
+                return;
+            }
+            batch.shader = Some(shader);
+            batch.instances = Some(GlowInstances::new(&data));
+            graphics.stream.batch(batch);
+            stream_unit_quad(&mut graphics.stream);
+            graphics.stream.batch_end();
+            return;
+        }
+        graphics.stream.batch_optimized(batch);
         graphics.stream.transformed(
             move |stream| {
                 let instances = match self.instances.borrow_mut().take() {
                     Some(instances) => instances,
                     None => return,
                 };
-                for instance in instances {
-                    if let Some(tile) = self.tileset.mappings.get(&instance.id) {
-                        let offset = Vec2 {
-                            x: (instance.location.x as isize + tile.offset.x) as f32,
-                            y: (instance.location.y as isize + tile.offset.y) as f32,
-                        } * self.emitter.tile_size;
-                        let size = Vec2 {
-                            x: tile.size.x as f32,
-                            y: tile.size.y as f32,
-                        } * self.emitter.tile_size;
-                        let color = tile.tint.into_array();
-                        stream.quad([
-                            Vertex {
-                                position: [offset.x, offset.y],
-                                uv: [tile.region.x, tile.region.y, tile.page],
-                                color,
-                            },
-                            Vertex {
-                                position: [offset.x + size.x, offset.y],
-                                uv: [tile.region.x + tile.region.w, tile.region.y, tile.page],
-                                color,
-                            },
-                            Vertex {
-                                position: [offset.x + size.x, offset.y + size.y],
-                                uv: [
-                                    tile.region.x + tile.region.w,
-                                    tile.region.y + tile.region.h,
-                                    tile.page,
-                                ],
-                                color,
-                            },
-                            Vertex {
-                                position: [offset.x, offset.y + size.y],
-                                uv: [tile.region.x, tile.region.y + tile.region.h, tile.page],
-                                color,
-                            },
-                        ]);
-                    }
-                }
+                Self::stream_instances(
+                    stream,
+                    self.tileset,
+                    self.emitter.tile_size,
+                    uv_inset,
+                    self.emitter.time,
+                    instances,
+                );
             },
             |vertex| {
                 let point = transform.mul_point(Vec2::from(vertex.position));
@@ -287,6 +362,83 @@ impl<I: IntoIterator<Item = TileInstance>> Drawable for TilesDraw<'_, I> {
     }
 }
 
+impl<I: IntoIterator<Item = TileInstance>> TilesDraw<'_, I> {
+    /// Places tile as particle instance, so both streamed and instanced
+    /// paths share same tile layout.
+    fn tile_instance(
+        tileset: &TileSet,
+        tile_size: Vec2<f32>,
+        uv_inset: Vec2<f32>,
+        time: f32,
+        instance: TileInstance,
+    ) -> Option<ParticleInstance> {
+        let tile = tileset.mappings.get(&instance.id)?;
+        let offset = Vec2 {
+            x: (instance.location.x as isize + tile.offset.x) as f32,
+            y: (instance.location.y as isize + tile.offset.y) as f32,
+        } * tile_size;
+        let size = Vec2 {
+            x: tile.size.x as f32,
+            y: tile.size.y as f32,
+        } * tile_size;
+        let region = tile.frame_region(time);
+        let region = Rect {
+            x: region.x + uv_inset.x,
+            y: region.y + uv_inset.y,
+            w: region.w - uv_inset.x * 2.0,
+            h: region.h - uv_inset.y * 2.0,
+        };
+        Some(
+            ParticleInstance::default()
+                .region_page(region, tile.page)
+                .tint(tile.tint)
+                .position(offset)
+                .size(size),
+        )
+    }
+
+    fn stream_instances(
+        stream: &mut VertexStream<Vertex, GraphicsBatch>,
+        tileset: &TileSet,
+        tile_size: Vec2<f32>,
+        uv_inset: Vec2<f32>,
+        time: f32,
+        instances: I,
+    ) {
+        for instance in instances.into_iter().filter_map(|instance| {
+            Self::tile_instance(tileset, tile_size, uv_inset, time, instance)
+        }) {
+            let offset = instance.transform.position;
+            let size = instance.size;
+            let region = instance.region;
+            let page = instance.page;
+            let color = instance.tint.into_array();
+            stream.quad([
+                Vertex {
+                    position: [offset.x, offset.y],
+                    uv: [region.x, region.y, page],
+                    color,
+                },
+                Vertex {
+                    position: [offset.x + size.x, offset.y],
+                    uv: [region.x + region.w, region.y, page],
+                    color,
+                },
+                Vertex {
+                    position: [offset.x + size.x, offset.y + size.y],
+                    uv: [region.x + region.w, region.y + region.h, page],
+                    color,
+                },
+                Vertex {
+                    position: [offset.x, offset.y + size.y],
+                    uv: [region.x, region.y + region.h, page],
+                    color,
+                },
+            ]);
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TileMap {
     pub include_ids: HashSet<usize>,
@@ -338,7 +490,7 @@ impl TileMap {
     pub fn location(&self, index: usize) -> Vec2<usize> {
         Vec2 {
             x: index % self.size.x,
-            y: (index / self.size.y) % self.size.y,
+            y: (index / self.size.x) % self.size.y,
         }
     }
 
@@ -431,3 +583,350 @@ impl<T: Into<Vec2<usize>>> IndexMut<T> for TileMap {
             .unwrap_or_else(|| panic!("Invalid location: {}", location))
     }
 }
+
+/// Width and height of single [`ChunkedTileMap`] chunk, in tiles.
+pub const TILE_CHUNK_SIZE: usize = 16;
+
+/// Sparse tile map that allocates tiles in square chunks only where they
+/// were set, so it can span negative and unbounded coordinates.
+#[derive(Debug, Clone)]
+pub struct ChunkedTileMap {
+    pub include_ids: HashSet<usize>,
+    pub exclude_ids: HashSet<usize>,
+    fill_id: usize,
+    chunks: HashMap<Vec2<i32>, Box<[usize; TILE_CHUNK_SIZE * TILE_CHUNK_SIZE]>>,
+}
+
+impl ChunkedTileMap {
+    /// `fill_id` is used for tiles of newly allocated chunks.
+    pub fn new(fill_id: usize) -> Self {
+        Self {
+            include_ids: Default::default(),
+            exclude_ids: Default::default(),
+            fill_id,
+            chunks: Default::default(),
+        }
+    }
+
+    pub fn fill_id(&self) -> usize {
+        self.fill_id
+    }
+
+    /// Returns chunk location and index of tile within that chunk.
+    pub fn chunk_location(location: impl Into<Vec2<i32>>) -> (Vec2<i32>, usize) {
+        let location = location.into();
+        let size = TILE_CHUNK_SIZE as i32;
+        let chunk = Vec2::new(location.x.div_euclid(size), location.y.div_euclid(size));
+        let local = Vec2::new(location.x.rem_euclid(size), location.y.rem_euclid(size));
+        (chunk, local.y as usize * TILE_CHUNK_SIZE + local.x as usize)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = Vec2<i32>> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn has_chunk(&self, chunk: impl Into<Vec2<i32>>) -> bool {
+        self.chunks.contains_key(&chunk.into())
+    }
+
+    pub fn remove_chunk(&mut self, chunk: impl Into<Vec2<i32>>) -> bool {
+        self.chunks.remove(&chunk.into()).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Returns `None` for tiles of chunks that were never populated.
+    pub fn get(&self, location: impl Into<Vec2<i32>>) -> Option<usize> {
+        let (chunk, index) = Self::chunk_location(location);
+        self.chunks.get(&chunk).map(|tiles| tiles[index])
+    }
+
+    pub fn set(&mut self, location: impl Into<Vec2<i32>>, id: usize) {
+        let (chunk, index) = Self::chunk_location(location);
+        let fill_id = self.fill_id;
+        self.chunks
+            .entry(chunk)
+            .or_insert_with(|| Box::new([fill_id; TILE_CHUNK_SIZE * TILE_CHUNK_SIZE]))[index] = id;
+    }
+
+    pub fn fill(&mut self, from: impl Into<Vec2<i32>>, to: impl Into<Vec2<i32>>, id: usize) {
+        let from = from.into();
+        let to = to.into();
+        for y in from.y..to.y {
+            for x in from.x..to.x {
+                self.set(Vec2::new(x, y), id);
+            }
+        }
+    }
+
+    pub fn is_id_valid(&self, id: usize) -> bool {
+        (self.include_ids.is_empty() || self.include_ids.contains(&id))
+            && (self.exclude_ids.is_empty() || !self.exclude_ids.contains(&id))
+    }
+
+    /// Location of top-left tile of top-left populated chunk. Emitted tile
+    /// instances are relative to it, so tiles emitter should be positioned
+    /// at `origin * tile_size` to place tiles at their world locations.
+    pub fn origin(&self) -> Vec2<i32> {
+        self.chunks
+            .keys()
+            .copied()
+            .reduce(Vec2::partial_min)
+            .unwrap_or_default()
+            * TILE_CHUNK_SIZE as i32
+    }
+
+    /// Yields tile instances of populated chunks only, relative to [`Self::origin`].
+    pub fn emit(&self) -> impl Iterator<Item = TileInstance> + '_ {
+        let origin = self.origin();
+        self.chunks.iter().flat_map(move |(chunk, tiles)| {
+            let base = *chunk * TILE_CHUNK_SIZE as i32 - origin;
+            tiles.iter().enumerate().filter_map(move |(index, id)| {
+                if self.is_id_valid(*id) {
+                    Some(TileInstance {
+                        id: *id,
+                        location: Vec2 {
+                            x: (base.x + (index % TILE_CHUNK_SIZE) as i32) as usize,
+                            y: (base.y + (index / TILE_CHUNK_SIZE) as i32) as usize,
+                        },
+                    })
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+impl Default for ChunkedTileMap {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_texel_inset() {
+        let tileset = TileSet::default().mapping(
+            1,
+            TileSetItem::default().region(Rect::new(0.25, 0.5, 0.25, 0.5)),
+        );
+        let instances = [TileInstance::new(1, Vec2::new(0, 0))];
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        TilesDraw::stream_instances(
+            &mut stream,
+            &tileset,
+            16.0.into(),
+            0.0.into(),
+            0.0,
+            instances,
+        );
+        TilesDraw::stream_instances(
+            &mut stream,
+            &tileset,
+            16.0.into(),
+            Vec2::new(0.01, 0.02),
+            0.0,
+            instances,
+        );
+        let uvs = stream
+            .vertices()
+            .iter()
+            .map(|vertex| [vertex.uv[0], vertex.uv[1]])
+            .collect::<Vec<_>>();
+        assert_eq!(uvs[0], [0.25, 0.5]);
+        assert_eq!(uvs[2], [0.5, 1.0]);
+        for (plain, inset) in uvs[0..4].iter().zip(&uvs[4..8]) {
+            let dx = inset[0] - plain[0];
+            let dy = inset[1] - plain[1];
+            assert!((dx.abs() - 0.01).abs() < 1.0e-6);
+            assert!((dy.abs() - 0.02).abs() < 1.0e-6);
+        }
+        assert!((uvs[4][0] - 0.26).abs() < 1.0e-6);
+        assert!((uvs[6][0] - 0.49).abs() < 1.0e-6);
+        assert!((uvs[4][1] - 0.52).abs() < 1.0e-6);
+        assert!((uvs[6][1] - 0.98).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn test_instanced_tiles() {
+        let tileset = TileSet::default()
+            .mapping(
+                1,
+                TileSetItem::default()
+                    .region(Rect::new(0.25, 0.5, 0.25, 0.5))
+                    .page(2.0)
+                    .size(Vec2::new(2, 1))
+                    .offset(Vec2::new(-1, 0)),
+            )
+            .mapping(2, TileSetItem::default());
+        let instances = [
+            TileInstance::new(1, Vec2::new(3, 2)),
+            TileInstance::new(3, Vec2::new(0, 0)),
+            TileInstance::new(2, Vec2::new(1, 1)),
+        ];
+        let parent = Mat4::translation_2d(Vec2::new(100.0, 50.0));
+        let data = instances
+            .into_iter()
+            .filter_map(|instance| {
+                TilesDraw::<[TileInstance; 3]>::tile_instance(
+                    &tileset,
+                    16.0.into(),
+                    Vec2::new(0.01, 0.02),
+                    0.0,
+                    instance,
+                )
+            })
+            .map(|instance| ParticleInstanceData::new(&instance, parent))
+            .collect::<Vec<_>>();
+        let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+        TilesDraw::stream_instances(
+            &mut stream,
+            &tileset,
+            16.0.into(),
+            Vec2::new(0.01, 0.02),
+            0.0,
+            instances,
+        );
+
+        assert_eq!(data.len(), 2);
+        assert_eq!(stream.vertices().len(), data.len() * 4);
+        assert_eq!(data[0].page, 2.0);
+        for (quad, data) in stream.vertices().chunks(4).zip(&data) {
+            let [x, y, w, h] = data.region;
+            assert_eq!([x, y], [quad[0].uv[0], quad[0].uv[1]]);
+            assert!((x + w - quad[2].uv[0]).abs() < 1.0e-6);
+            assert!((y + h - quad[2].uv[1]).abs() < 1.0e-6);
+            for (vertex, unit) in quad
+                .iter()
+                .zip([[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]])
+            {
+                let point = [unit[0], unit[1], 1.0];
+                let dot = |row: [f32; 3]| row.iter().zip(point).map(|(a, b)| a * b).sum::<f32>();
+                let position = parent.mul_point(Vec2::from(vertex.position));
+                assert_eq!(
+                    [position.x, position.y],
+                    [dot(data.transform_x), dot(data.transform_y)]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_tilemap_index_location() {
+        let size = Vec2::new(5, 3);
+        let mut map = TileMap::new(size, 0);
+        for index in 0..(size.x * size.y) {
+            let location = map.location(index);
+            assert!(location.x < size.x && location.y < size.y);
+            assert_eq!(map.index(location), index);
+        }
+        assert_eq!(map.index(Vec2::new(3, 1)), 8);
+        assert_eq!(map.location(8), Vec2::new(3, 1));
+
+        map.set(Vec2::new(4, 0), 1);
+        map.set(Vec2::new(0, 2), 2);
+        map[Vec2::new(2, 1)] = 3;
+        assert_eq!(map.buffer()[4], 1);
+        assert_eq!(map.buffer()[10], 2);
+        assert_eq!(map.buffer()[7], 3);
+        assert_eq!(map.get(Vec2::new(4, 0)), Some(1));
+        assert_eq!(map.get(Vec2::new(0, 2)), Some(2));
+        assert_eq!(map[Vec2::new(2, 1)], 3);
+        assert_eq!(map.buffer().iter().filter(|id| **id != 0).count(), 3);
+    }
+
+    #[test]
+    fn test_animated_tiles() {
+        let frames = [
+            Rect::new(0.0, 0.0, 0.5, 0.5),
+            Rect::new(0.5, 0.0, 0.5, 0.5),
+            Rect::new(0.0, 0.5, 0.5, 0.5),
+        ];
+        let tileset = TileSet::default()
+            .mapping(1, TileSetItem::default().animation(frames, 4.0))
+            .mapping(2, TileSetItem::default());
+        let instances = [
+            TileInstance::new(1, Vec2::new(0, 0)),
+            TileInstance::new(2, Vec2::new(1, 0)),
+            TileInstance::new(1, Vec2::new(5, 3)),
+        ];
+        let uvs = |time: f32| {
+            let mut stream = VertexStream::<Vertex, GraphicsBatch>::default();
+            TilesDraw::stream_instances(
+                &mut stream,
+                &tileset,
+                16.0.into(),
+                0.0.into(),
+                time,
+                instances,
+            );
+            stream
+                .vertices()
+                .chunks(4)
+                .map(|quad| [quad[0].uv[0], quad[0].uv[1]])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(uvs(0.0), vec![[0.0, 0.0], [0.0, 0.0], [0.0, 0.0]]);
+        assert_eq!(uvs(0.3), vec![[0.5, 0.0], [0.0, 0.0], [0.5, 0.0]]);
+        assert_eq!(uvs(0.5), vec![[0.0, 0.5], [0.0, 0.0], [0.0, 0.5]]);
+        assert_eq!(uvs(0.8), uvs(0.0));
+        assert_eq!(
+            tileset.mappings[&2].frame_region(10.0),
+            Rect::new(0.0, 0.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_chunked_tilemap() {
+        let mut map = ChunkedTileMap::default();
+        assert_eq!(map.get(Vec2::new(0, 0)), None);
+        assert_eq!(map.emit().count(), 0);
+        assert_eq!(
+            ChunkedTileMap::chunk_location(Vec2::new(-1, 17)),
+            (Vec2::new(-1, 1), TILE_CHUNK_SIZE * 2 - 1)
+        );
+
+        map.set(Vec2::new(-1, -1), 1);
+        map.set(Vec2::new(100, 3), 2);
+        assert_eq!(map.chunks_count(), 2);
+        assert_eq!(map.get(Vec2::new(-1, -1)), Some(1));
+        assert_eq!(map.get(Vec2::new(-2, -1)), Some(0));
+        assert_eq!(map.get(Vec2::new(100, 3)), Some(2));
+        assert_eq!(map.get(Vec2::new(50, 3)), None);
+        assert_eq!(map.origin(), Vec2::new(-16, -16));
+
+        map.exclude_ids.insert(0);
+        let origin = map.origin();
+        let mut instances = map
+            .emit()
+            .map(|instance| {
+                (
+                    instance.id,
+                    Vec2::new(
+                        instance.location.x as i32 + origin.x,
+                        instance.location.y as i32 + origin.y,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+        instances.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            instances,
+            vec![(1, Vec2::new(-1, -1)), (2, Vec2::new(100, 3))]
+        );
+
+        assert!(map.remove_chunk(Vec2::new(-1, -1)));
+        assert_eq!(map.origin(), Vec2::new(96, 0));
+        assert_eq!(map.emit().next().unwrap().location, Vec2::new(4, 3));
+    }
+}
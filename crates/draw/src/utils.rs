@@ -61,6 +61,82 @@ impl TextVertex<Rgba<f32>> for Vertex {
     }
 }
 
+/// Vertex with second UV set, for detail textures and lightmaps.
+/// Works with [`Shader::DUAL_TEXTURED_VERTEX_2D`] shaders.
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+#[repr(C)]
+pub struct Vertex2UV {
+    pub position: [f32; 2],
+    pub uv: [f32; 3],
+    pub uv2: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl Default for Vertex2UV {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            uv: Default::default(),
+            uv2: Default::default(),
+            color: [1.0, 1.0, 1.0, 1.0],
+        }
+    }
+}
+
+impl From<Vertex> for Vertex2UV {
+    fn from(value: Vertex) -> Self {
+        Self {
+            position: value.position,
+            uv: value.uv,
+            uv2: value.uv,
+            color: value.color,
+        }
+    }
+}
+
+impl GlowVertexAttribs for Vertex2UV {
+    const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)] = &[
+        (
+            "a_position",
+            GlowVertexAttrib::Float {
+                channels: 2,
+                normalized: false,
+            },
+        ),
+        (
+            "a_uv",
+            GlowVertexAttrib::Float {
+                channels: 3,
+                normalized: false,
+            },
+        ),
+        (
+            "a_uv2",
+            GlowVertexAttrib::Float {
+                channels: 3,
+                normalized: false,
+            },
+        ),
+        (
+            "a_color",
+            GlowVertexAttrib::Float {
+                channels: 4,
+                normalized: false,
+            },
+        ),
+    ];
+}
+
+impl TextVertex<Rgba<f32>> for Vertex2UV {
+    /// Glyph coords go to both UV sets.
+    fn apply(&mut self, position: [f32; 2], tex_coord: [f32; 3], user_data: Rgba<f32>) {
+        self.position = position;
+        self.uv = tex_coord;
+        self.uv2 = tex_coord;
+        self.color = user_data.into_array();
+    }
+}
+
 pub trait Drawable {
     fn draw(&self, context: &mut DrawContext, graphics: &mut Graphics<Vertex>);
 }
@@ -94,11 +170,13 @@ pub type TextureRef = ResourceRef<Texture>;
 pub struct FontMap {
     keys: Vec<Cow<'static, str>>,
     values: Vec<Font>,
+    revision: usize,
 }
 
 impl FontMap {
     pub fn insert(&mut self, name: impl Into<Cow<'static, str>>, font: Font) {
         let name = name.into();
+        self.revision += 1;
         if let Some(index) = self.index_of(&name) {
             self.values[index] = font;
         } else {
@@ -109,6 +187,7 @@ impl FontMap {
 
     pub fn remove(&mut self, name: &str) -> Option<Font> {
         if let Some(index) = self.index_of(name) {
+            self.revision += 1;
             self.keys.remove(index);
             Some(self.values.remove(index))
         } else {
@@ -116,6 +195,25 @@ impl FontMap {
         }
     }
 
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &Font) -> bool) {
+        let mut index = 0;
+        while index < self.keys.len() {
+            if f(&self.keys[index], &self.values[index]) {
+                index += 1;
+            } else {
+                self.revision += 1;
+                self.keys.remove(index);
+                self.values.remove(index);
+            }
+        }
+    }
+
+    /// Changes whenever fonts get inserted or removed, which also shifts
+    /// font indices stored in text layouts.
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
     pub fn index_of(&self, name: &str) -> Option<usize> {
         self.keys.iter().position(|key| key == name)
     }
@@ -1,12 +1,15 @@
 use bytemuck::Pod;
-use etagere::{euclid::default::Rect, size2, AtlasAllocator};
+use etagere::{
+    euclid::default::{Point2D, Rect},
+    size2, AllocId, AtlasAllocator,
+};
 use fontdue::{
     layout::{GlyphPosition, GlyphRasterConfig, Layout},
     Font,
 };
 use spitfire_core::VertexStream;
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{HashMap, HashSet},
     marker::PhantomData,
 };
 
@@ -20,8 +23,89 @@ pub struct TextRendererGlyph {
     pub rectangle: Rect<u32>,
 }
 
+/// What happens when glyph does not fit into atlas pages and
+/// [`TextRenderer::max_pages`] limit is reached.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextRendererOverflow {
+    /// Adds new page anyway, treating limit as a hint only.
+    #[default]
+    AddPage,
+    /// Frees least recently used glyphs until new glyph fits.
+    Evict,
+    /// Skips rasterizing glyph, so it renders empty.
+    Fail,
+}
+
+/// Controls which trailing parts of laid out text are counted by
+/// [`TextRenderer::measure`].
+///
+/// By default measured extents cover only what gets drawn: trailing
+/// whitespace of each line does not add to the width and lines with no
+/// visible glyphs at the end of text do not add to the height.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextMeasureSettings {
+    /// Include advance of whitespace at the end of each line in the width.
+    pub trailing_whitespace: bool,
+    /// Include blank lines at the end of text in the height, including the
+    /// empty line started by final new line character.
+    pub trailing_blank_lines: bool,
+}
+
+impl TextMeasureSettings {
+    pub fn trailing_whitespace(mut self, value: bool) -> Self {
+        self.trailing_whitespace = value;
+        self
+    }
+
+    pub fn trailing_blank_lines(mut self, value: bool) -> Self {
+        self.trailing_blank_lines = value;
+        self
+    }
+}
+
+/// Selects lines drawn by [`TextRenderer::render_decorations_to_stream`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl TextDecoration {
+    pub fn underline(mut self, value: bool) -> Self {
+        self.underline = value;
+        self
+    }
+
+    pub fn strikethrough(mut self, value: bool) -> Self {
+        self.strikethrough = value;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.underline && !self.strikethrough
+    }
+}
+
+/// Identifies rasterized glyph variant in atlas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextRendererGlyphKey {
+    pub config: GlyphRasterConfig,
+    /// Horizontal offset in [`TextRenderer::SUBPIXEL_STEPS`] fractions of
+    /// pixel. Always 0 when sub-pixel positioning is disabled.
+    pub subpixel: u8,
+}
+
+impl From<GlyphRasterConfig> for TextRendererGlyphKey {
+    fn from(config: GlyphRasterConfig) -> Self {
+        Self {
+            config,
+            subpixel: 0,
+        }
+    }
+}
+
 pub struct TextRendererUnpacked<UD: Copy> {
-    pub glyphs: HashMap<GlyphRasterConfig, TextRendererGlyph>,
+    pub glyphs: HashMap<TextRendererGlyphKey, TextRendererGlyph>,
     pub atlas_size: [usize; 3],
     pub image: Vec<u8>,
     pub renderables: Vec<GlyphPosition<UD>>,
@@ -30,11 +114,29 @@ pub struct TextRendererUnpacked<UD: Copy> {
 #[derive(Clone)]
 pub struct TextRenderer<UD: Copy = ()> {
     pub renderables_resize: usize,
-    used_glyphs: HashMap<GlyphRasterConfig, TextRendererGlyph>,
+    pub max_pages: Option<usize>,
+    pub overflow: TextRendererOverflow,
+    /// When enabled, glyphs keep fractional horizontal position and each of
+    /// [`Self::SUBPIXEL_STEPS`] offsets gets its own atlas entry, which gives
+    /// smoother spacing at cost of bigger atlas. Disabled by default, so
+    /// glyphs snap to whole pixels and are rasterized once.
+    pub subpixel: bool,
+    /// Empty pixels kept between glyphs in atlas, so filtered sampling near
+    /// glyph edges does not pick up neighbor glyphs.
+    pub padding: usize,
+    sdf_spread: Option<usize>,
+    /// Fully covered atlas area used by decoration quads.
+    solid: Option<TextRendererGlyph>,
+    /// Area of each page written since last [`Self::take_dirty_rect`].
+    dirty: Vec<Option<Rect<u32>>>,
+    used_glyphs: HashMap<TextRendererGlyphKey, TextRendererGlyph>,
+    /// {key: (page, allocation, last used)}
+    allocations: HashMap<TextRendererGlyphKey, (usize, AllocId, usize)>,
+    usage_counter: usize,
     atlas_size: [usize; 3],
     image: Vec<u8>,
     atlases: Vec<AtlasAllocator>,
-    ready_to_render: Vec<GlyphPosition<UD>>,
+    ready_to_render: Vec<(GlyphPosition<UD>, TextRendererGlyphKey)>,
     _phantom: PhantomData<fn() -> UD>,
 }
 
@@ -45,10 +147,21 @@ impl<UD: Copy> Default for TextRenderer<UD> {
 }
 
 impl<UD: Copy> TextRenderer<UD> {
+    pub const SUBPIXEL_STEPS: u8 = 4;
+
     pub fn new(width: usize, height: usize) -> Self {
         Self {
             renderables_resize: 1024,
+            max_pages: None,
+            overflow: Default::default(),
+            subpixel: false,
+            padding: 1,
+            sdf_spread: None,
+            solid: None,
+            dirty: Default::default(),
             used_glyphs: Default::default(),
+            allocations: Default::default(),
+            usage_counter: 0,
             atlas_size: [width, height, 0],
             image: Default::default(),
             atlases: Default::default(),
@@ -57,8 +170,68 @@ impl<UD: Copy> TextRenderer<UD> {
         }
     }
 
+    /// Creates renderer that packs signed distance fields of glyphs instead
+    /// of their coverage. Each glyph gets padded by `spread` pixels on every
+    /// side and atlas values map distance range of `-spread..spread` pixels
+    /// to `0..255`, with glyph edge at 128. Such atlas needs fragment shader
+    /// that thresholds distance, like `spitfire_glow`'s
+    /// `Shader::TEXT_SDF_FRAGMENT`, to stay crisp at any scale.
+    pub fn new_sdf(width: usize, height: usize, spread: usize) -> Self {
+        let mut result = Self::new(width, height);
+        result.sdf_spread = Some(spread.max(1));
+        result
+    }
+
+    /// Distance range in pixels when renderer packs signed distance fields.
+    pub fn sdf_spread(&self) -> Option<usize> {
+        self.sdf_spread
+    }
+
+    pub fn max_pages(mut self, value: Option<usize>) -> Self {
+        self.max_pages = value;
+        self
+    }
+
+    pub fn overflow(mut self, value: TextRendererOverflow) -> Self {
+        self.overflow = value;
+        self
+    }
+
+    /// Caps atlas pages count and evicts least recently used glyphs when
+    /// all pages are full. Pages already allocated over the limit are kept.
+    pub fn set_page_limit(&mut self, limit: usize) {
+        self.max_pages = Some(limit);
+        self.overflow = TextRendererOverflow::Evict;
+    }
+
+    /// Marks glyph as recently used, so it is evicted after other glyphs.
+    /// Returns false if glyph is not in atlas.
+    pub fn touch(&mut self, key: &TextRendererGlyphKey) -> bool {
+        if let Some((_, _, last_used)) = self.allocations.get_mut(key) {
+            self.usage_counter += 1;
+            *last_used = self.usage_counter;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn subpixel(mut self, value: bool) -> Self {
+        self.subpixel = value;
+        self
+    }
+
+    pub fn padding(mut self, value: usize) -> Self {
+        self.padding = value;
+        self
+    }
+
     pub fn clear(&mut self) {
         self.used_glyphs.clear();
+        self.allocations.clear();
+        self.usage_counter = 0;
+        self.solid = None;
+        self.dirty.clear();
         self.atlas_size[2] = 0;
         self.image.clear();
         self.atlases.clear();
@@ -66,56 +239,39 @@ impl<UD: Copy> TextRenderer<UD> {
     }
 
     pub fn include(&mut self, fonts: &[Font], layout: &Layout<UD>) {
+        self.usage_counter += 1;
         for glyph in layout.glyphs() {
+            let (glyph, key) = self.snap(*glyph);
             if glyph.char_data.rasterize() {
                 if self.ready_to_render.len() == self.ready_to_render.capacity() {
                     self.ready_to_render.reserve(self.renderables_resize);
                 }
-                self.ready_to_render.push(*glyph);
+                self.ready_to_render.push((glyph, key));
+            }
+            if let Some((_, _, last_used)) = self.allocations.get_mut(&key) {
+                *last_used = self.usage_counter;
             }
-            if let Entry::Vacant(entry) = self.used_glyphs.entry(glyph.key) {
+            if !self.used_glyphs.contains_key(&key) {
                 let font = &fonts[glyph.font_index];
-                let (metrics, coverage) = font.rasterize_config(glyph.key);
+                let (mut metrics, mut coverage) = font.rasterize_config(glyph.key);
+                if key.subpixel > 0 {
+                    coverage = Self::shift_coverage(
+                        &coverage,
+                        metrics.width,
+                        key.subpixel as f32 / Self::SUBPIXEL_STEPS as f32,
+                    );
+                    metrics.width += 1;
+                }
+                if let Some(spread) = self.sdf_spread {
+                    coverage =
+                        Self::distance_field(&coverage, metrics.width, metrics.height, spread);
+                    metrics.width += spread * 2;
+                    metrics.height += spread * 2;
+                }
                 if glyph.char_data.rasterize() {
-                    let allocation = self
-                        .atlases
-                        .iter_mut()
-                        .enumerate()
-                        .find_map(|(page, atlas)| {
-                            Some((
-                                page,
-                                atlas
-                                    .allocate(size2(
-                                        metrics.width as i32 + 1,
-                                        metrics.height as i32 + 1,
-                                    ))?
-                                    .rectangle
-                                    .to_rect()
-                                    .origin
-                                    .to_u32(),
-                            ))
-                        })
-                        .or_else(|| {
-                            let w = self.atlas_size[0];
-                            let h = self.atlas_size[1];
-                            let mut atlas = AtlasAllocator::new(size2(w as _, h as _));
-                            let page = self.atlases.len();
-                            let origin = atlas
-                                .allocate(size2(
-                                    metrics.width as i32 + 1,
-                                    metrics.height as i32 + 1,
-                                ))?
-                                .rectangle
-                                .to_rect()
-                                .origin
-                                .to_u32();
-                            self.atlases.push(atlas);
-                            self.atlas_size[2] += 1;
-                            let [w, h, d] = self.atlas_size;
-                            self.image.resize(w * h * d, 0);
-                            Some((page, origin))
-                        });
-                    if let Some((page, origin)) = allocation {
+                    let allocation =
+                        self.allocate(metrics.width + self.padding, metrics.height + self.padding);
+                    if let Some((page, id, origin)) = allocation {
                         let [w, h, _] = self.atlas_size;
                         for (index, value) in coverage.iter().enumerate() {
                             let x = origin.x as usize + index % metrics.width;
@@ -123,17 +279,222 @@ impl<UD: Copy> TextRenderer<UD> {
                             let index = page * w * h + y * w + x;
                             self.image[index] = *value;
                         }
-                        entry.insert(TextRendererGlyph {
-                            page,
-                            rectangle: Rect::new(
-                                origin,
-                                [metrics.width as _, metrics.height as _].into(),
-                            ),
-                        });
+                        let rectangle =
+                            Rect::new(origin, [metrics.width as _, metrics.height as _].into());
+                        self.mark_dirty(page, rectangle);
+                        self.used_glyphs
+                            .insert(key, TextRendererGlyph { page, rectangle });
+                        self.allocations.insert(key, (page, id, self.usage_counter));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves glyph to whole pixel position, leaving quantized fractional
+    /// horizontal part in key when sub-pixel positioning is enabled.
+    fn snap(&self, mut glyph: GlyphPosition<UD>) -> (GlyphPosition<UD>, TextRendererGlyphKey) {
+        let mut key = TextRendererGlyphKey::from(glyph.key);
+        glyph.y = glyph.y.round();
+        if self.subpixel {
+            let steps = Self::SUBPIXEL_STEPS as f32;
+            let x = glyph.x.floor();
+            let step = ((glyph.x - x) * steps).round();
+            if step >= steps {
+                glyph.x = x + 1.0;
+            } else {
+                glyph.x = x;
+                key.subpixel = step as u8;
+            }
+        } else {
+            glyph.x = glyph.x.round();
+        }
+        (glyph, key)
+    }
+
+    /// Resamples coverage shifted right by fraction of pixel, into bitmap
+    /// one pixel wider.
+    fn shift_coverage(coverage: &[u8], width: usize, offset: f32) -> Vec<u8> {
+        if width == 0 {
+            return Default::default();
+        }
+        coverage
+            .chunks_exact(width)
+            .flat_map(|row| {
+                (0..=width).map(move |x| {
+                    let current = row.get(x).copied().unwrap_or_default() as f32;
+                    let previous = x
+                        .checked_sub(1)
+                        .and_then(|x| row.get(x))
+                        .copied()
+                        .unwrap_or_default() as f32;
+                    (current * (1.0 - offset) + previous * offset).round() as u8
+                })
+            })
+            .collect()
+    }
+
+    /// Converts coverage into signed distance field padded by `spread`
+    /// pixels on every side, positive distances being inside of glyph.
+    fn distance_field(coverage: &[u8], width: usize, height: usize, spread: usize) -> Vec<u8> {
+        let spread = spread as isize;
+        let result_width = width as isize + spread * 2;
+        let result_height = height as isize + spread * 2;
+        let inside = |x: isize, y: isize| {
+            let x = x - spread;
+            let y = y - spread;
+            x >= 0
+                && y >= 0
+                && x < width as isize
+                && y < height as isize
+                && coverage[y as usize * width + x as usize] >= 128
+        };
+        (0..result_height)
+            .flat_map(|y| (0..result_width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let state = inside(x, y);
+                let mut nearest = (spread * spread) as f32 * 2.0;
+                for dy in -spread..=spread {
+                    for dx in -spread..=spread {
+                        if inside(x + dx, y + dy) != state {
+                            nearest = nearest.min((dx * dx + dy * dy) as f32);
+                        }
                     }
                 }
+                // Edge lies between pixel centers.
+                let distance = (nearest.sqrt() - 0.5).min(spread as f32);
+                let distance = if state { distance } else { -distance };
+                ((0.5 + distance / (spread as f32 * 2.0)) * 255.0)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            })
+            .collect()
+    }
+
+    /// Moves and resizes glyph to cover its atlas entry, including
+    /// distance field padding.
+    fn fit(
+        spread: Option<usize>,
+        mut glyph: GlyphPosition<UD>,
+        data: &TextRendererGlyph,
+    ) -> GlyphPosition<UD> {
+        if let Some(spread) = spread {
+            glyph.x -= spread as f32;
+            glyph.y -= spread as f32;
+            glyph.height = data.rectangle.height() as _;
+        }
+        glyph.width = data.rectangle.width() as _;
+        glyph
+    }
+
+    fn mark_dirty(&mut self, page: usize, rectangle: Rect<u32>) {
+        if self.dirty.len() <= page {
+            self.dirty.resize(page + 1, None);
+        }
+        let dirty = &mut self.dirty[page];
+        *dirty = Some(match dirty {
+            Some(dirty) => dirty.union(&rectangle),
+            None => rectangle,
+        });
+    }
+
+    /// Takes area of atlas page modified since last call, so only that part
+    /// of texture has to be uploaded. Call repeatedly until it returns
+    /// `None` to get all modified pages. When atlas depth changed, whole
+    /// texture has to be uploaded anyway.
+    pub fn take_dirty_rect(&mut self) -> Option<(usize, Rect<u32>)> {
+        self.dirty
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, dirty)| Some((page, dirty.take()?)))
+    }
+
+    /// Copies pixels of atlas page area, row by row.
+    pub fn image_region(&self, page: usize, rectangle: Rect<u32>) -> Vec<u8> {
+        let [w, h, _] = self.atlas_size;
+        (rectangle.min_y()..rectangle.max_y())
+            .flat_map(|y| {
+                let start = page * w * h + y as usize * w;
+                self.image
+                    [(start + rectangle.min_x() as usize)..(start + rectangle.max_x() as usize)]
+                    .iter()
+                    .copied()
+            })
+            .collect()
+    }
+
+    fn allocate(&mut self, width: usize, height: usize) -> Option<(usize, AllocId, Point2D<u32>)> {
+        let size = size2(width as i32, height as i32);
+        let allocation = self
+            .atlases
+            .iter_mut()
+            .enumerate()
+            .find_map(|(page, atlas)| Some((page, atlas.allocate(size)?)));
+        if let Some((page, allocation)) = allocation {
+            return Some((
+                page,
+                allocation.id,
+                allocation.rectangle.to_rect().origin.to_u32(),
+            ));
+        }
+        let limit_reached = self
+            .max_pages
+            .map(|limit| self.atlases.len() >= limit)
+            .unwrap_or_default();
+        if !limit_reached || self.overflow == TextRendererOverflow::AddPage {
+            let w = self.atlas_size[0];
+            let h = self.atlas_size[1];
+            let mut atlas = AtlasAllocator::new(size2(w as _, h as _));
+            let page = self.atlases.len();
+            let allocation = atlas.allocate(size)?;
+            self.atlases.push(atlas);
+            self.atlas_size[2] += 1;
+            let [w, h, d] = self.atlas_size;
+            self.image.resize(w * h * d, 0);
+            self.mark_dirty(page, Rect::new(Point2D::zero(), [w as _, h as _].into()));
+            return Some((
+                page,
+                allocation.id,
+                allocation.rectangle.to_rect().origin.to_u32(),
+            ));
+        }
+        if self.overflow != TextRendererOverflow::Evict {
+            return None;
+        }
+        let protected = self
+            .ready_to_render
+            .iter()
+            .map(|(_, key)| *key)
+            .collect::<HashSet<_>>();
+        let mut candidates = self
+            .allocations
+            .iter()
+            .filter(|(key, _)| !protected.contains(key))
+            .map(|(key, (page, id, last_used))| (*last_used, *key, *page, *id))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(last_used, ..)| *last_used);
+        for (_, key, page, id) in candidates {
+            self.atlases[page].deallocate(id);
+            self.allocations.remove(&key);
+            if let Some(glyph) = self.used_glyphs.remove(&key) {
+                let [w, h, _] = self.atlas_size;
+                for y in glyph.rectangle.min_y()..glyph.rectangle.max_y() {
+                    let start = page * w * h + y as usize * w;
+                    self.image[(start + glyph.rectangle.min_x() as usize)
+                        ..(start + glyph.rectangle.max_x() as usize)]
+                        .fill(0);
+                }
+                self.mark_dirty(page, glyph.rectangle);
+            }
+            if let Some(allocation) = self.atlases[page].allocate(size) {
+                return Some((
+                    page,
+                    allocation.id,
+                    allocation.rectangle.to_rect().origin.to_u32(),
+                ));
             }
         }
+        None
     }
 
     pub fn include_consumed(
@@ -145,16 +506,97 @@ impl<UD: Copy> TextRenderer<UD> {
         self.consume_renderables()
     }
 
+    /// Measures extents of laid out text as `[x, y, width, height]`.
+    /// Assumes layout uses `CoordinateSystem::PositiveYDown`.
+    pub fn measure(fonts: &[Font], layout: &Layout<UD>, settings: TextMeasureSettings) -> [f32; 4] {
+        let Some(lines) = layout.lines() else {
+            return [layout.settings().x, layout.settings().y, 0.0, 0.0];
+        };
+        let glyphs = layout.glyphs();
+        let is_blank = |index: usize| {
+            let line = &lines[index];
+            glyphs[line.glyph_start..=line.glyph_end]
+                .iter()
+                .all(|glyph| glyph.char_data.is_whitespace())
+        };
+        let count = if settings.trailing_blank_lines {
+            lines.len()
+        } else {
+            (0..lines.len())
+                .rev()
+                .find(|index| !is_blank(*index))
+                .map(|index| index + 1)
+                .unwrap_or_default()
+        };
+        let Some(first) = lines.first() else {
+            return [layout.settings().x, layout.settings().y, 0.0, 0.0];
+        };
+        let x = layout.settings().x;
+        let y = first.baseline_y - first.max_ascent;
+        let mut left = f32::INFINITY;
+        let mut right = f32::NEG_INFINITY;
+        let mut height = 0.0;
+        for line in &lines[..count] {
+            height += line.max_new_line_size;
+            let line_glyphs = &glyphs[line.glyph_start..=line.glyph_end];
+            let visible = line_glyphs
+                .iter()
+                .rposition(|glyph| !glyph.char_data.is_whitespace());
+            let end = if settings.trailing_whitespace {
+                line_glyphs.len()
+            } else {
+                visible.map(|index| index + 1).unwrap_or_default()
+            };
+            for glyph in &line_glyphs[..end] {
+                if glyph.char_data.is_control() {
+                    continue;
+                }
+                let extent = if glyph.char_data.is_whitespace() {
+                    fonts[glyph.font_index]
+                        .metrics_indexed(glyph.key.glyph_index, glyph.key.px)
+                        .advance_width
+                } else {
+                    glyph.width as f32
+                };
+                left = left.min(glyph.x);
+                right = right.max(glyph.x + extent);
+            }
+        }
+        if settings.trailing_blank_lines
+            && glyphs
+                .last()
+                .map(|glyph| glyph.parent == '\n')
+                .unwrap_or_default()
+        {
+            height += lines[lines.len() - 1].max_new_line_size;
+        }
+        if left <= right {
+            [left, y, right - left, height]
+        } else {
+            [x, y, 0.0, height]
+        }
+    }
+
     pub fn glyph(&self, key: &GlyphRasterConfig) -> Option<TextRendererGlyph> {
+        self.glyph_variant(&TextRendererGlyphKey::from(*key))
+    }
+
+    pub fn glyph_variant(&self, key: &TextRendererGlyphKey) -> Option<TextRendererGlyph> {
         self.used_glyphs.get(key).copied()
     }
 
+    /// Yields glyphs snapped to pixel grid, with width matching atlas entry.
     pub fn consume_renderables(
         &mut self,
     ) -> impl Iterator<Item = (GlyphPosition<UD>, TextRendererGlyph)> + '_ {
+        let spread = self.sdf_spread;
+        let used_glyphs = &self.used_glyphs;
         self.ready_to_render
             .drain(..)
-            .filter_map(|glyph| Some((glyph, *self.used_glyphs.get(&glyph.key)?)))
+            .filter_map(move |(glyph, key)| {
+                let data = *used_glyphs.get(&key)?;
+                Some((Self::fit(spread, glyph, &data), data))
+            })
     }
 
     pub fn image(&self) -> &[u8] {
@@ -174,10 +616,98 @@ impl<UD: Copy> TextRenderer<UD> {
             glyphs: self.used_glyphs,
             atlas_size: self.atlas_size,
             image: self.image,
-            renderables: self.ready_to_render,
+            renderables: self
+                .ready_to_render
+                .into_iter()
+                .map(|(glyph, _)| glyph)
+                .collect(),
         }
     }
 
+    /// Emits quads of underline and strikethrough for every line of laid out
+    /// text, spanning its visible glyphs. Quads sample fully covered area of
+    /// atlas, so they render with the same shader and texture as glyphs.
+    /// Assumes layout uses `CoordinateSystem::PositiveYDown`.
+    pub fn render_decorations_to_stream<V, B>(
+        &mut self,
+        layout: &Layout<UD>,
+        decoration: TextDecoration,
+        stream: &mut VertexStream<V, B>,
+    ) where
+        V: TextVertex<UD> + Pod + Default,
+    {
+        if decoration.is_empty() {
+            return;
+        }
+        let Some(lines) = layout.lines() else {
+            return;
+        };
+        let Some(solid) = self.solid_region() else {
+            return;
+        };
+        let [w, h, _] = self.atlas_size;
+        let uv = [
+            (solid.rectangle.min_x() as f32 + solid.rectangle.width() as f32 * 0.5) / w as f32,
+            (solid.rectangle.min_y() as f32 + solid.rectangle.height() as f32 * 0.5) / h as f32,
+            solid.page as f32,
+        ];
+        let glyphs = layout.glyphs();
+        for line in lines {
+            let visible = glyphs[line.glyph_start..=line.glyph_end]
+                .iter()
+                .filter(|glyph| !glyph.char_data.is_whitespace() && !glyph.char_data.is_control())
+                .collect::<Vec<_>>();
+            let Some(first) = visible.first() else {
+                continue;
+            };
+            let left = visible
+                .iter()
+                .map(|glyph| glyph.x)
+                .fold(f32::INFINITY, f32::min);
+            let right = visible
+                .iter()
+                .map(|glyph| glyph.x + glyph.width as f32)
+                .fold(f32::NEG_INFINITY, f32::max);
+            let size = visible.iter().map(|glyph| glyph.key.px).fold(0.0, f32::max);
+            let thickness = (size / 16.0).round().max(1.0);
+            let mut bars = Vec::with_capacity(2);
+            if decoration.underline {
+                bars.push(line.baseline_y + thickness);
+            }
+            if decoration.strikethrough {
+                bars.push(line.baseline_y - line.max_ascent * 0.3 - thickness * 0.5);
+            }
+            for top in bars {
+                let mut a = V::default();
+                let mut b = V::default();
+                let mut c = V::default();
+                let mut d = V::default();
+                a.apply([left, top], uv, first.user_data);
+                b.apply([right, top], uv, first.user_data);
+                c.apply([right, top + thickness], uv, first.user_data);
+                d.apply([left, top + thickness], uv, first.user_data);
+                stream.quad([a, b, c, d]);
+            }
+        }
+    }
+
+    fn solid_region(&mut self) -> Option<TextRendererGlyph> {
+        if self.solid.is_none() {
+            // Bigger than single texel so filtering never reaches its border.
+            let size = 3;
+            let (page, _, origin) = self.allocate(size + self.padding, size + self.padding)?;
+            let [w, h, _] = self.atlas_size;
+            for y in 0..size {
+                let start = page * w * h + (origin.y as usize + y) * w + origin.x as usize;
+                self.image[start..(start + size)].fill(255);
+            }
+            let rectangle = Rect::new(origin, [size as _, size as _].into());
+            self.mark_dirty(page, rectangle);
+            self.solid = Some(TextRendererGlyph { page, rectangle });
+        }
+        self.solid
+    }
+
     pub fn render_to_stream<V, B>(&mut self, stream: &mut VertexStream<V, B>)
     where
         V: TextVertex<UD> + Pod + Default,
@@ -185,8 +715,9 @@ impl<UD: Copy> TextRenderer<UD> {
         let [w, h, _] = self.atlas_size;
         let w = w as f32;
         let h = h as f32;
-        for glyph in self.ready_to_render.drain(..) {
-            if let Some(data) = self.used_glyphs.get(&glyph.key) {
+        for (glyph, key) in self.ready_to_render.drain(..) {
+            if let Some(data) = self.used_glyphs.get(&key) {
+                let glyph = Self::fit(self.sdf_spread, glyph, data);
                 let mut a = V::default();
                 let mut b = V::default();
                 let mut c = V::default();
@@ -235,12 +766,17 @@ impl<UD: Copy> TextRenderer<UD> {
 
 #[cfg(test)]
 mod tests {
-    use crate::TextRenderer;
+    use crate::{
+        TextDecoration, TextMeasureSettings, TextRenderer, TextRendererGlyphKey,
+        TextRendererOverflow, TextVertex,
+    };
+    use etagere::euclid::default::{Point2D, Rect, Size2D};
     use fontdue::{
-        layout::{CoordinateSystem, Layout, TextStyle},
+        layout::{CoordinateSystem, Layout, LayoutSettings, TextStyle},
         Font,
     };
     use image::RgbImage;
+    use spitfire_core::VertexStream;
 
     #[test]
     fn test_text_renderer() {
@@ -268,4 +804,322 @@ mod tests {
         .unwrap();
         image.save("../../resources/test.png").unwrap();
     }
+
+    #[test]
+    fn test_measure_trailing() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let measure = |text: &str, settings: TextMeasureSettings| {
+            let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+            layout.append(&fonts, &TextStyle::new(text, 32.0, 0));
+            TextRenderer::measure(&fonts, &layout, settings)
+        };
+
+        let settings = TextMeasureSettings::default();
+        assert_eq!(measure("abc ", settings), measure("abc", settings));
+        assert_eq!(measure("abc\n", settings), measure("abc", settings));
+
+        let settings = TextMeasureSettings::default().trailing_whitespace(true);
+        let [_, _, trimmed, _] = measure("abc", settings);
+        let [_, _, spaced, _] = measure("abc ", settings);
+        assert!(spaced > trimmed);
+
+        let settings = TextMeasureSettings::default().trailing_blank_lines(true);
+        let [_, _, _, single] = measure("abc", settings);
+        let [_, _, _, blank] = measure("abc\n", settings);
+        let [_, _, _, blanks] = measure("abc\n\n", settings);
+        assert_eq!(blank, single * 2.0);
+        assert_eq!(blanks, single * 3.0);
+    }
+
+    #[test]
+    fn test_max_pages_overflow() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let layout = |text: &str| {
+            let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+            layout.append(&fonts, &TextStyle::new(text, 32.0, 0));
+            layout
+        };
+
+        let mut renderer = TextRenderer::<()>::new(64, 64)
+            .max_pages(Some(1))
+            .overflow(TextRendererOverflow::Fail);
+        renderer.include(&fonts, &layout("ABCDEFGHIJKLMNOPQRSTUVWXYZ"));
+        assert_eq!(renderer.atlas_size(), [64, 64, 1]);
+        let rendered = renderer.consume_renderables().count();
+        assert!(rendered > 0);
+        assert!(rendered < 26);
+
+        let mut renderer = TextRenderer::<()>::new(64, 64)
+            .max_pages(Some(1))
+            .overflow(TextRendererOverflow::Evict);
+        renderer.include(&fonts, &layout("ABCDEFGHIJKLMNOPQRSTUVWXYZ"));
+        renderer.consume_renderables().count();
+        renderer.include(&fonts, &layout("Z"));
+        renderer.consume_renderables().count();
+        renderer.include(&fonts, &layout("abc"));
+        assert_eq!(renderer.atlas_size(), [64, 64, 1]);
+        assert_eq!(renderer.consume_renderables().count(), 3);
+        assert!(renderer.glyph(&layout("Z").glyphs()[0].key).is_some());
+
+        let mut renderer = TextRenderer::<()>::new(64, 64).max_pages(Some(1));
+        renderer.include(&fonts, &layout("ABCDEFGHIJKLMNOPQRSTUVWXYZ"));
+        assert!(renderer.atlas_size()[2] > 1);
+        assert_eq!(renderer.consume_renderables().count(), 26);
+    }
+
+    #[test]
+    fn test_subpixel() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let include = |renderer: &mut TextRenderer<()>| {
+            for x in [10.0, 10.25, 10.5, 10.75, 11.0] {
+                let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+                layout.reset(&LayoutSettings {
+                    x,
+                    ..Default::default()
+                });
+                layout.append(&fonts, &TextStyle::new("a", 32.0, 0));
+                renderer.include(&fonts, &layout);
+            }
+            renderer
+                .consume_renderables()
+                .map(|(glyph, _)| glyph.x)
+                .collect::<Vec<_>>()
+        };
+
+        let mut renderer = TextRenderer::<()>::new(256, 256);
+        let positions = include(&mut renderer);
+        assert_eq!(renderer.used_glyphs.len(), 1);
+        assert!(positions.iter().all(|x| x.fract() == 0.0));
+
+        let mut renderer = TextRenderer::<()>::new(256, 256).subpixel(true);
+        let positions = include(&mut renderer);
+        assert_eq!(
+            renderer.used_glyphs.len(),
+            TextRenderer::<()>::SUBPIXEL_STEPS as usize
+        );
+        assert!(positions.iter().all(|x| x.fract() == 0.0));
+        let key = renderer.used_glyphs.keys().next().unwrap().config;
+        let base = renderer.glyph(&key).unwrap();
+        let shifted = renderer
+            .glyph_variant(&TextRendererGlyphKey {
+                config: key,
+                subpixel: 2,
+            })
+            .unwrap();
+        assert_eq!(shifted.rectangle.width(), base.rectangle.width() + 1);
+    }
+
+    #[test]
+    fn test_sdf() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+        layout.append(&fonts, &TextStyle::new("O", 32.0, 0));
+
+        let mut renderer = TextRenderer::<()>::new(256, 256);
+        renderer.include(&fonts, &layout);
+        let (plain, plain_data) = renderer.consume_renderables().next().unwrap();
+
+        let spread = 4;
+        let mut renderer = TextRenderer::<()>::new_sdf(256, 256, spread);
+        assert_eq!(renderer.sdf_spread(), Some(spread));
+        renderer.include(&fonts, &layout);
+        let (glyph, data) = renderer.consume_renderables().next().unwrap();
+        assert_eq!(
+            data.rectangle.width(),
+            plain_data.rectangle.width() + spread as u32 * 2
+        );
+        assert_eq!(
+            data.rectangle.height(),
+            plain_data.rectangle.height() + spread as u32 * 2
+        );
+        assert_eq!(glyph.x, plain.x - spread as f32);
+        assert_eq!(glyph.y, plain.y - spread as f32);
+        assert_eq!(glyph.height, plain.height + spread * 2);
+
+        let [width, height, _] = renderer.atlas_size();
+        let value = |x: u32, y: u32| {
+            let x = data.rectangle.min_x() + x;
+            let y = data.rectangle.min_y() + y;
+            renderer.image()[data.page * width * height + y as usize * width + x as usize]
+        };
+        let w = data.rectangle.width();
+        let h = data.rectangle.height();
+        // Padding corner is far outside, stroke of letter is inside and
+        // hole in the middle is outside again.
+        assert_eq!(value(0, 0), 0);
+        assert!(value(spread as u32 + 2, h / 2) >= 128);
+        assert!(value(w / 2, h / 2) < 128);
+        assert!((1..w).any(|x| {
+            let value = value(x, h / 2);
+            value > 64 && value < 192
+        }));
+    }
+
+    #[test]
+    fn test_page_limit_touch() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let layout = |text: &str| {
+            let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+            layout.append(&fonts, &TextStyle::new(text, 32.0, 0));
+            layout
+        };
+        let key = |text: &str| TextRendererGlyphKey::from(layout(text).glyphs()[0].key);
+
+        let mut renderer = TextRenderer::<()>::new(64, 64);
+        renderer.set_page_limit(1);
+        renderer.include(&fonts, &layout("A"));
+        renderer.consume_renderables().count();
+        assert!(renderer.touch(&key("A")));
+        assert!(!renderer.touch(&key("B")));
+        for letter in "BCDEFGHIJKLMNOPQRSTUVWXYZ".chars() {
+            renderer.include(&fonts, &layout(&letter.to_string()));
+            renderer.consume_renderables().count();
+            renderer.touch(&key("A"));
+        }
+        assert_eq!(renderer.atlas_size()[2], 1);
+        assert!(renderer.glyph_variant(&key("A")).is_some());
+        assert!(renderer.glyph_variant(&key("B")).is_none());
+        assert!(renderer.glyph_variant(&key("Z")).is_some());
+    }
+
+    #[test]
+    fn test_padding() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+        layout.append(
+            &fonts,
+            &TextStyle::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ", 32.0, 0),
+        );
+        let padding = 2;
+        let mut renderer = TextRenderer::<()>::new(256, 256).padding(padding);
+        renderer.include(&fonts, &layout);
+        let glyphs = renderer
+            .consume_renderables()
+            .map(|(_, data)| data)
+            .collect::<Vec<_>>();
+        assert_eq!(glyphs.len(), 26);
+        // Linear filtering samples at most one pixel around glyph, which must
+        // stay out of any other glyph.
+        let expanded = |rectangle: Rect<u32>| {
+            Rect::new(
+                Point2D::new(
+                    rectangle.min_x().saturating_sub(padding as u32 / 2),
+                    rectangle.min_y().saturating_sub(padding as u32 / 2),
+                ),
+                Size2D::new(
+                    rectangle.width() + padding as u32 / 2 * 2,
+                    rectangle.height() + padding as u32 / 2 * 2,
+                ),
+            )
+        };
+        for (index, a) in glyphs.iter().enumerate() {
+            for b in glyphs.iter().skip(index + 1) {
+                if a.page == b.page && a.rectangle != b.rectangle {
+                    assert!(!expanded(a.rectangle).intersects(&b.rectangle));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_decorations() {
+        #[derive(Debug, Default, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+        #[repr(C)]
+        struct TestVertex {
+            position: [f32; 2],
+            uv: [f32; 3],
+        }
+
+        impl TextVertex<()> for TestVertex {
+            fn apply(&mut self, position: [f32; 2], tex_coord: [f32; 3], _: ()) {
+                self.position = position;
+                self.uv = tex_coord;
+            }
+        }
+
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+        layout.append(&fonts, &TextStyle::new("link\n \nline two ", 32.0, 0));
+        let lines = layout.lines().unwrap().to_owned();
+        let mut renderer = TextRenderer::<()>::new(256, 256);
+        let mut stream = VertexStream::<TestVertex, ()>::default();
+
+        renderer.render_decorations_to_stream(&layout, TextDecoration::default(), &mut stream);
+        assert!(stream.vertices().is_empty());
+
+        let decoration = TextDecoration::default()
+            .underline(true)
+            .strikethrough(true);
+        renderer.render_decorations_to_stream(&layout, decoration, &mut stream);
+        // Blank middle line gets no bars.
+        assert_eq!(stream.vertices().len(), 2 * 2 * 4);
+        let vertices = stream.vertices();
+        let underline = &vertices[0..4];
+        let strikethrough = &vertices[4..8];
+        assert!(underline[0].position[1] > lines[0].baseline_y);
+        assert!(strikethrough[0].position[1] < lines[0].baseline_y);
+        assert!(vertices[8].position[1] > lines[1].baseline_y + 1.0);
+        let [w, h, _] = renderer.atlas_size();
+        let uv = underline[0].uv;
+        let x = (uv[0] * w as f32) as usize;
+        let y = (uv[1] * h as f32) as usize;
+        assert_eq!(renderer.image()[uv[2] as usize * w * h + y * w + x], 255);
+
+        let glyphs = layout.glyphs();
+        let last = &glyphs[lines[2].glyph_end - 1];
+        assert_eq!(vertices[8].position[0], glyphs[lines[2].glyph_start].x);
+        assert_eq!(vertices[9].position[0], last.x + last.width as f32);
+    }
+
+    #[test]
+    fn test_dirty_rect() {
+        let font = include_bytes!("../../../resources/Roboto-Regular.ttf") as &[_];
+        let font = Font::from_bytes(font, Default::default()).unwrap();
+        let fonts = [font];
+        let layout = |text: &str| {
+            let mut layout = Layout::<()>::new(CoordinateSystem::PositiveYDown);
+            layout.append(&fonts, &TextStyle::new(text, 32.0, 0));
+            layout
+        };
+
+        let mut renderer = TextRenderer::<()>::new(128, 128);
+        assert!(renderer.take_dirty_rect().is_none());
+        renderer.include(&fonts, &layout("a"));
+        let (page, rectangle) = renderer.take_dirty_rect().unwrap();
+        assert_eq!(page, 0);
+        assert_eq!(rectangle, Rect::new(Point2D::zero(), Size2D::new(128, 128)));
+        assert!(renderer.take_dirty_rect().is_none());
+
+        renderer.include(&fonts, &layout("a"));
+        assert!(renderer.take_dirty_rect().is_none());
+
+        renderer.include(&fonts, &layout("bc"));
+        let glyphs = renderer
+            .consume_renderables()
+            .map(|(_, data)| data)
+            .collect::<Vec<_>>();
+        let (page, rectangle) = renderer.take_dirty_rect().unwrap();
+        assert_eq!(page, 0);
+        let b = glyphs[glyphs.len() - 2].rectangle;
+        let c = glyphs[glyphs.len() - 1].rectangle;
+        assert_eq!(rectangle, b.union(&c));
+        assert!(renderer.take_dirty_rect().is_none());
+        let pixels = renderer.image_region(page, c);
+        assert_eq!(pixels.len(), (c.width() * c.height()) as usize);
+        assert!(pixels.iter().any(|value| *value > 0));
+    }
 }
@@ -4,30 +4,220 @@ use glow::{Context, HasContext};
 use glutin::{
     dpi::LogicalSize,
     event::{Event, WindowEvent},
-    event_loop::{ControlFlow, EventLoop},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
     platform::run_return::EventLoopExtRunReturn,
-    window::{Fullscreen, Window, WindowBuilder},
-    ContextBuilder, ContextWrapper, PossiblyCurrent,
+    window::{Fullscreen, Icon, Window, WindowBuilder},
+    Api, ContextBuilder, ContextWrapper, GlProfile, GlRequest, PossiblyCurrent,
 };
+use std::path::PathBuf;
 #[cfg(target_arch = "wasm32")]
 use web_sys::{wasm_bindgen::JsCast, HtmlCanvasElement, WebGl2RenderingContext};
 #[cfg(target_arch = "wasm32")]
 use winit::{
     dpi::LogicalSize,
     event::Event,
-    event_loop::{ControlFlow, EventLoop},
-    window::{Fullscreen, Window, WindowBuilder},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
+    window::{CursorGrabMode, Fullscreen, Window, WindowBuilder},
 };
 
+/// Lets application state drive run loop from within event handlers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppControl {
+    exit: bool,
+    scale_factor: f64,
+    dirty_cursor_grab: Option<bool>,
+    dirty_cursor_visible: Option<bool>,
+    dirty_fullscreen: Option<bool>,
+}
+
+impl Default for AppControl {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl AppControl {
+    fn new(scale_factor: f64) -> Self {
+        Self {
+            exit: false,
+            scale_factor,
+            dirty_cursor_grab: None,
+            dirty_cursor_visible: None,
+            dirty_fullscreen: None,
+        }
+    }
+
+    /// Requests run loop to stop after current event. Ignored on web.
+    pub fn exit(&mut self) {
+        self.exit = true;
+    }
+
+    pub fn is_exiting(&self) -> bool {
+        self.exit
+    }
+
+    /// Ratio of physical to logical pixels of window, as of current event.
+    /// Screen size of [`Graphics::main_camera`] is in physical pixels, so
+    /// multiply logical sizes of fonts and UI by it to keep them crisp.
+    pub fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Requests cursor to be locked to window. Not every platform supports
+    /// it - failure gets logged. On web it locks pointer and needs to be
+    /// requested while handling user input.
+    pub fn set_cursor_grab(&mut self, grab: bool) {
+        self.dirty_cursor_grab = Some(grab);
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.dirty_cursor_visible = Some(visible);
+    }
+
+    /// Switches window between borderless fullscreen and windowed mode.
+    /// Leaving fullscreen restores size and position window had before
+    /// entering it. On web it needs to be requested while handling user
+    /// input, otherwise browser rejects it.
+    ///
+    /// There is no runtime counterpart for [`AppConfig::vsync`] - on native
+    /// swap interval is bound to GL context created at startup, and on web
+    /// frames are always synchronized with display refresh.
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.dirty_fullscreen = Some(fullscreen);
+    }
+
+    // Requesting mode window is already in would make some platforms
+    // forget windowed size they restore when leaving fullscreen.
+    fn fullscreen_request(&self, is_fullscreen: bool) -> Option<bool> {
+        self.dirty_fullscreen
+            .filter(|fullscreen| is_fullscreen != *fullscreen)
+    }
+
+    fn apply(&self, window: &Window) {
+        if let Some(fullscreen) = self.fullscreen_request(window.fullscreen().is_some()) {
+            window.set_fullscreen(if fullscreen {
+                Some(Fullscreen::Borderless(None))
+            } else {
+                None
+            });
+        }
+        if let Some(visible) = self.dirty_cursor_visible {
+            window.set_cursor_visible(visible);
+        }
+        if let Some(grab) = self.dirty_cursor_grab {
+            #[cfg(not(target_arch = "wasm32"))]
+            let result = window.set_cursor_grab(grab);
+            #[cfg(target_arch = "wasm32")]
+            let result = window.set_cursor_grab(if grab {
+                CursorGrabMode::Locked
+            } else {
+                CursorGrabMode::None
+            });
+            if let Err(error) = result {
+                crate::console_log!("* Could not change cursor grab: {}", error);
+            }
+        }
+    }
+}
+
+/// Frame timing measured by run loop.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AppTiming {
+    /// Seconds since previous frame, clamped to [`AppConfig::max_delta_time`].
+    pub delta: f32,
+    /// Sum of all frames deltas, in seconds.
+    pub elapsed: f32,
+    /// Index of current frame, starting at 0.
+    pub frame: u64,
+}
+
+impl AppTiming {
+    fn advance(&mut self, delta: f32, max_delta: f32) {
+        self.delta = delta.clamp(0.0, max_delta);
+        self.elapsed += self.delta;
+    }
+}
+
+/// Accumulates frame time into fixed size steps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AppFixedStep {
+    timestep: f32,
+    max_steps: usize,
+    accumulator: f32,
+}
+
+impl AppFixedStep {
+    /// Returns number of steps to run this frame. Time that exceeds
+    /// `max_steps` is dropped, so slow steps can't pile up endlessly.
+    fn advance(&mut self, delta: f32) -> usize {
+        if self.timestep <= 0.0 {
+            return 0;
+        }
+        self.accumulator += delta;
+        let steps = (self.accumulator / self.timestep).floor() as usize;
+        if steps > self.max_steps {
+            self.accumulator = 0.0;
+            self.max_steps
+        } else {
+            self.accumulator -= steps as f32 * self.timestep;
+            steps
+        }
+    }
+}
+
 #[allow(unused_variables)]
-pub trait AppState<V: GlowVertexAttribs> {
+pub trait AppState<V: GlowVertexAttribs, T: 'static = ()> {
     fn on_init(&mut self, graphics: &mut Graphics<V>) {}
 
     fn on_redraw(&mut self, graphics: &mut Graphics<V>) {}
 
-    fn on_event(&mut self, event: Event<()>, window: &mut Window) -> bool {
+    /// Called once right after [`AppState::on_init`] with initial scale
+    /// factor, then every time window moves to display with different DPI
+    /// or user changes scaling settings (browser zoom on web), so state can
+    /// re-layout. Current value is also available in [`AppControl`].
+    fn on_scale_factor_changed(&mut self, graphics: &mut Graphics<V>, scale_factor: f64) {}
+
+    /// Called for every file dropped onto window. Not called on web.
+    fn on_file_dropped(&mut self, path: PathBuf) {}
+
+    /// Called zero or more times per frame before redraw, once for every
+    /// [`AppConfig::fixed_timestep`] of elapsed time, if one is set.
+    fn on_fixed_update(&mut self, graphics: &mut Graphics<V>, control: &mut AppControl) {}
+
+    /// Called by run loop for every frame, with frame timing.
+    ///
+    /// Default implementation forwards to [`AppState::on_redraw`].
+    fn on_redraw_with(&mut self, graphics: &mut Graphics<V>, timing: AppTiming) {
+        self.on_redraw(graphics);
+    }
+
+    /// Returning `false` stops the run loop.
+    fn on_event(&mut self, event: Event<T>, window: &mut Window) -> bool {
         true
     }
+
+    /// Called by run loop for every event, with access to graphics and run loop control.
+    ///
+    /// Default implementation forwards to [`AppState::on_event`] and exits when it returns
+    /// `false`, so existing states keep working. To migrate, move `on_event` body here and
+    /// replace returning `false` with calling [`AppControl::exit`].
+    fn on_event_with(
+        &mut self,
+        event: Event<T>,
+        window: &mut Window,
+        graphics: &mut Graphics<V>,
+        control: &mut AppControl,
+    ) {
+        if !self.on_event(event, window) {
+            control.exit();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppGlProfile {
+    Core,
+    Compatibility,
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +227,8 @@ pub struct AppConfig {
     pub height: u32,
     pub fullscreen: bool,
     pub maximized: bool,
+    /// Fixed for whole app lifetime. Ignored on web, where frames are always
+    /// synchronized with display refresh.
     pub vsync: bool,
     pub decorations: bool,
     pub transparent: bool,
@@ -44,6 +236,33 @@ pub struct AppConfig {
     pub hardware_acceleration: Option<bool>,
     pub refresh_on_event: bool,
     pub color: [f32; 4],
+    /// Starts with hidden window that gets shown after first drawn frame.
+    /// Ignored on web.
+    pub visible: bool,
+    /// Requested (major, minor) OpenGL version, also used as minimum one
+    /// accepted. When not set, latest available is used and at least 3.0
+    /// is required. Ignored on web, which always uses WebGL2.
+    pub gl_version: Option<(u8, u8)>,
+    /// Ignored on web.
+    pub gl_profile: Option<AppGlProfile>,
+    /// Requested MSAA samples count, 0 disables multisampling. Falls back to
+    /// no multisampling when not supported. On web any non-zero value only
+    /// requests antialiased context.
+    pub multisampling: u16,
+    /// Requests depth buffer for default framebuffer, which then gets
+    /// cleared every frame together with color.
+    pub depth: bool,
+    /// Upper limit of [`AppTiming::delta`], so frames after window was
+    /// unfocused or stalled don't make simulation jump.
+    pub max_delta_time: f32,
+    /// Seconds between [`AppState::on_fixed_update`] calls, which are not
+    /// made when not set.
+    pub fixed_timestep: Option<f32>,
+    /// Limit of fixed updates made per frame.
+    pub max_fixed_steps: usize,
+    /// Window icon as (RGBA pixels, width, height). Invalid icon gets logged
+    /// and ignored. Ignored on web.
+    pub icon: Option<(Vec<u8>, u32, u32)>,
 }
 
 impl Default for AppConfig {
@@ -61,6 +280,15 @@ impl Default for AppConfig {
             hardware_acceleration: Some(true),
             refresh_on_event: false,
             color: [1.0, 1.0, 1.0, 1.0],
+            visible: true,
+            gl_version: None,
+            gl_profile: None,
+            multisampling: 0,
+            depth: false,
+            max_delta_time: 0.25,
+            fixed_timestep: None,
+            max_fixed_steps: 5,
+            icon: None,
         }
     }
 }
@@ -125,15 +353,70 @@ impl AppConfig {
         self.color = v.into();
         self
     }
+
+    pub fn visible(mut self, v: bool) -> Self {
+        self.visible = v;
+        self
+    }
+
+    pub fn gl_version(mut self, major: u8, minor: u8) -> Self {
+        self.gl_version = Some((major, minor));
+        self
+    }
+
+    pub fn gl_profile(mut self, v: AppGlProfile) -> Self {
+        self.gl_profile = Some(v);
+        self
+    }
+
+    /// Samples count gets rounded up to power of two.
+    pub fn multisampling(mut self, samples: u16) -> Self {
+        self.multisampling = if samples == 0 {
+            0
+        } else {
+            samples.next_power_of_two()
+        };
+        self
+    }
+
+    pub fn depth(mut self, v: bool) -> Self {
+        self.depth = v;
+        self
+    }
+
+    pub fn max_delta_time(mut self, v: f32) -> Self {
+        self.max_delta_time = v;
+        self
+    }
+
+    pub fn fixed_timestep(mut self, v: Option<f32>) -> Self {
+        self.fixed_timestep = v;
+        self
+    }
+
+    pub fn max_fixed_steps(mut self, v: usize) -> Self {
+        self.max_fixed_steps = v;
+        self
+    }
+
+    pub fn icon(mut self, rgba: Vec<u8>, width: u32, height: u32) -> Self {
+        self.icon = Some((rgba, width, height));
+        self
+    }
 }
 
-pub struct App<V: GlowVertexAttribs> {
+/// `T` is type of user events sent into run loop with [`App::proxy`].
+pub struct App<V: GlowVertexAttribs, T: 'static = ()> {
     #[cfg(not(target_arch = "wasm32"))]
     width: u32,
     #[cfg(not(target_arch = "wasm32"))]
     height: u32,
+    #[cfg(not(target_arch = "wasm32"))]
+    visible: bool,
     refresh_on_event: bool,
-    event_loop: EventLoop<()>,
+    max_delta_time: f32,
+    fixed_step: AppFixedStep,
+    event_loop: EventLoop<T>,
     #[cfg(not(target_arch = "wasm32"))]
     context_wrapper: ContextWrapper<PossiblyCurrent, Window>,
     #[cfg(target_arch = "wasm32")]
@@ -141,13 +424,13 @@ pub struct App<V: GlowVertexAttribs> {
     graphics: Graphics<V>,
 }
 
-impl<V: GlowVertexAttribs> Default for App<V> {
+impl<V: GlowVertexAttribs, T: 'static> Default for App<V, T> {
     fn default() -> Self {
         Self::new(Default::default())
     }
 }
 
-impl<V: GlowVertexAttribs> App<V> {
+impl<V: GlowVertexAttribs, T: 'static> App<V, T> {
     pub fn new(config: AppConfig) -> Self {
         #[cfg(not(target_arch = "wasm32"))]
         let AppConfig {
@@ -163,6 +446,15 @@ impl<V: GlowVertexAttribs> App<V> {
             hardware_acceleration,
             refresh_on_event,
             color,
+            visible,
+            gl_version,
+            gl_profile,
+            multisampling,
+            depth,
+            max_delta_time,
+            fixed_timestep,
+            max_fixed_steps,
+            icon,
         } = config;
         #[cfg(target_arch = "wasm32")]
         let AppConfig {
@@ -175,6 +467,11 @@ impl<V: GlowVertexAttribs> App<V> {
             transparent,
             refresh_on_event,
             color,
+            multisampling,
+            depth,
+            max_delta_time,
+            fixed_timestep,
+            max_fixed_steps,
             ..
         } = config;
         let fullscreen = if fullscreen {
@@ -182,7 +479,7 @@ impl<V: GlowVertexAttribs> App<V> {
         } else {
             None
         };
-        let event_loop = EventLoop::new();
+        let event_loop = EventLoop::with_user_event();
         let window_builder = WindowBuilder::new()
             .with_title(title.as_str())
             .with_inner_size(LogicalSize::new(width, height))
@@ -191,16 +488,55 @@ impl<V: GlowVertexAttribs> App<V> {
             .with_decorations(decorations)
             .with_transparent(transparent);
         #[cfg(not(target_arch = "wasm32"))]
+        let window_builder = window_builder
+            .with_visible(visible)
+            .with_window_icon(icon.and_then(|(rgba, width, height)| {
+                Icon::from_rgba(rgba, width, height)
+                    .map_err(|error| crate::console_log!("* Invalid window icon: {}", error))
+                    .ok()
+            }));
+        #[cfg(not(target_arch = "wasm32"))]
         let (context_wrapper, context) = {
             let context_builder = ContextBuilder::new()
+                .with_gl(
+                    gl_version
+                        .map(|version| GlRequest::Specific(Api::OpenGl, version))
+                        .unwrap_or(GlRequest::Latest),
+                )
                 .with_vsync(vsync)
                 .with_double_buffer(double_buffer)
                 .with_hardware_acceleration(hardware_acceleration);
+            let context_builder = match gl_profile {
+                Some(AppGlProfile::Core) => context_builder.with_gl_profile(GlProfile::Core),
+                Some(AppGlProfile::Compatibility) => {
+                    context_builder.with_gl_profile(GlProfile::Compatibility)
+                }
+                None => context_builder,
+            };
+            let context_builder = if depth {
+                context_builder.with_depth_buffer(24)
+            } else {
+                context_builder
+            };
             #[cfg(debug_assertions)]
             crate::console_log!("* GL {:#?}", context_builder);
-            let context_wrapper = unsafe {
+            let context_wrapper = if multisampling > 0 {
                 context_builder
-                    .build_windowed(window_builder, &event_loop)
+                    .clone()
+                    .with_multisampling(multisampling)
+                    .build_windowed(window_builder.clone(), &event_loop)
+                    .or_else(|error| {
+                        crate::console_log!(
+                            "* Could not build multisampled context: {}. Falling back to none.",
+                            error
+                        );
+                        context_builder.build_windowed(window_builder, &event_loop)
+                    })
+            } else {
+                context_builder.build_windowed(window_builder, &event_loop)
+            };
+            let context_wrapper = unsafe {
+                context_wrapper
                     .expect("Could not build windowed context wrapper!")
                     .make_current()
                     .expect("Could not make windowed context wrapper a current one!")
@@ -227,9 +563,16 @@ impl<V: GlowVertexAttribs> App<V> {
                 .with_canvas(Some(canvas.clone()))
                 .build(&event_loop)
                 .expect("Could not build window!");
+            let options = web_sys::js_sys::Object::new();
+            let _ = web_sys::js_sys::Reflect::set(
+                &options,
+                &"antialias".into(),
+                &(multisampling > 0).into(),
+            );
+            let _ = web_sys::js_sys::Reflect::set(&options, &"depth".into(), &depth.into());
+            let context = canvas.get_context_with_context_options("webgl2", &options);
             let context = Context::from_webgl2_context(
-                canvas
-                    .get_context("webgl2")
+                context
                     .expect("Could not get WebGL 2 context!")
                     .expect("Could not get WebGL 2 context!")
                     .dyn_into::<WebGl2RenderingContext>()
@@ -238,19 +581,32 @@ impl<V: GlowVertexAttribs> App<V> {
             (window, context)
         };
         let context_version = context.version();
-        #[cfg(debug_assertions)]
         crate::console_log!("* GL Version: {:?}", context_version);
-        if context_version.major < 3 {
-            panic!("* Minimum GL version required is 3.0!");
+        #[cfg(not(target_arch = "wasm32"))]
+        let (major, minor) = gl_version.unwrap_or((3, 0));
+        #[cfg(target_arch = "wasm32")]
+        let (major, minor) = (3, 0);
+        if (context_version.major, context_version.minor) < (major as u32, minor as u32) {
+            panic!("* Minimum GL version required is {}.{}!", major, minor);
         }
         let mut graphics = Graphics::<V>::new(context);
         graphics.color = color;
+        graphics.depth_buffer = depth;
+        crate::console_log!("* MSAA samples: {}", graphics.multisampling());
         Self {
             #[cfg(not(target_arch = "wasm32"))]
             width,
             #[cfg(not(target_arch = "wasm32"))]
             height,
+            #[cfg(not(target_arch = "wasm32"))]
+            visible,
             refresh_on_event,
+            max_delta_time,
+            fixed_step: AppFixedStep {
+                timestep: fixed_timestep.unwrap_or_default(),
+                max_steps: max_fixed_steps,
+                accumulator: 0.0,
+            },
             event_loop,
             #[cfg(not(target_arch = "wasm32"))]
             context_wrapper,
@@ -260,12 +616,26 @@ impl<V: GlowVertexAttribs> App<V> {
         }
     }
 
-    pub fn run<S: AppState<V> + 'static>(self, mut state: S) {
+    /// Proxy that can be sent to other threads to wake run loop with user
+    /// events, received as `Event::UserEvent` in [`AppState::on_event`].
+    ///
+    /// Proxy is not available through [`AppControl`], which stays a plain
+    /// copyable value independent of user event type. Create proxy here,
+    /// before calling [`Self::run`], and store it in state or give it to
+    /// worker threads - proxies are cheap to clone.
+    pub fn proxy(&self) -> EventLoopProxy<T> {
+        self.event_loop.create_proxy()
+    }
+
+    pub fn run<S: AppState<V, T> + 'static>(self, mut state: S) {
         #[cfg(not(target_arch = "wasm32"))]
         let App {
             mut width,
             mut height,
+            mut visible,
             refresh_on_event,
+            max_delta_time,
+            mut fixed_step,
             mut event_loop,
             context_wrapper,
             mut graphics,
@@ -273,6 +643,8 @@ impl<V: GlowVertexAttribs> App<V> {
         #[cfg(target_arch = "wasm32")]
         let App {
             refresh_on_event,
+            max_delta_time,
+            mut fixed_step,
             event_loop,
             mut window,
             mut graphics,
@@ -280,8 +652,12 @@ impl<V: GlowVertexAttribs> App<V> {
         #[cfg(not(target_arch = "wasm32"))]
         let (context, mut window) = unsafe { context_wrapper.split() };
         state.on_init(&mut graphics);
+        let mut scale_factor = window.scale_factor();
+        state.on_scale_factor_changed(&mut graphics, scale_factor);
+        let mut timing = AppTiming::default();
         #[cfg(not(target_arch = "wasm32"))]
         {
+            let mut last_frame = std::time::Instant::now();
             let mut running = true;
             while running {
                 event_loop.run_return(|event, _, control_flow| {
@@ -300,10 +676,26 @@ impl<V: GlowVertexAttribs> App<V> {
                             }
                             graphics.main_camera.screen_size.x = width as _;
                             graphics.main_camera.screen_size.y = height as _;
+                            let now = std::time::Instant::now();
+                            timing.advance((now - last_frame).as_secs_f32(), max_delta_time);
+                            last_frame = now;
+                            let mut control = AppControl::new(scale_factor);
+                            for _ in 0..fixed_step.advance(timing.delta) {
+                                state.on_fixed_update(&mut graphics, &mut control);
+                            }
+                            control.apply(&window);
+                            if control.is_exiting() {
+                                running = false;
+                            }
                             let _ = graphics.prepare_frame(true);
-                            state.on_redraw(&mut graphics);
-                            let _ = graphics.draw();
-                            let _ = context.swap_buffers();
+                            state.on_redraw_with(&mut graphics, timing);
+                            timing.frame += 1;
+                            let drawn = graphics.draw().is_ok();
+                            let swapped = context.swap_buffers().is_ok();
+                            if !visible && drawn && swapped {
+                                window.set_visible(true);
+                                visible = true;
+                            }
                             *control_flow = ControlFlow::Exit;
                         }
                         Event::WindowEvent { event, .. } => match event {
@@ -312,14 +704,27 @@ impl<V: GlowVertexAttribs> App<V> {
                                 width = physical_size.width;
                                 height = physical_size.height;
                             }
+                            WindowEvent::ScaleFactorChanged {
+                                scale_factor: value,
+                                ..
+                            } => {
+                                scale_factor = *value;
+                                state.on_scale_factor_changed(&mut graphics, scale_factor);
+                            }
                             WindowEvent::CloseRequested => {
                                 running = false;
                             }
+                            WindowEvent::DroppedFile(path) => {
+                                state.on_file_dropped(path.to_owned());
+                            }
                             _ => {}
                         },
                         _ => {}
                     }
-                    if !state.on_event(event, &mut window) {
+                    let mut control = AppControl::new(scale_factor);
+                    state.on_event_with(event, &mut window, &mut graphics, &mut control);
+                    control.apply(&window);
+                    if control.is_exiting() {
                         running = false;
                     }
                 });
@@ -328,6 +733,7 @@ impl<V: GlowVertexAttribs> App<V> {
         }
         #[cfg(target_arch = "wasm32")]
         {
+            let mut last_frame = web_sys::js_sys::Date::now();
             event_loop.run(move |event, _, control_flow| {
                 *control_flow = if refresh_on_event {
                     ControlFlow::Wait
@@ -344,20 +750,96 @@ impl<V: GlowVertexAttribs> App<V> {
                             .as_f64()
                             .unwrap()
                             .max(1.0);
-                        let scaled_width = width * window.scale_factor();
-                        let scaled_height = height * window.scale_factor();
+                        if window.scale_factor() != scale_factor {
+                            scale_factor = window.scale_factor();
+                            state.on_scale_factor_changed(&mut graphics, scale_factor);
+                        }
+                        let scaled_width = width * scale_factor;
+                        let scaled_height = height * scale_factor;
                         window.set_inner_size(LogicalSize::new(width, height));
                         graphics.main_camera.screen_size.x = scaled_width as _;
                         graphics.main_camera.screen_size.y = scaled_height as _;
+                        let now = web_sys::js_sys::Date::now();
+                        timing.advance(((now - last_frame) * 0.001) as f32, max_delta_time);
+                        last_frame = now;
+                        let mut control = AppControl::new(scale_factor);
+                        for _ in 0..fixed_step.advance(timing.delta) {
+                            state.on_fixed_update(&mut graphics, &mut control);
+                        }
+                        control.apply(&window);
                         graphics.prepare_frame(true);
-                        state.on_redraw(&mut graphics);
+                        state.on_redraw_with(&mut graphics, timing);
+                        timing.frame += 1;
                         let _ = graphics.draw();
                         window.request_redraw();
                     }
                     _ => {}
                 }
-                state.on_event(event, &mut window);
+                let mut control = AppControl::new(scale_factor);
+                state.on_event_with(event, &mut window, &mut graphics, &mut control);
+                control.apply(&window);
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AppControl, AppFixedStep, AppTiming};
+
+    #[test]
+    fn test_control_scale_factor() {
+        assert_eq!(AppControl::default().scale_factor(), 1.0);
+        let mut control = AppControl::new(2.0);
+        assert!(!control.is_exiting());
+        control.set_fullscreen(true);
+        control.exit();
+        assert_eq!(control.scale_factor(), 2.0);
+        assert_eq!(control.dirty_fullscreen, Some(true));
+        assert!(control.is_exiting());
+    }
+
+    #[test]
+    fn test_control_fullscreen_request() {
+        let mut control = AppControl::default();
+        assert_eq!(control.fullscreen_request(false), None);
+        control.set_fullscreen(true);
+        assert_eq!(control.fullscreen_request(false), Some(true));
+        assert_eq!(control.fullscreen_request(true), None);
+        // Leaving fullscreen is only requested from fullscreen, so windowed
+        // size stored by platform is not overwritten by redundant request.
+        control.set_fullscreen(false);
+        assert_eq!(control.fullscreen_request(true), Some(false));
+        assert_eq!(control.fullscreen_request(false), None);
+    }
+
+    #[test]
+    fn test_timing_advance() {
+        let mut timing = AppTiming::default();
+        timing.advance(0.1, 0.25);
+        assert_eq!(timing.delta, 0.1);
+        timing.advance(5.0, 0.25);
+        assert_eq!(timing.delta, 0.25);
+        timing.advance(-1.0, 0.25);
+        assert_eq!(timing.delta, 0.0);
+        assert_eq!(timing.elapsed, 0.35);
+    }
+
+    #[test]
+    fn test_fixed_step_advance() {
+        let mut fixed_step = AppFixedStep {
+            timestep: 0.25,
+            max_steps: 3,
+            accumulator: 0.0,
+        };
+        assert_eq!(fixed_step.advance(0.125), 0);
+        assert_eq!(fixed_step.advance(0.25), 1);
+        assert_eq!(fixed_step.accumulator, 0.125);
+        assert_eq!(fixed_step.advance(0.5), 2);
+        assert_eq!(fixed_step.accumulator, 0.125);
+        assert_eq!(fixed_step.advance(10.0), 3);
+        assert_eq!(fixed_step.accumulator, 0.0);
+        fixed_step.timestep = 0.0;
+        assert_eq!(fixed_step.advance(1.0), 0);
+    }
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlowShaderStage {
+    Vertex,
+    Fragment,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlowError {
+    ContextLost,
+    ShaderCompile {
+        stage: GlowShaderStage,
+        log: String,
+    },
+    ProgramLink(String),
+    TextureCreate(String),
+    ResourceCreate(String),
+    SurfaceEmpty,
+    SurfaceLayerOutOfRange {
+        index: usize,
+        layer: usize,
+        depth: u32,
+    },
+    SurfaceSizeMismatch {
+        width: u32,
+        height: u32,
+    },
+    TextureRegionOutOfBounds {
+        x: u32,
+        y: u32,
+        page: u32,
+        width: u32,
+        height: u32,
+    },
+    TextureDataTooShort {
+        expected: usize,
+        actual: usize,
+    },
+    Unsupported(String),
+}
+
+impl fmt::Display for GlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ContextLost => write!(f, "Invalid context"),
+            Self::ShaderCompile { stage, log } => match stage {
+                GlowShaderStage::Vertex => write!(f, "Vertex Shader: {}", log),
+                GlowShaderStage::Fragment => write!(f, "Fragment Shader: {}", log),
+            },
+            Self::ProgramLink(log) => write!(f, "Shader Program: {}", log),
+            Self::TextureCreate(message) | Self::ResourceCreate(message) => {
+                write!(f, "{}", message)
+            }
+            Self::SurfaceEmpty => write!(f, "Surface must have at least one texture!"),
+            Self::SurfaceLayerOutOfRange {
+                index,
+                layer,
+                depth,
+            } => write!(
+                f,
+                "Surface texture #{} has layer: {} out of texture depth range: {}",
+                index, layer, depth
+            ),
+            Self::SurfaceSizeMismatch { width, height } => write!(
+                f,
+                "Some surface texture has different size than expected: {} x {}",
+                width, height
+            ),
+            Self::TextureRegionOutOfBounds {
+                x,
+                y,
+                page,
+                width,
+                height,
+            } => write!(
+                f,
+                "Texture region: {} x {} at ({}, {}) on page: {} exceeds texture size",
+                width, height, x, y, page
+            ),
+            Self::TextureDataTooShort { expected, actual } => write!(
+                f,
+                "Texture data has: {} bytes, but at least: {} bytes are expected",
+                actual, expected
+            ),
+            Self::Unsupported(feature) => write!(f, "Unsupported feature: {}", feature),
+        }
+    }
+}
+
+impl std::error::Error for GlowError {}
+
+impl From<GlowError> for String {
+    fn from(value: GlowError) -> Self {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(GlowError::ContextLost.to_string(), "Invalid context");
+        assert_eq!(
+            String::from(GlowError::SurfaceSizeMismatch {
+                width: 2,
+                height: 3
+            }),
+            "Some surface texture has different size than expected: 2 x 3"
+        );
+        assert_eq!(
+            GlowError::ShaderCompile {
+                stage: GlowShaderStage::Vertex,
+                log: "bad".to_owned()
+            }
+            .to_string(),
+            "Vertex Shader: bad"
+        );
+        assert_eq!(
+            GlowError::ShaderCompile {
+                stage: GlowShaderStage::Fragment,
+                log: "bad".to_owned()
+            }
+            .to_string(),
+            "Fragment Shader: bad"
+        );
+        assert_eq!(
+            GlowError::ProgramLink("bad".to_owned()).to_string(),
+            "Shader Program: bad"
+        );
+        assert_eq!(
+            GlowError::TextureCreate("no texture".to_owned()).to_string(),
+            "no texture"
+        );
+        assert_eq!(
+            GlowError::ResourceCreate("no buffer".to_owned()).to_string(),
+            "no buffer"
+        );
+        assert_eq!(
+            GlowError::SurfaceEmpty.to_string(),
+            "Surface must have at least one texture!"
+        );
+        assert_eq!(
+            GlowError::SurfaceLayerOutOfRange {
+                index: 1,
+                layer: 4,
+                depth: 2
+            }
+            .to_string(),
+            "Surface texture #1 has layer: 4 out of texture depth range: 2"
+        );
+        assert_eq!(
+            GlowError::TextureRegionOutOfBounds {
+                x: 1,
+                y: 2,
+                page: 3,
+                width: 4,
+                height: 5
+            }
+            .to_string(),
+            "Texture region: 4 x 5 at (1, 2) on page: 3 exceeds texture size"
+        );
+        assert_eq!(
+            GlowError::TextureDataTooShort {
+                expected: 16,
+                actual: 4
+            }
+            .to_string(),
+            "Texture data has: 4 bytes, but at least: 16 bytes are expected"
+        );
+        assert_eq!(
+            GlowError::Unsupported("instancing".to_owned()).to_string(),
+            "Unsupported feature: instancing"
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_shader_compile_stage() {
+        use crate::{
+            graphics::{Shader, Vertex3d},
+            headless::HeadlessGraphics,
+        };
+
+        let headless = HeadlessGraphics::<Vertex3d>::new(1, 1).expect("GL context");
+        let graphics = &headless.graphics;
+        let invalid = "#version 300 es\nthis is not glsl";
+        assert!(matches!(
+            graphics.shader(invalid, Shader::PASS_FRAGMENT),
+            Err(GlowError::ShaderCompile {
+                stage: GlowShaderStage::Vertex,
+                ..
+            })
+        ));
+        assert!(matches!(
+            graphics.shader(Shader::PASS_VERTEX_3D, invalid),
+            Err(GlowError::ShaderCompile {
+                stage: GlowShaderStage::Fragment,
+                ..
+            })
+        ));
+    }
+}
@@ -1,21 +1,32 @@
-use crate::renderer::{
-    GlowBatch, GlowBlending, GlowRenderer, GlowState, GlowTextureFiltering, GlowTextureFormat,
-    GlowUniformValue, GlowVertexAttrib, GlowVertexAttribs,
+use crate::{
+    error::{GlowError, GlowShaderStage},
+    renderer::{
+        GlowBatch, GlowBlending, GlowCulling, GlowDepthFunc, GlowInstances, GlowRenderer,
+        GlowState, GlowTextureFiltering, GlowTextureFormat, GlowTextureWrap, GlowTopology,
+        GlowUniformValue, GlowVertexAttrib, GlowVertexAttribs,
+    },
 };
 use bytemuck::{Pod, Zeroable};
 use glow::{
-    Context, Framebuffer as GlowFrameBuffer, HasContext, Program as GlowProgram,
-    Shader as GlowShader, Texture as GlowTexture, BLEND, CLAMP_TO_EDGE, COLOR_ATTACHMENT0,
-    COLOR_BUFFER_BIT, FRAGMENT_SHADER, FRAMEBUFFER, NEAREST, SCISSOR_TEST, TEXTURE_2D_ARRAY,
-    TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TEXTURE_WRAP_R, TEXTURE_WRAP_S, TEXTURE_WRAP_T,
-    UNSIGNED_BYTE, VERTEX_SHADER,
+    Context, Framebuffer as GlowFrameBuffer, HasContext, PixelPackData, PixelUnpackData,
+    Program as GlowProgram, Query, Shader as GlowShader, Texture as GlowTexture, Version, BLEND,
+    COLOR_ATTACHMENT0, COLOR_BUFFER_BIT, CULL_FACE, DEBUG_OUTPUT, DEBUG_OUTPUT_SYNCHRONOUS,
+    DEBUG_SEVERITY_HIGH, DEBUG_SEVERITY_LOW, DEBUG_SEVERITY_MEDIUM, DEBUG_SEVERITY_NOTIFICATION,
+    DEBUG_SOURCE_APPLICATION, DEBUG_TYPE_MARKER, DEPTH_BUFFER_BIT, DEPTH_TEST, FRAGMENT_SHADER,
+    FRAMEBUFFER, MULTISAMPLE, NEAREST, QUERY_RESULT, QUERY_RESULT_AVAILABLE, RGBA, SAMPLES,
+    SCISSOR_TEST, TEXTURE_2D_ARRAY, TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TEXTURE_WRAP_R,
+    TEXTURE_WRAP_S, TEXTURE_WRAP_T, TIME_ELAPSED, UNPACK_ALIGNMENT, UNSIGNED_BYTE, VERTEX_SHADER,
 };
-use spitfire_core::{VertexStream, VertexStreamRenderer};
+use spitfire_core::{TriangleIndex, VertexStream, VertexStreamRenderer};
 use std::{
     borrow::Cow,
-    cell::{Cell, Ref, RefCell},
+    cell::{Cell, Ref, RefCell, RefMut},
     collections::HashMap,
     rc::Rc,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
 };
 use vek::{FrustumPlanes, Mat4, Rect, Transform, Vec2};
 
@@ -100,6 +111,15 @@ impl StrongContext {
         self.0.get()
     }
 
+    fn get_mut(&self) -> Option<RefMut<Context>> {
+        let access = (self.0).0.borrow_mut();
+        if access.1 {
+            Some(RefMut::map(access, |access| &mut access.0))
+        } else {
+            None
+        }
+    }
+
     fn new(context: Context) -> Self {
         Self(MaybeContext(Rc::new(RefCell::new((context, true)))))
     }
@@ -108,29 +128,41 @@ impl StrongContext {
 pub struct Graphics<V: GlowVertexAttribs> {
     pub main_camera: Camera,
     pub color: [f32; 4],
+    /// Set when default framebuffer has depth attachment, so it gets
+    /// cleared together with color.
+    pub depth_buffer: bool,
     pub stream: VertexStream<V, GraphicsBatch>,
     state: GlowState,
     context: StrongContext,
     surface_stack: Vec<(Surface, Vec2<f32>, [f32; 4])>,
+    debug_min_severity: RefCell<Option<Arc<AtomicU32>>>,
+    gpu_timer: RefCell<GpuTimer>,
+    samples: u32,
 }
 
 impl<V: GlowVertexAttribs> Drop for Graphics<V> {
     fn drop(&mut self) {
         if let Some(context) = self.context.get() {
             self.state.dispose(&context);
+            self.gpu_timer.borrow_mut().dispose(&context);
         }
     }
 }
 
 impl<V: GlowVertexAttribs> Graphics<V> {
     pub fn new(context: Context) -> Self {
+        let samples = unsafe { context.get_parameter_i32(SAMPLES).max(0) as u32 };
         Self {
             main_camera: Default::default(),
             color: [1.0, 1.0, 1.0, 1.0],
+            depth_buffer: false,
             stream: Default::default(),
             state: Default::default(),
             context: StrongContext::new(context),
             surface_stack: Default::default(),
+            debug_min_severity: Default::default(),
+            gpu_timer: Default::default(),
+            samples,
         }
     }
 
@@ -138,18 +170,129 @@ impl<V: GlowVertexAttribs> Graphics<V> {
         self.context.get()
     }
 
-    pub fn surface(&self, attachments: Vec<SurfaceAttachment>) -> Result<Surface, String> {
+    /// MSAA samples count of default framebuffer, 0 when not multisampled.
+    pub fn multisampling(&self) -> u32 {
+        self.samples
+    }
+
+    /// Tells if instanced batches can be drawn - always on WebGL2 and GLES 3,
+    /// on desktop GL since 3.3 or with `GL_ARB_instanced_arrays`.
+    pub fn supports_instancing(&self) -> bool {
+        self.context
+            .get()
+            .map(|context| {
+                let version = context.version();
+                version.is_embedded
+                    || (version.major, version.minor) >= (3, 3)
+                    || context
+                        .supported_extensions()
+                        .contains("GL_ARB_instanced_arrays")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Version of GL context that driver actually provided.
+    pub fn gl_version(&self) -> Option<Version> {
+        self.context.get().map(|context| context.version().clone())
+    }
+
+    /// Installs `KHR_debug` message callback that prints driver messages of
+    /// at least given severity with [`console_log!`](crate::console_log).
+    /// Calling it again only changes severity filter.
+    /// Returns false if debug output is not supported (e.g. on WebGL2).
+    pub fn enable_debug_output(&self, min_severity: DebugSeverity) -> bool {
+        let Some(mut context) = self.context.get_mut() else {
+            return false;
+        };
+        if !context.supports_debug() {
+            return false;
+        }
+        let mut debug_min_severity = self.debug_min_severity.borrow_mut();
+        if let Some(value) = debug_min_severity.as_ref() {
+            value.store(min_severity as u32, Ordering::Relaxed);
+        } else {
+            let value = Arc::new(AtomicU32::new(min_severity as u32));
+            *debug_min_severity = Some(value.clone());
+            unsafe {
+                context.debug_message_callback(move |source, kind, id, severity, message| {
+                    let severity = DebugSeverity::from_gl(severity);
+                    if severity as u32 >= value.load(Ordering::Relaxed) {
+                        crate::console_log!(
+                            "* GL Debug [{:?}] source: {:#x}, type: {:#x}, id: {}: {}",
+                            severity,
+                            source,
+                            kind,
+                            id,
+                            message
+                        );
+                    }
+                });
+            }
+        }
+        unsafe {
+            context.enable(DEBUG_OUTPUT);
+            context.enable(DEBUG_OUTPUT_SYNCHRONOUS);
+        }
+        true
+    }
+
+    pub fn disable_debug_output(&self) {
+        if let Some(context) = self.context.get() {
+            if context.supports_debug() {
+                unsafe {
+                    context.disable(DEBUG_OUTPUT);
+                }
+            }
+        }
+    }
+
+    /// Inserts application message into GL debug output stream.
+    pub fn debug_message(&self, severity: DebugSeverity, message: &str) {
+        if let Some(context) = self.context.get() {
+            if context.supports_debug() {
+                unsafe {
+                    context.debug_message_insert(
+                        DEBUG_SOURCE_APPLICATION,
+                        DEBUG_TYPE_MARKER,
+                        0,
+                        severity.into_gl(),
+                        message,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Starts measuring GPU time of commands issued until [`Self::end_gpu_timer`].
+    /// Does nothing if timer queries are not supported.
+    pub fn begin_gpu_timer(&self) {
+        if let Some(context) = self.context.get() {
+            self.gpu_timer.borrow_mut().begin(&context);
+        }
+    }
+
+    /// Stops GPU timer and returns GPU time in milliseconds.
+    ///
+    /// Queries are double-buffered so reading results never stalls pipeline,
+    /// which means returned time is the one measured one frame earlier.
+    /// First call returns `None`, as well as calls made when previous result
+    /// is not ready yet or timer queries are not supported (e.g. on WebGL2).
+    pub fn end_gpu_timer(&self) -> Option<f64> {
+        let context = self.context.get()?;
+        self.gpu_timer.borrow_mut().end(&context)
+    }
+
+    pub fn surface(&self, attachments: Vec<SurfaceAttachment>) -> Result<Surface, GlowError> {
         if attachments.is_empty() {
-            return Err("Surface must have at least one texture!".to_owned());
+            return Err(GlowError::SurfaceEmpty);
         }
         for (index, attachment) in attachments.iter().enumerate() {
             if attachment.texture.depth() < attachment.layer as _ {
-                return Err(format!(
-                    "Surface texture #{} has layer: {} out of texture depth range: {}",
+                return Err(GlowError::SurfaceLayerOutOfRange {
                     index,
-                    attachment.layer,
-                    attachment.texture.depth()
-                ));
+                    layer: attachment.layer,
+                    depth: attachment.texture.depth(),
+                });
             }
         }
         if let [first, rest @ ..] = attachments.as_slice() {
@@ -159,15 +302,14 @@ impl<V: GlowVertexAttribs> Graphics<V> {
                 .iter()
                 .any(|item| item.texture.width() != width || item.texture.height() != height)
             {
-                return Err(format!(
-                    "Some surface texture has different size than expected: {} x {}",
-                    width, height
-                ));
+                return Err(GlowError::SurfaceSizeMismatch { width, height });
             }
         }
         unsafe {
             if let Some(context) = self.context.get() {
-                let framebuffer = context.create_framebuffer()?;
+                let framebuffer = context
+                    .create_framebuffer()
+                    .map_err(GlowError::ResourceCreate)?;
                 context.bind_framebuffer(FRAMEBUFFER, Some(framebuffer));
                 for (index, attachment) in attachments.iter().enumerate() {
                     context.framebuffer_texture_layer(
@@ -188,12 +330,23 @@ impl<V: GlowVertexAttribs> Graphics<V> {
                     }),
                 })
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
             }
         }
     }
 
-    pub fn pixel_texture(&self, color: [u8; 3]) -> Result<Texture, String> {
+    /// Creates surface with single color texture of given size, to render
+    /// into with [`Self::draw_to`] and later sample its texture like any other.
+    pub fn render_target(
+        &self,
+        width: u32,
+        height: u32,
+        format: GlowTextureFormat,
+    ) -> Result<Surface, GlowError> {
+        self.surface(vec![self.texture(width, height, 1, format, None)?.into()])
+    }
+
+    pub fn pixel_texture(&self, color: [u8; 3]) -> Result<Texture, GlowError> {
         self.texture(1, 1, 1, GlowTextureFormat::Rgb, Some(&color))
     }
 
@@ -204,99 +357,164 @@ impl<V: GlowVertexAttribs> Graphics<V> {
         depth: u32,
         format: GlowTextureFormat,
         data: Option<&[u8]>,
-    ) -> Result<Texture, String> {
+    ) -> Result<Texture, GlowError> {
         unsafe {
             if let Some(context) = self.context.get() {
-                let texture = context.create_texture()?;
+                let texture = context.create_texture().map_err(GlowError::TextureCreate)?;
                 let mut result = Texture {
                     inner: Rc::new(TextureInner {
                         context: self.context.0.clone(),
                         texture,
                         size: Cell::new((0, 0, 0)),
                         format: Cell::new(format),
+                        wrap: Default::default(),
+                        mipmaps: Cell::new(false),
                     }),
                 };
-                result.upload(width, height, depth, format, data);
+                result.upload(width, height, depth, format, data)?;
                 Ok(result)
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
             }
         }
     }
 
-    pub fn shader(&self, vertex: &str, fragment: &str) -> Result<Shader, String> {
+    pub fn shader(&self, vertex: &str, fragment: &str) -> Result<Shader, GlowError> {
         unsafe {
             if let Some(context) = self.context.get() {
-                let vertex_shader = context.create_shader(VERTEX_SHADER)?;
-                let fragment_shader = context.create_shader(FRAGMENT_SHADER)?;
-                let program = context.create_program()?;
-                context.shader_source(vertex_shader, vertex);
-                context.compile_shader(vertex_shader);
-                if !context.get_shader_compile_status(vertex_shader) {
-                    return Err(format!(
-                        "Vertex Shader: {}",
-                        context.get_shader_info_log(vertex_shader)
-                    ));
-                }
-                context.shader_source(fragment_shader, fragment);
-                context.compile_shader(fragment_shader);
-                if !context.get_shader_compile_status(fragment_shader) {
-                    return Err(format!(
-                        "Fragment Shader: {}",
-                        context.get_shader_info_log(fragment_shader)
-                    ));
-                }
-                context.attach_shader(program, vertex_shader);
-                context.attach_shader(program, fragment_shader);
-                context.link_program(program);
-                if !context.get_program_link_status(program) {
-                    return Err(format!(
-                        "Shader Program: {}",
-                        context.get_program_info_log(program)
-                    ));
-                }
+                let (program, vertex_shader, fragment_shader) =
+                    Shader::compile(&context, vertex, fragment)?;
                 Ok(Shader {
                     inner: Rc::new(ShaderInner {
                         context: self.context.0.clone(),
-                        program,
-                        vertex_shader,
-                        fragment_shader,
+                        program: Cell::new(program),
+                        vertex_shader: Cell::new(vertex_shader),
+                        fragment_shader: Cell::new(fragment_shader),
                         shared_uniforms: Default::default(),
                     }),
                 })
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
             }
         }
     }
 
-    pub fn prepare_frame(&self, clear: bool) -> Result<(), String> {
+    /// Finds topmost triangle of current frame stream that contains point,
+    /// so it has to be called before stream gets drawn and cleared.
+    /// Point is expected in the same space as emitted vertices, which for
+    /// drawables is world space, since their vertices are already transformed.
+    pub fn pick(&self, world_point: Vec2<f32>) -> Option<PickResult> {
+        Self::pick_stream(&self.stream, world_point)
+    }
+
+    /// Same as [`Self::pick`], but tests given stream. Vertex position is
+    /// read from `a_position` attribute, or first attribute if not found.
+    pub fn pick_stream(
+        stream: &VertexStream<V, GraphicsBatch>,
+        point: Vec2<f32>,
+    ) -> Option<PickResult> {
+        let size = std::mem::size_of::<V>();
+        if size < 8 {
+            return None;
+        }
+        let offset = GlowVertexAttrib::offset_of(V::ATTRIBS, "a_position")
+            .filter(|offset| offset + 8 <= size)
+            .unwrap_or(0);
+        let position = |index: u32| {
+            let bytes = bytemuck::bytes_of(&stream.vertices()[index as usize]);
+            Vec2::from(bytemuck::pod_read_unaligned::<[f32; 2]>(
+                &bytes[offset..(offset + 8)],
+            ))
+        };
+        let triangles = stream.triangles();
+        let batches = stream.batches();
+        batches
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(batch, (_, range))| {
+                // Last batch range gets closed only when stream is rendered.
+                let end = if batch + 1 == batches.len() {
+                    triangles.len()
+                } else {
+                    range.end
+                };
+                (range.start..end).rev().find_map(|index| {
+                    let triangle = triangles[index];
+                    let a = position(triangle.a);
+                    let b = position(triangle.b);
+                    let c = position(triangle.c);
+                    let edge = |a: Vec2<f32>, b: Vec2<f32>| {
+                        (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+                    };
+                    let ab = edge(a, b);
+                    let bc = edge(b, c);
+                    let ca = edge(c, a);
+                    let inside = (ab >= 0.0 && bc >= 0.0 && ca >= 0.0)
+                        || (ab <= 0.0 && bc <= 0.0 && ca <= 0.0);
+                    inside.then_some(PickResult {
+                        batch,
+                        triangle: index,
+                    })
+                })
+            })
+    }
+
+    pub fn prepare_frame(&self, clear: bool) -> Result<(), GlowError> {
+        self.prepare_frame_with(PrepareFrameOptions::default().clear(clear))
+    }
+
+    pub fn prepare_frame_with(&self, options: PrepareFrameOptions) -> Result<(), GlowError> {
         unsafe {
             if let Some(context) = self.context.get() {
-                context.viewport(
-                    0,
-                    0,
-                    self.main_camera.screen_size.x as _,
-                    self.main_camera.screen_size.y as _,
-                );
-                context.bind_texture(TEXTURE_2D_ARRAY, None);
-                context.bind_vertex_array(None);
-                context.use_program(None);
-                context.disable(BLEND);
-                context.disable(SCISSOR_TEST);
-                if clear {
+                // WebGL2 has no MULTISAMPLE switch, antialiasing is decided
+                // at context creation.
+                if self.samples > 0 && !cfg!(target_arch = "wasm32") {
+                    context.enable(MULTISAMPLE);
+                }
+                if options.viewport {
+                    context.viewport(
+                        0,
+                        0,
+                        self.main_camera.screen_size.x as _,
+                        self.main_camera.screen_size.y as _,
+                    );
+                }
+                if options.unbind {
+                    context.bind_texture(TEXTURE_2D_ARRAY, None);
+                    context.bind_vertex_array(None);
+                    context.use_program(None);
+                }
+                if options.disable_blend {
+                    context.disable(BLEND);
+                }
+                if options.disable_scissor {
+                    context.disable(SCISSOR_TEST);
+                }
+                if options.disable_depth_test {
+                    context.disable(DEPTH_TEST);
+                }
+                if options.disable_culling {
+                    context.disable(CULL_FACE);
+                }
+                if options.clear {
                     let [r, g, b, a] = self.color;
                     context.clear_color(r, g, b, a);
-                    context.clear(COLOR_BUFFER_BIT);
+                    // Surfaces have no depth attachment.
+                    if self.depth_buffer && self.surface_stack.is_empty() {
+                        context.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+                    } else {
+                        context.clear(COLOR_BUFFER_BIT);
+                    }
                 }
                 Ok(())
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
             }
         }
     }
 
-    pub fn draw(&mut self) -> Result<(), String> {
+    pub fn draw(&mut self) -> Result<(), GlowError> {
         if let Some(context) = self.context.get() {
             let mut renderer = GlowRenderer::<GraphicsBatch>::new(&context, &mut self.state);
             self.stream.batch_end();
@@ -304,11 +522,93 @@ impl<V: GlowVertexAttribs> Graphics<V> {
             self.stream.clear();
             Ok(())
         } else {
-            Err("Invalid context".to_owned())
+            Err(GlowError::ContextLost)
         }
     }
 
-    pub fn push_surface(&mut self, surface: Surface) -> Result<(), String> {
+    /// Draws external stream right away, e.g. one with `u16` indices to cut
+    /// index upload size. Stream is not cleared, so it can be drawn again.
+    /// Pending frame stream should be drawn first to keep draw order.
+    pub fn draw_stream<I: TriangleIndex>(
+        &mut self,
+        stream: &mut VertexStream<V, GraphicsBatch, I>,
+    ) -> Result<(), GlowError> {
+        if let Some(context) = self.context.get() {
+            let mut renderer = GlowRenderer::<GraphicsBatch>::new(&context, &mut self.state);
+            stream.render(&mut renderer)
+        } else {
+            Err(GlowError::ContextLost)
+        }
+    }
+
+    /// Reads RGBA pixels of given rectangle from currently bound framebuffer
+    /// (top surface or screen), with origin at bottom left. Rows are ordered
+    /// bottom-up, use [`Self::flip_pixels_vertically`] to get top-down image.
+    ///
+    /// On web, screen contents are available only before frame gets
+    /// presented, so read them right after drawing.
+    pub fn read_pixels(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<u8>, GlowError> {
+        let mut result = vec![0; width as usize * height as usize * 4];
+        unsafe {
+            if let Some(context) = self.context.get() {
+                context.read_pixels(
+                    x as _,
+                    y as _,
+                    width as _,
+                    height as _,
+                    RGBA,
+                    UNSIGNED_BYTE,
+                    PixelPackData::Slice(&mut result),
+                );
+                Ok(result)
+            } else {
+                Err(GlowError::ContextLost)
+            }
+        }
+    }
+
+    /// Reverses order of rows of RGBA pixels in place.
+    pub fn flip_pixels_vertically(width: u32, height: u32, data: &mut [u8]) {
+        let stride = width as usize * 4;
+        let height = height as usize;
+        for row in 0..(height / 2) {
+            let (top, bottom) = data.split_at_mut((height - row - 1) * stride);
+            top[(row * stride)..((row + 1) * stride)].swap_with_slice(&mut bottom[..stride]);
+        }
+    }
+
+    /// Renders pending stream into target surface instead of current one,
+    /// then restores previous framebuffer and viewport.
+    ///
+    /// Batches keep projection they were created with, so stream should be
+    /// built with camera matching target size.
+    pub fn draw_to(&mut self, target: &Surface, clear: bool) -> Result<(), GlowError> {
+        self.push_surface(target.clone())?;
+        let result = self
+            .prepare_frame_with(Self::surface_frame_options(clear))
+            .and_then(|_| self.draw());
+        self.pop_surface()?;
+        self.prepare_frame_with(Self::surface_frame_options(false))?;
+        result
+    }
+
+    fn surface_frame_options(clear: bool) -> PrepareFrameOptions {
+        PrepareFrameOptions::default()
+            .clear(clear)
+            .unbind(false)
+            .disable_blend(false)
+            .disable_scissor(false)
+            .disable_depth_test(false)
+            .disable_culling(false)
+    }
+
+    pub fn push_surface(&mut self, surface: Surface) -> Result<(), GlowError> {
         unsafe {
             let old_size = self.main_camera.screen_size;
             let old_color = self.color;
@@ -320,12 +620,12 @@ impl<V: GlowVertexAttribs> Graphics<V> {
                 self.surface_stack.push((surface, old_size, old_color));
                 Ok(())
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
             }
         }
     }
 
-    pub fn pop_surface(&mut self) -> Result<Option<Surface>, String> {
+    pub fn pop_surface(&mut self) -> Result<Option<Surface>, GlowError> {
         unsafe {
             if let Some(context) = self.context.get() {
                 if let Some((surface, size, color)) = self.surface_stack.pop() {
@@ -341,10 +641,192 @@ impl<V: GlowVertexAttribs> Graphics<V> {
                     Ok(None)
                 }
             } else {
-                Err("Invalid context".to_owned())
+                Err(GlowError::ContextLost)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugSeverity {
+    #[default]
+    Notification,
+    Low,
+    Medium,
+    High,
+}
+
+impl DebugSeverity {
+    pub fn from_gl(value: u32) -> Self {
+        match value {
+            DEBUG_SEVERITY_HIGH => Self::High,
+            DEBUG_SEVERITY_MEDIUM => Self::Medium,
+            DEBUG_SEVERITY_LOW => Self::Low,
+            _ => Self::Notification,
+        }
+    }
+
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::Notification => DEBUG_SEVERITY_NOTIFICATION,
+            Self::Low => DEBUG_SEVERITY_LOW,
+            Self::Medium => DEBUG_SEVERITY_MEDIUM,
+            Self::High => DEBUG_SEVERITY_HIGH,
+        }
+    }
+}
+
+#[derive(Default)]
+struct GpuTimer {
+    queries: Option<[Query; 2]>,
+    pending: [bool; 2],
+    current: usize,
+    running: bool,
+}
+
+impl GpuTimer {
+    fn is_supported(context: &Context) -> bool {
+        if cfg!(target_arch = "wasm32") {
+            return false;
+        }
+        let version = context.version();
+        (!version.is_embedded && (version.major, version.minor) >= (3, 3))
+            || context
+                .supported_extensions()
+                .contains("GL_ARB_timer_query")
+    }
+
+    fn begin(&mut self, context: &Context) {
+        if self.running {
+            return;
+        }
+        if self.queries.is_none() {
+            if !Self::is_supported(context) {
+                return;
+            }
+            unsafe {
+                let (Ok(a), Ok(b)) = (context.create_query(), context.create_query()) else {
+                    return;
+                };
+                self.queries = Some([a, b]);
+            }
+        }
+        if let Some(queries) = self.queries {
+            // Result of query that gets reused is dropped if it wasn't read.
+            self.pending[self.current] = false;
+            unsafe {
+                context.begin_query(TIME_ELAPSED, queries[self.current]);
+            }
+            self.running = true;
+        }
+    }
+
+    fn end(&mut self, context: &Context) -> Option<f64> {
+        if !self.running {
+            return None;
+        }
+        let queries = self.queries?;
+        unsafe {
+            context.end_query(TIME_ELAPSED);
+        }
+        self.running = false;
+        self.pending[self.current] = true;
+        self.current = 1 - self.current;
+        let query = queries[self.current];
+        if !self.pending[self.current] {
+            return None;
+        }
+        unsafe {
+            if context.get_query_parameter_u32(query, QUERY_RESULT_AVAILABLE) == 0 {
+                return None;
             }
+            self.pending[self.current] = false;
+            let nanoseconds = context.get_query_parameter_u32(query, QUERY_RESULT);
+            Some(nanoseconds as f64 / 1_000_000.0)
         }
     }
+
+    fn dispose(&mut self, context: &Context) {
+        if let Some(queries) = self.queries.take() {
+            unsafe {
+                for query in queries {
+                    context.delete_query(query);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickResult {
+    /// Index into stream batches.
+    pub batch: usize,
+    /// Index into stream triangles.
+    pub triangle: usize,
+}
+
+/// Selects which parts of GL state [`Graphics::prepare_frame_with`] resets.
+/// By default everything gets reset, same as [`Graphics::prepare_frame`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrepareFrameOptions {
+    pub clear: bool,
+    pub viewport: bool,
+    pub unbind: bool,
+    pub disable_blend: bool,
+    pub disable_scissor: bool,
+    pub disable_depth_test: bool,
+    pub disable_culling: bool,
+}
+
+impl Default for PrepareFrameOptions {
+    fn default() -> Self {
+        Self {
+            clear: true,
+            viewport: true,
+            unbind: true,
+            disable_blend: true,
+            disable_scissor: true,
+            disable_depth_test: true,
+            disable_culling: true,
+        }
+    }
+}
+
+impl PrepareFrameOptions {
+    pub fn clear(mut self, value: bool) -> Self {
+        self.clear = value;
+        self
+    }
+
+    pub fn viewport(mut self, value: bool) -> Self {
+        self.viewport = value;
+        self
+    }
+
+    pub fn unbind(mut self, value: bool) -> Self {
+        self.unbind = value;
+        self
+    }
+
+    pub fn disable_blend(mut self, value: bool) -> Self {
+        self.disable_blend = value;
+        self
+    }
+
+    pub fn disable_scissor(mut self, value: bool) -> Self {
+        self.disable_scissor = value;
+        self
+    }
+
+    pub fn disable_depth_test(mut self, value: bool) -> Self {
+        self.disable_depth_test = value;
+        self
+    }
+
+    pub fn disable_culling(mut self, value: bool) -> Self {
+        self.disable_culling = value;
+        self
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -400,6 +882,8 @@ pub struct Camera {
     pub screen_size: Vec2<f32>,
     pub scaling: CameraScaling,
     pub transform: Transform<f32, f32, f32>,
+    /// Replaces projection computed from scaling and screen size.
+    pub projection_override: Option<Mat4<f32>>,
 }
 
 impl Camera {
@@ -427,6 +911,9 @@ impl Camera {
     }
 
     pub fn world_projection_matrix(&self) -> Mat4<f32> {
+        if let Some(matrix) = self.projection_override {
+            return matrix;
+        }
         let size = self.world_size();
         let offset = size * -self.screen_alignment;
         Mat4::orthographic_without_depth_planes(FrustumPlanes {
@@ -447,6 +934,24 @@ impl Camera {
         self.world_projection_matrix() * self.world_view_matrix()
     }
 
+    /// Converts point in screen pixels (origin at top left) to world space.
+    pub fn screen_to_world(&self, point: Vec2<f32>) -> Vec2<f32> {
+        let point = Vec2::new(
+            point.x / self.screen_size.x * 2.0 - 1.0,
+            1.0 - point.y / self.screen_size.y * 2.0,
+        );
+        self.world_matrix().inverted().mul_point(point)
+    }
+
+    /// Converts point in world space to screen pixels (origin at top left).
+    pub fn world_to_screen(&self, point: Vec2<f32>) -> Vec2<f32> {
+        let point = self.world_matrix().mul_point(point);
+        Vec2::new(
+            (point.x + 1.0) * 0.5 * self.screen_size.x,
+            (1.0 - point.y) * 0.5 * self.screen_size.y,
+        )
+    }
+
     pub fn world_polygon(&self) -> [Vec2<f32>; 4] {
         let matrix = self.world_matrix().inverted();
         [
@@ -470,6 +975,138 @@ impl Camera {
             h: yt - yf,
         }
     }
+
+    /// Resets camera transform and scaling so given world rectangle with
+    /// padding around it is fully visible and centered on screen.
+    pub fn frame_rect(&mut self, rect: Rect<f32, f32>, padding: f32) {
+        let size = Vec2::new(
+            (rect.w + padding * 2.0).max(1.0e-6),
+            (rect.h + padding * 2.0).max(1.0e-6),
+        );
+        self.scaling = CameraScaling::FitToView {
+            size,
+            inside: false,
+        };
+        let world_size = self.world_size();
+        let center = Vec2::new(rect.x + rect.w * 0.5, rect.y + rect.h * 0.5);
+        self.transform = Transform::default();
+        self.transform.position = (center + world_size * (self.screen_alignment - 0.5)).into();
+    }
+}
+
+/// Screen shake that perturbs camera position by decaying pseudo random offset.
+///
+/// Offset applied in previous call to [`Self::apply`] gets removed before
+/// new one is added, so camera can be moved freely between frames. Offsets
+/// depend only on seed and elapsed time, so shake is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraShake {
+    /// Max offset in world units, reached at full trauma.
+    pub intensity: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// How many times per second offset changes direction.
+    pub frequency: f32,
+    pub seed: u64,
+    trauma: f32,
+    time: f32,
+    offset: Vec2<f32>,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new(10.0, 1.0)
+    }
+}
+
+impl CameraShake {
+    pub fn new(intensity: f32, decay: f32) -> Self {
+        Self {
+            intensity,
+            decay,
+            frequency: 15.0,
+            seed: 0,
+            trauma: 0.0,
+            time: 0.0,
+            offset: Default::default(),
+        }
+    }
+
+    pub fn frequency(mut self, value: f32) -> Self {
+        self.frequency = value;
+        self
+    }
+
+    pub fn seed(mut self, value: u64) -> Self {
+        self.seed = value;
+        self
+    }
+
+    /// Adds trauma in 0-1 range. Offset scales with squared trauma, so small
+    /// hits barely move camera and big ones shake it hard.
+    pub fn shake(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Offset currently added to camera position.
+    pub fn offset(&self) -> Vec2<f32> {
+        self.offset
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.trauma > 0.0 || self.offset != Vec2::zero()
+    }
+
+    pub fn apply(&mut self, camera: &mut Camera, delta_time: f32) {
+        camera.transform.position.x -= self.offset.x;
+        camera.transform.position.y -= self.offset.y;
+        self.time += delta_time;
+        self.trauma = (self.trauma - self.decay * delta_time).max(0.0);
+        self.offset = if self.trauma > 0.0 {
+            let time = self.time * self.frequency;
+            Vec2::new(
+                Self::noise(self.seed, 0, time),
+                Self::noise(self.seed, 1, time),
+            ) * self.intensity
+                * self.trauma
+                * self.trauma
+        } else {
+            Vec2::zero()
+        };
+        camera.transform.position.x += self.offset.x;
+        camera.transform.position.y += self.offset.y;
+    }
+
+    /// Removes offset from camera and stops shaking.
+    pub fn stop(&mut self, camera: &mut Camera) {
+        self.trauma = 0.0;
+        self.apply(camera, 0.0);
+    }
+
+    /// Smooth value noise in -1 to 1 range.
+    fn noise(seed: u64, axis: u64, time: f32) -> f32 {
+        let index = time.floor();
+        let factor = time - index;
+        let factor = factor * factor * (3.0 - 2.0 * factor);
+        let from = Self::hash(seed, axis, index as i64);
+        let to = Self::hash(seed, axis, index as i64 + 1);
+        from + (to - from) * factor
+    }
+
+    fn hash(seed: u64, axis: u64, index: i64) -> f32 {
+        // SplitMix64 finalizer.
+        let mut value = seed
+            .wrapping_add(axis.wrapping_mul(0x9E3779B97F4A7C15))
+            .wrapping_add((index as u64).wrapping_mul(0xBF58476D1CE4E5B9));
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+        value ^= value >> 31;
+        (value >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -480,6 +1117,17 @@ pub struct GraphicsBatch {
     /// (source, destination)?
     pub blending: GlowBlending,
     pub scissor: Option<Rect<i32, i32>>,
+    /// Enables depth testing against depth buffer. 2D shaders read vertex
+    /// depth from `u_depth` uniform.
+    pub depth_test: Option<GlowDepthFunc>,
+    /// Discards triangles facing away (or towards) camera, usually needed
+    /// by closed 3D meshes.
+    pub culling: Option<GlowCulling>,
+    /// Lines and points ignore culling and are rasterized one pixel wide.
+    pub topology: GlowTopology,
+    /// Draws batch geometry once per instance, see
+    /// [`Graphics::supports_instancing`].
+    pub instances: Option<GlowInstances>,
 }
 
 #[allow(clippy::from_over_into)]
@@ -494,7 +1142,7 @@ impl Into<GlowBatch> for GraphicsBatch {
                 } else {
                     uniforms
                         .iter()
-                        .map(|(k, v)| (k.clone(), *v))
+                        .map(|(k, v)| (k.clone(), v.clone()))
                         .chain(self.uniforms)
                         .collect()
                 }
@@ -511,6 +1159,10 @@ impl Into<GlowBatch> for GraphicsBatch {
                 .collect(),
             blending: self.blending.into_gl(),
             scissor: self.scissor.map(|v| [v.x, v.y, v.w, v.h]),
+            depth_test: self.depth_test.map(|v| v.into_gl()),
+            culling: self.culling.map(|v| v.into_gl()),
+            topology: self.topology,
+            instances: self.instances,
         }
     }
 }
@@ -588,6 +1240,8 @@ struct TextureInner {
     texture: GlowTexture,
     format: Cell<GlowTextureFormat>,
     size: Cell<(u32, u32, u32)>,
+    wrap: Cell<GlowTextureWrap>,
+    mipmaps: Cell<bool>,
 }
 
 impl Drop for TextureInner {
@@ -626,6 +1280,61 @@ impl Texture {
         self.inner.format.get()
     }
 
+    pub fn wrap(&self) -> GlowTextureWrap {
+        self.inner.wrap.get()
+    }
+
+    /// Sets how UVs outside of [0, 1] range are sampled, on all axes.
+    pub fn set_wrap(&mut self, wrap: GlowTextureWrap) {
+        unsafe {
+            if let Some(context) = self.inner.context.get() {
+                context.bind_texture(TEXTURE_2D_ARRAY, Some(self.inner.texture));
+                Self::apply_wrap(&context, wrap);
+                self.inner.wrap.set(wrap);
+            }
+        }
+    }
+
+    pub fn mipmaps(&self) -> bool {
+        self.inner.mipmaps.get()
+    }
+
+    /// When enabled, generates mipmaps right away and after every upload,
+    /// so texture can be sampled with `GlowTextureFiltering::LinearMipmap`.
+    /// WebGL2 handles non-power-of-two sizes, but their smaller levels get
+    /// rounded down and may look blurrier than expected. Integer formats
+    /// can't have mipmaps, so they stay without them.
+    pub fn set_mipmaps(&mut self, enabled: bool) {
+        self.inner.mipmaps.set(enabled);
+        unsafe {
+            if let Some(context) = self.inner.context.get() {
+                context.bind_texture(TEXTURE_2D_ARRAY, Some(self.inner.texture));
+                Self::apply_mipmaps(&context, enabled, self.inner.format.get());
+            }
+        }
+    }
+
+    unsafe fn apply_mipmaps(context: &Context, enabled: bool, format: GlowTextureFormat) {
+        let filtering = if enabled && !format.is_integer() {
+            context.generate_mipmap(TEXTURE_2D_ARRAY);
+            GlowTextureFiltering::LinearMipmap
+        } else {
+            GlowTextureFiltering::Nearest
+        };
+        let (min_filter, _) = filtering.into_gl();
+        context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_MIN_FILTER, min_filter);
+    }
+
+    unsafe fn apply_wrap(context: &Context, wrap: GlowTextureWrap) {
+        let wrap = wrap.into_gl();
+        context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_S, wrap);
+        context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_T, wrap);
+        context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_R, wrap);
+    }
+
+    /// Reallocates texture storage, filling it with `data` if provided.
+    /// Data shorter than `width * height * depth` pixels of given format
+    /// gets rejected.
     pub fn upload(
         &mut self,
         width: u32,
@@ -633,15 +1342,19 @@ impl Texture {
         depth: u32,
         format: GlowTextureFormat,
         data: Option<&[u8]>,
-    ) {
+    ) -> Result<(), GlowError> {
+        if let Some(data) = data {
+            Self::validate_data_size(width, height, depth, format, data)?;
+        }
         unsafe {
             if let Some(context) = self.inner.context.get() {
                 context.bind_texture(TEXTURE_2D_ARRAY, Some(self.inner.texture));
-                context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_S, CLAMP_TO_EDGE as _);
-                context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_T, CLAMP_TO_EDGE as _);
-                context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_WRAP_R, CLAMP_TO_EDGE as _);
+                Self::apply_wrap(&context, self.inner.wrap.get());
                 context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_MIN_FILTER, NEAREST as _);
                 context.tex_parameter_i32(TEXTURE_2D_ARRAY, TEXTURE_MAG_FILTER, NEAREST as _);
+                let (pixel_format, pixel_type) = format.into_gl_pixel();
+                // Rows of narrow formats are not always 4 bytes aligned.
+                context.pixel_store_i32(UNPACK_ALIGNMENT, 1);
                 context.tex_image_3d(
                     TEXTURE_2D_ARRAY,
                     0,
@@ -650,12 +1363,185 @@ impl Texture {
                     height as _,
                     depth as _,
                     0,
-                    format.into_gl(),
-                    UNSIGNED_BYTE,
+                    pixel_format,
+                    pixel_type,
                     data,
                 );
+                context.pixel_store_i32(UNPACK_ALIGNMENT, 4);
+                if self.inner.mipmaps.get() {
+                    Self::apply_mipmaps(&context, true, format);
+                }
                 self.inner.size.set((width, height, depth));
                 self.inner.format.set(format);
+                Ok(())
+            } else {
+                Err(GlowError::ContextLost)
+            }
+        }
+    }
+
+    /// Replaces pixels of `width` x `height` region at `x`, `y` of given
+    /// page, without reallocating texture storage.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        page: u32,
+        width: u32,
+        height: u32,
+        format: GlowTextureFormat,
+        data: &[u8],
+    ) -> Result<(), GlowError> {
+        if !Self::region_in_bounds(self.inner.size.get(), x, y, page, width, height) {
+            return Err(GlowError::TextureRegionOutOfBounds {
+                x,
+                y,
+                page,
+                width,
+                height,
+            });
+        }
+        Self::validate_data_size(width, height, 1, format, data)?;
+        unsafe {
+            if let Some(context) = self.inner.context.get() {
+                context.bind_texture(TEXTURE_2D_ARRAY, Some(self.inner.texture));
+                // Region rows are tightly packed, whatever their width is.
+                let (pixel_format, pixel_type) = format.into_gl_pixel();
+                context.pixel_store_i32(UNPACK_ALIGNMENT, 1);
+                context.tex_sub_image_3d(
+                    TEXTURE_2D_ARRAY,
+                    0,
+                    x as _,
+                    y as _,
+                    page as _,
+                    width as _,
+                    height as _,
+                    1,
+                    pixel_format,
+                    pixel_type,
+                    PixelUnpackData::Slice(data),
+                );
+                context.pixel_store_i32(UNPACK_ALIGNMENT, 4);
+                if self.inner.mipmaps.get() {
+                    Self::apply_mipmaps(&context, true, self.inner.format.get());
+                }
+                Ok(())
+            } else {
+                Err(GlowError::ContextLost)
+            }
+        }
+    }
+
+    fn validate_data_size(
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: GlowTextureFormat,
+        data: &[u8],
+    ) -> Result<(), GlowError> {
+        let expected = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|size| size.checked_mul(depth as usize))
+            .and_then(|size| size.checked_mul(format.pixel_size()))
+            .unwrap_or(usize::MAX);
+        if data.len() < expected {
+            Err(GlowError::TextureDataTooShort {
+                expected,
+                actual: data.len(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn region_in_bounds(
+        (texture_width, texture_height, texture_depth): (u32, u32, u32),
+        x: u32,
+        y: u32,
+        page: u32,
+        width: u32,
+        height: u32,
+    ) -> bool {
+        page < texture_depth
+            && x.checked_add(width)
+                .map(|right| right <= texture_width)
+                .unwrap_or_default()
+            && y.checked_add(height)
+                .map(|bottom| bottom <= texture_height)
+                .unwrap_or_default()
+    }
+
+    /// Uploads data same as [`Self::upload`], but for `GlowTextureFormat::Rgba`
+    /// first bleeds colors into fully transparent texels (see [`Self::alpha_bleed`]).
+    pub fn upload_alpha_bleed(
+        &mut self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: GlowTextureFormat,
+        data: Option<&[u8]>,
+        iterations: usize,
+    ) -> Result<(), GlowError> {
+        match (format, data) {
+            (GlowTextureFormat::Rgba, Some(data)) => {
+                Self::validate_data_size(width, height, depth, format, data)?;
+                let mut data = data.to_owned();
+                Self::alpha_bleed(width, height, depth, &mut data, iterations);
+                self.upload(width, height, depth, format, Some(&data))
+            }
+            _ => self.upload(width, height, depth, format, data),
+        }
+    }
+
+    /// Fills RGB of fully transparent texels in RGBA data with average color
+    /// of their non-transparent neighbors, growing by one texel per iteration.
+    /// Alpha stays untouched, so straight alpha blending result does not change,
+    /// but linear filtering no longer samples dark halos around shape edges.
+    pub fn alpha_bleed(width: u32, height: u32, depth: u32, data: &mut [u8], iterations: usize) {
+        let width = width as usize;
+        let height = height as usize;
+        let layer_size = width * height;
+        for layer in data.chunks_exact_mut(layer_size * 4).take(depth as usize) {
+            let mut filled = layer
+                .chunks_exact(4)
+                .map(|texel| texel[3] > 0)
+                .collect::<Vec<_>>();
+            let mut changes = Vec::with_capacity(layer_size);
+            for _ in 0..iterations {
+                changes.clear();
+                for y in 0..height {
+                    for x in 0..width {
+                        let index = y * width + x;
+                        if filled[index] {
+                            continue;
+                        }
+                        let mut color = [0u32; 3];
+                        let mut count = 0;
+                        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                                let neighbor = ny * width + nx;
+                                if filled[neighbor] {
+                                    let texel = &layer[neighbor * 4..neighbor * 4 + 3];
+                                    for (target, source) in color.iter_mut().zip(texel) {
+                                        *target += *source as u32;
+                                    }
+                                    count += 1;
+                                }
+                            }
+                        }
+                        if count > 0 {
+                            changes.push((index, color.map(|channel| (channel / count) as u8)));
+                        }
+                    }
+                }
+                if changes.is_empty() {
+                    break;
+                }
+                for (index, color) in &changes {
+                    layer[index * 4..index * 4 + 3].copy_from_slice(color);
+                    filled[*index] = true;
+                }
             }
         }
     }
@@ -670,9 +1556,9 @@ impl PartialEq for Texture {
 #[derive(Debug)]
 struct ShaderInner {
     context: MaybeContext,
-    program: GlowProgram,
-    vertex_shader: GlowShader,
-    fragment_shader: GlowShader,
+    program: Cell<GlowProgram>,
+    vertex_shader: Cell<GlowShader>,
+    fragment_shader: Cell<GlowShader>,
     shared_uniforms: RefCell<HashMap<Cow<'static, str>, GlowUniformValue>>,
 }
 
@@ -680,9 +1566,9 @@ impl Drop for ShaderInner {
     fn drop(&mut self) {
         unsafe {
             if let Some(context) = self.context.get() {
-                context.delete_program(self.program);
-                context.delete_shader(self.vertex_shader);
-                context.delete_shader(self.fragment_shader);
+                context.delete_program(self.program.get());
+                context.delete_shader(self.vertex_shader.get());
+                context.delete_shader(self.fragment_shader.get());
             }
         }
     }
@@ -732,9 +1618,10 @@ impl Shader {
     layout(location = 2) in vec4 a_color;
     out vec4 v_color;
     uniform mat4 u_projection_view;
+    uniform float u_depth;
 
     void main() {
-        gl_Position = u_projection_view * vec4(a_position, 0.0, 1.0);
+        gl_Position = u_projection_view * vec4(a_position, u_depth, 1.0);
         v_color = a_color;
     }
     "#;
@@ -758,14 +1645,42 @@ impl Shader {
     out vec4 v_color;
     out vec3 v_uv;
     uniform mat4 u_projection_view;
+    uniform float u_depth;
 
     void main() {
-        gl_Position = u_projection_view * vec4(a_position, 0.0, 1.0);
+        gl_Position = u_projection_view * vec4(a_position, u_depth, 1.0);
         v_color = a_color;
         v_uv = a_uv;
     }
     "#;
 
+    /// Instanced variant of [`Self::TEXTURED_VERTEX_2D`], drawing unit quad
+    /// once per instance. Per instance attributes: `i_transform_x` and
+    /// `i_transform_y` rows of 2D affine transform, `i_region` texture rect,
+    /// `i_tint` color and `i_page` texture page.
+    pub const TEXTURED_INSTANCED_VERTEX_2D: &'static str = r#"#version 300 es
+    layout(location = 0) in vec2 a_position;
+    layout(location = 1) in vec3 a_uv;
+    layout(location = 2) in vec4 a_color;
+    layout(location = 3) in vec3 i_transform_x;
+    layout(location = 4) in vec3 i_transform_y;
+    layout(location = 5) in vec4 i_region;
+    layout(location = 6) in vec4 i_tint;
+    layout(location = 7) in float i_page;
+    out vec4 v_color;
+    out vec3 v_uv;
+    uniform mat4 u_projection_view;
+    uniform float u_depth;
+
+    void main() {
+        vec3 point = vec3(a_position, 1.0);
+        vec2 position = vec2(dot(i_transform_x, point), dot(i_transform_y, point));
+        gl_Position = u_projection_view * vec4(position, u_depth, 1.0);
+        v_color = a_color * i_tint;
+        v_uv = vec3(i_region.xy + a_uv.xy * i_region.zw, i_page);
+    }
+    "#;
+
     pub const TEXTURED_VERTEX_3D: &'static str = r#"#version 300 es
     layout(location = 0) in vec3 a_position;
     layout(location = 2) in vec3 a_uv;
@@ -795,6 +1710,82 @@ impl Shader {
     }
     "#;
 
+    /// Writes texture alpha coverage into every channel, meant for drawing
+    /// into single channel (`GlowTextureFormat::Monochromatic`) mask surfaces.
+    pub const COVERAGE_FRAGMENT: &'static str = r#"#version 300 es
+    precision highp float;
+    precision highp int;
+    precision highp sampler2DArray;
+    in vec4 v_color;
+    in vec3 v_uv;
+    out vec4 o_color;
+    uniform sampler2DArray u_image;
+
+    void main() {
+        float coverage = texture(u_image, v_uv).w * v_color.w;
+        o_color = vec4(coverage);
+    }
+    "#;
+
+    /// Meant for vertices with second UV set at location 2, like
+    /// `spitfire_draw::utils::Vertex2UV`.
+    pub const DUAL_TEXTURED_VERTEX_2D: &'static str = r#"#version 300 es
+    layout(location = 0) in vec2 a_position;
+    layout(location = 1) in vec3 a_uv;
+    layout(location = 2) in vec3 a_uv2;
+    layout(location = 3) in vec4 a_color;
+    out vec4 v_color;
+    out vec3 v_uv;
+    out vec3 v_uv2;
+    uniform mat4 u_projection_view;
+    uniform float u_depth;
+
+    void main() {
+        gl_Position = u_projection_view * vec4(a_position, u_depth, 1.0);
+        v_color = a_color;
+        v_uv = a_uv;
+        v_uv2 = a_uv2;
+    }
+    "#;
+
+    /// Multiplies base image by second image (e.g. lightmap or detail map).
+    pub const DUAL_TEXTURED_MULTIPLY_FRAGMENT: &'static str = r#"#version 300 es
+    precision highp float;
+    precision highp int;
+    precision highp sampler2DArray;
+    in vec4 v_color;
+    in vec3 v_uv;
+    in vec3 v_uv2;
+    out vec4 o_color;
+    uniform sampler2DArray u_image;
+    uniform sampler2DArray u_image2;
+
+    void main() {
+        vec4 base = texture(u_image, v_uv);
+        vec4 other = texture(u_image2, v_uv2);
+        o_color = vec4(base.xyz * other.xyz, base.w) * v_color;
+    }
+    "#;
+
+    /// Adds second image color (e.g. emissive map) to base image.
+    pub const DUAL_TEXTURED_ADD_FRAGMENT: &'static str = r#"#version 300 es
+    precision highp float;
+    precision highp int;
+    precision highp sampler2DArray;
+    in vec4 v_color;
+    in vec3 v_uv;
+    in vec3 v_uv2;
+    out vec4 o_color;
+    uniform sampler2DArray u_image;
+    uniform sampler2DArray u_image2;
+
+    void main() {
+        vec4 base = texture(u_image, v_uv);
+        vec4 other = texture(u_image2, v_uv2);
+        o_color = vec4(min(base.xyz + other.xyz * other.w, vec3(1.0)), base.w) * v_color;
+    }
+    "#;
+
     pub const TEXT_VERTEX: &'static str = r#"#version 300 es
     layout(location = 0) in vec2 a_position;
     layout(location = 1) in vec3 a_uv;
@@ -802,9 +1793,10 @@ impl Shader {
     out vec4 v_color;
     out vec3 v_uv;
     uniform mat4 u_projection_view;
+    uniform float u_depth;
 
     void main() {
-        gl_Position = u_projection_view * vec4(a_position, 0.0, 1.0);
+        gl_Position = u_projection_view * vec4(a_position, u_depth, 1.0);
         v_color = a_color;
         v_uv = a_uv;
     }
@@ -825,8 +1817,120 @@ impl Shader {
     }
     "#;
 
+    /// Meant for fonts atlas of signed distance fields (see
+    /// `spitfire_fontdue::TextRenderer::new_sdf`), antialiases glyph edge
+    /// over one screen pixel regardless of text scale.
+    pub const TEXT_SDF_FRAGMENT: &'static str = r#"#version 300 es
+    precision highp float;
+    precision highp int;
+    precision highp sampler2DArray;
+    in vec4 v_color;
+    in vec3 v_uv;
+    out vec4 o_color;
+    uniform sampler2DArray u_image;
+
+    void main() {
+        float distance = texture(u_image, v_uv).x;
+        float width = max(fwidth(distance) * 0.5, 0.0001);
+        float alpha = smoothstep(0.5 - width, 0.5 + width, distance);
+        o_color = vec4(v_color.xyz, v_color.w * alpha);
+    }
+    "#;
+
     pub fn handle(&self) -> GlowProgram {
-        self.inner.program
+        self.inner.program.get()
+    }
+
+    /// Replaces shader sources, so every batch sharing this shader uses new
+    /// program from now on, while shared uniforms are kept. When compilation
+    /// or linking fails, previous program stays in use and error is returned.
+    pub fn recompile(&self, vertex: &str, fragment: &str) -> Result<(), GlowError> {
+        unsafe {
+            if let Some(context) = self.inner.context.get() {
+                let (program, vertex_shader, fragment_shader) =
+                    Self::compile(&context, vertex, fragment)?;
+                context.delete_program(self.inner.program.replace(program));
+                context.delete_shader(self.inner.vertex_shader.replace(vertex_shader));
+                context.delete_shader(self.inner.fragment_shader.replace(fragment_shader));
+                Ok(())
+            } else {
+                Err(GlowError::ContextLost)
+            }
+        }
+    }
+
+    unsafe fn compile(
+        context: &Context,
+        vertex: &str,
+        fragment: &str,
+    ) -> Result<(GlowProgram, GlowShader, GlowShader), GlowError> {
+        let vertex_shader = context
+            .create_shader(VERTEX_SHADER)
+            .map_err(GlowError::ResourceCreate)?;
+        let fragment_shader = match context.create_shader(FRAGMENT_SHADER) {
+            Ok(shader) => shader,
+            Err(error) => {
+                context.delete_shader(vertex_shader);
+                return Err(GlowError::ResourceCreate(error));
+            }
+        };
+        let program = match context.create_program() {
+            Ok(program) => program,
+            Err(error) => {
+                context.delete_shader(vertex_shader);
+                context.delete_shader(fragment_shader);
+                return Err(GlowError::ResourceCreate(error));
+            }
+        };
+        let status = Self::link(
+            context,
+            program,
+            vertex_shader,
+            fragment_shader,
+            vertex,
+            fragment,
+        );
+        if status.is_err() {
+            context.delete_program(program);
+            context.delete_shader(vertex_shader);
+            context.delete_shader(fragment_shader);
+        }
+        status.map(|_| (program, vertex_shader, fragment_shader))
+    }
+
+    unsafe fn link(
+        context: &Context,
+        program: GlowProgram,
+        vertex_shader: GlowShader,
+        fragment_shader: GlowShader,
+        vertex: &str,
+        fragment: &str,
+    ) -> Result<(), GlowError> {
+        context.shader_source(vertex_shader, vertex);
+        context.compile_shader(vertex_shader);
+        if !context.get_shader_compile_status(vertex_shader) {
+            return Err(GlowError::ShaderCompile {
+                stage: GlowShaderStage::Vertex,
+                log: context.get_shader_info_log(vertex_shader),
+            });
+        }
+        context.shader_source(fragment_shader, fragment);
+        context.compile_shader(fragment_shader);
+        if !context.get_shader_compile_status(fragment_shader) {
+            return Err(GlowError::ShaderCompile {
+                stage: GlowShaderStage::Fragment,
+                log: context.get_shader_info_log(fragment_shader),
+            });
+        }
+        context.attach_shader(program, vertex_shader);
+        context.attach_shader(program, fragment_shader);
+        context.link_program(program);
+        if !context.get_program_link_status(program) {
+            return Err(GlowError::ProgramLink(
+                context.get_program_info_log(program),
+            ));
+        }
+        Ok(())
     }
 
     pub fn set_shared_uniform(
@@ -858,3 +1962,377 @@ impl PartialEq for Shader {
         Rc::ptr_eq(&self.inner, &other.inner)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{GlowCullFace, GlowWinding};
+    use vek::Vec3;
+
+    #[test]
+    fn test_texture_alpha_bleed() {
+        #[rustfmt::skip]
+        let mut data = vec![
+            0, 0, 0, 0,   0, 0, 0, 0,     0, 0, 0, 0,   0, 0, 0, 0,
+            0, 0, 0, 0,   200, 100, 0, 255, 0, 0, 0, 0,   0, 0, 0, 0,
+            0, 0, 0, 0,   0, 0, 0, 0,     0, 0, 0, 0,   0, 0, 0, 0,
+        ];
+        Texture::alpha_bleed(4, 3, 1, &mut data, 1);
+        for (index, texel) in data.chunks_exact(4).enumerate() {
+            match index {
+                5 => assert_eq!(texel, [200, 100, 0, 255]),
+                3 | 7 | 11 => assert_eq!(texel, [0, 0, 0, 0]),
+                _ => assert_eq!(texel, [200, 100, 0, 0]),
+            }
+        }
+
+        Texture::alpha_bleed(4, 3, 1, &mut data, 8);
+        for texel in data.chunks_exact(4) {
+            assert_eq!(texel[..3], [200, 100, 0]);
+        }
+        assert_eq!(data.chunks_exact(4).filter(|texel| texel[3] > 0).count(), 1);
+    }
+
+    #[test]
+    fn test_pick() {
+        let quad = |x: f32, y: f32, size: f32| {
+            [
+                Vertex3d {
+                    position: [x, y, 0.0],
+                    ..Default::default()
+                },
+                Vertex3d {
+                    position: [x + size, y, 0.0],
+                    ..Default::default()
+                },
+                Vertex3d {
+                    position: [x + size, y + size, 0.0],
+                    ..Default::default()
+                },
+                Vertex3d {
+                    position: [x, y + size, 0.0],
+                    ..Default::default()
+                },
+            ]
+        };
+        let mut stream = VertexStream::<Vertex3d, GraphicsBatch>::default();
+        stream.batch(GraphicsBatch::default());
+        stream.quad(quad(0.0, 0.0, 100.0));
+        stream.batch(GraphicsBatch {
+            blending: GlowBlending::Alpha,
+            ..Default::default()
+        });
+        stream.quad(quad(50.0, 50.0, 100.0));
+
+        let pick = |x: f32, y: f32| Graphics::pick_stream(&stream, Vec2::new(x, y));
+        assert_eq!(pick(75.0, 75.0).map(|result| result.batch), Some(1));
+        assert_eq!(pick(25.0, 25.0).map(|result| result.batch), Some(0));
+        assert_eq!(pick(125.0, 125.0).map(|result| result.batch), Some(1));
+        assert!(pick(125.0, 25.0).is_none());
+        let result = pick(60.0, 90.0).unwrap();
+        assert!(result.triangle >= 2);
+    }
+
+    #[test]
+    fn test_pick_packed_vertex() {
+        #[derive(Debug, Default, Clone, Copy, Pod, Zeroable)]
+        #[repr(C)]
+        struct PackedVertex {
+            color: [u8; 4],
+            position: [f32; 2],
+        }
+
+        impl GlowVertexAttribs for PackedVertex {
+            const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)] = &[
+                (
+                    "a_color",
+                    GlowVertexAttrib::UnsignedByte {
+                        channels: 4,
+                        normalized: true,
+                    },
+                ),
+                (
+                    "a_position",
+                    GlowVertexAttrib::Float {
+                        channels: 2,
+                        normalized: false,
+                    },
+                ),
+            ];
+        }
+
+        let vertex = |x, y| PackedVertex {
+            color: [255; 4],
+            position: [x, y],
+        };
+        let mut stream = VertexStream::<PackedVertex, GraphicsBatch>::default();
+        stream.batch(GraphicsBatch::default());
+        stream.quad([
+            vertex(0.0, 0.0),
+            vertex(10.0, 0.0),
+            vertex(10.0, 10.0),
+            vertex(0.0, 10.0),
+        ]);
+
+        let pick = |x: f32, y: f32| Graphics::pick_stream(&stream, Vec2::new(x, y));
+        assert!(pick(5.0, 5.0).is_some());
+        assert!(pick(15.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_camera_screen_to_world() {
+        let mut camera = Camera {
+            screen_size: Vec2::new(800.0, 600.0),
+            ..Default::default()
+        };
+        assert!(
+            camera
+                .screen_to_world(Vec2::new(100.0, 50.0))
+                .distance(Vec2::new(100.0, 50.0))
+                < 1.0e-3
+        );
+
+        camera.screen_alignment = Vec2::new(0.5, 0.5);
+        camera.scaling = CameraScaling::Constant(2.0);
+        camera.transform.position.x = 10.0;
+        camera.transform.position.y = -20.0;
+        let world = camera.screen_to_world(Vec2::new(400.0, 300.0));
+        assert!(world.distance(Vec2::new(10.0, -20.0)) < 1.0e-3);
+        let world = camera.screen_to_world(Vec2::zero());
+        assert!(world.distance(Vec2::new(-790.0, -620.0)) < 1.0e-3);
+        let screen = camera.world_to_screen(Vec2::new(-790.0, -620.0));
+        assert!(screen.distance(Vec2::zero()) < 1.0e-3);
+        let screen = camera.world_to_screen(Vec2::new(810.0, 580.0));
+        assert!(screen.distance(Vec2::new(800.0, 600.0)) < 1.0e-3);
+    }
+
+    #[test]
+    fn test_camera_projection_override() {
+        let mut camera = Camera {
+            screen_size: Vec2::new(800.0, 600.0),
+            ..Default::default()
+        };
+        camera.transform.position.x = 10.0;
+        let computed = camera.world_matrix();
+        let computed_rectangle = camera.world_rectangle();
+
+        let projection = Mat4::<f32>::scaling_3d(Vec3::new(0.01, -0.02, 1.0));
+        camera.projection_override = Some(projection);
+        assert_eq!(camera.world_projection_matrix(), projection);
+        assert_eq!(
+            camera.world_matrix(),
+            projection * Mat4::translation_3d(Vec3::new(-10.0, 0.0, 0.0))
+        );
+        let rectangle = camera.world_rectangle();
+        assert!((rectangle.x - -90.0).abs() < 1.0e-4);
+        assert!((rectangle.y - -50.0).abs() < 1.0e-4);
+        assert!((rectangle.w - 200.0).abs() < 1.0e-4);
+        assert!((rectangle.h - 100.0).abs() < 1.0e-4);
+
+        camera.projection_override = None;
+        assert_eq!(camera.world_matrix(), computed);
+        assert_eq!(camera.world_rectangle(), computed_rectangle);
+    }
+
+    #[test]
+    fn test_camera_frame_rect() {
+        let rect = Rect::new(100.0, -50.0, 40.0, 20.0);
+        for screen_alignment in [Vec2::new(0.5, 0.5), Vec2::new(0.0, 0.0)] {
+            for screen_size in [Vec2::new(800.0, 600.0), Vec2::new(300.0, 900.0)] {
+                let mut camera = Camera {
+                    screen_alignment,
+                    screen_size,
+                    ..Default::default()
+                };
+                camera.frame_rect(rect, 5.0);
+                let view = camera.world_rectangle();
+                for corner in [
+                    Vec2::new(rect.x, rect.y),
+                    Vec2::new(rect.x + rect.w, rect.y),
+                    Vec2::new(rect.x + rect.w, rect.y + rect.h),
+                    Vec2::new(rect.x, rect.y + rect.h),
+                ] {
+                    assert!(view.contains_point(corner));
+                }
+                let center = view.center();
+                assert!((center.x - 120.0).abs() < 1.0e-3);
+                assert!((center.y + 40.0).abs() < 1.0e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_depth_test() {
+        let batch: GlowBatch = GraphicsBatch::default().into();
+        assert_eq!(batch.depth_test, None);
+        let batch: GlowBatch = GraphicsBatch {
+            depth_test: Some(GlowDepthFunc::LessEqual),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(batch.depth_test, Some(glow::LEQUAL));
+        assert_eq!(GlowDepthFunc::default().into_gl(), glow::LESS);
+    }
+
+    #[test]
+    fn test_batch_culling() {
+        let batch: GlowBatch = GraphicsBatch::default().into();
+        assert_eq!(batch.culling, None);
+        let batch: GlowBatch = GraphicsBatch {
+            culling: Some(GlowCulling::new(GlowCullFace::Back, GlowWinding::Clockwise)),
+            ..Default::default()
+        }
+        .into();
+        assert_eq!(batch.culling, Some((glow::BACK, glow::CW)));
+        let culling = GlowCulling::default();
+        assert!(culling.culls(GlowWinding::Clockwise));
+        assert!(!culling.culls(GlowWinding::CounterClockwise));
+        let culling = GlowCulling::new(GlowCullFace::Front, GlowWinding::CounterClockwise);
+        assert!(culling.culls(GlowWinding::CounterClockwise));
+    }
+
+    #[test]
+    fn test_texture_region_in_bounds() {
+        let size = (64, 32, 2);
+        assert!(Texture::region_in_bounds(size, 0, 0, 0, 64, 32));
+        assert!(Texture::region_in_bounds(size, 60, 30, 1, 4, 2));
+        assert!(!Texture::region_in_bounds(size, 60, 30, 2, 4, 2));
+        assert!(!Texture::region_in_bounds(size, 61, 0, 0, 4, 2));
+        assert!(!Texture::region_in_bounds(size, 0, 31, 0, 4, 2));
+        assert!(!Texture::region_in_bounds(size, u32::MAX, 0, 0, 4, 2));
+    }
+
+    #[test]
+    fn test_texture_validate_data_size() {
+        let validate = |width, height, depth, format, size| {
+            Texture::validate_data_size(width, height, depth, format, &vec![0; size])
+        };
+        assert!(validate(4, 2, 3, GlowTextureFormat::Rgba, 96).is_ok());
+        assert!(validate(3, 3, 1, GlowTextureFormat::Rgb, 27).is_ok());
+        assert!(validate(3, 3, 1, GlowTextureFormat::R16Uint, 20).is_ok());
+        assert_eq!(
+            validate(4, 2, 3, GlowTextureFormat::Rgba, 95),
+            Err(GlowError::TextureDataTooShort {
+                expected: 96,
+                actual: 95
+            })
+        );
+        assert_eq!(
+            validate(2, 2, 1, GlowTextureFormat::Data32, 63),
+            Err(GlowError::TextureDataTooShort {
+                expected: 64,
+                actual: 63
+            })
+        );
+        assert!(validate(u32::MAX, u32::MAX, u32::MAX, GlowTextureFormat::Rgba, 0).is_err());
+    }
+
+    #[test]
+    fn test_flip_pixels_vertically() {
+        let mut data = (0..24).collect::<Vec<u8>>();
+        Graphics::<Vertex3d>::flip_pixels_vertically(2, 3, &mut data);
+        assert_eq!(&data[0..8], &[16, 17, 18, 19, 20, 21, 22, 23]);
+        assert_eq!(&data[8..16], &[8, 9, 10, 11, 12, 13, 14, 15]);
+        assert_eq!(&data[16..24], &[0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_camera_shake() {
+        let run = |seed| {
+            let mut camera = Camera::default();
+            camera.transform.position = Vec3::new(100.0, 50.0, 0.0);
+            let mut shake = CameraShake::new(10.0, 1.0).seed(seed);
+            shake.shake(1.0);
+            let positions = (0..10)
+                .map(|_| {
+                    shake.apply(&mut camera, 0.05);
+                    camera.transform.position
+                })
+                .collect::<Vec<_>>();
+            (camera, shake, positions)
+        };
+        let (mut camera, mut shake, positions) = run(42);
+        assert_eq!(positions, run(42).2);
+        assert_ne!(positions, run(7).2);
+        assert!(positions.iter().any(|position| position.x != 100.0));
+        assert!(positions.iter().all(
+            |position| (position.x - 100.0).abs() <= 10.0 && (position.y - 50.0).abs() <= 10.0
+        ));
+
+        camera.transform.position.x += 20.0;
+        shake.stop(&mut camera);
+        assert!(!shake.is_active());
+        assert_eq!(camera.transform.position, Vec3::new(120.0, 50.0, 0.0));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_prepare_frame_keeps_blend() {
+        use crate::headless::HeadlessGraphics;
+
+        let headless = HeadlessGraphics::<Vertex3d>::new(1, 1).expect("GL context");
+        let graphics = &headless.graphics;
+        let blend_enabled = || unsafe { graphics.context().unwrap().is_enabled(BLEND) };
+        unsafe { graphics.context().unwrap().enable(BLEND) };
+        graphics
+            .prepare_frame_with(PrepareFrameOptions::default().disable_blend(false))
+            .unwrap();
+        assert!(blend_enabled());
+        graphics.prepare_frame(false).unwrap();
+        assert!(!blend_enabled());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_texture_r8_round_trip() {
+        use crate::headless::HeadlessGraphics;
+
+        let mut headless = HeadlessGraphics::<Vertex3d>::new(1, 1).expect("GL context");
+        let graphics = &mut headless.graphics;
+        let data = [0, 64, 128, 255];
+        let mut texture = graphics
+            .texture(2, 2, 1, GlowTextureFormat::R8, Some(&data))
+            .unwrap();
+        texture.set_mipmaps(true);
+        let surface = graphics.surface(vec![texture.into()]).unwrap();
+        graphics.push_surface(surface).unwrap();
+        let pixels = graphics.read_pixels(0, 0, 2, 2).unwrap();
+        graphics.pop_surface().unwrap();
+        assert_eq!(
+            pixels,
+            vec![0, 0, 0, 255, 64, 0, 0, 255, 128, 0, 0, 255, 255, 0, 0, 255]
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    #[ignore = "requires GL context"]
+    fn test_shader_recompile() {
+        use crate::headless::HeadlessGraphics;
+
+        let headless = HeadlessGraphics::<Vertex3d>::new(1, 1).expect("GL context");
+        let graphics = &headless.graphics;
+        let shader = graphics
+            .shader(Shader::PASS_VERTEX_3D, Shader::PASS_FRAGMENT)
+            .unwrap();
+        let shared = shader.clone();
+        shader
+            .recompile(Shader::COLORED_VERTEX_3D, Shader::PASS_FRAGMENT)
+            .unwrap();
+        let handle = shader.handle();
+        assert_eq!(shared.handle(), handle);
+
+        let invalid = "#version 300 es\nthis is not glsl";
+        assert!(matches!(
+            shader.recompile(invalid, Shader::PASS_FRAGMENT),
+            Err(GlowError::ShaderCompile {
+                stage: GlowShaderStage::Vertex,
+                ..
+            })
+        ));
+        assert_eq!(shader.handle(), handle);
+        assert_eq!(shared.handle(), handle);
+    }
+}
@@ -0,0 +1,123 @@
+//! Offscreen GL context for tests, servers and asset baking.
+//!
+//! Not available on web - browsers only give GL context through canvas, so
+//! there test pure data path instead: [`crate::graphics::GraphicsBatch`]
+//! converts into [`crate::renderer::GlowBatch`] without any context, and
+//! `spitfire-draw` has software rasterizer for streams.
+
+use crate::{error::GlowError, graphics::Graphics, renderer::GlowVertexAttribs};
+use glow::Context;
+use glutin::{
+    dpi::PhysicalSize, event_loop::EventLoop, ContextBuilder, NotCurrent, PossiblyCurrent,
+};
+
+type HeadlessContext = (glutin::Context<NotCurrent>, Option<EventLoop<()>>);
+
+/// [`Graphics`] running on GL context without window.
+///
+/// Headless contexts have no default framebuffer to present, so render into
+/// [`crate::graphics::Surface`] and read results back with
+/// [`Graphics::read_pixels`].
+///
+/// Platform notes:
+/// - Linux and BSD use pbuffer context of X11 or Wayland display. Without
+///   any display (`DISPLAY` and `WAYLAND_DISPLAY` unset) OSMesa context gets
+///   created instead, which needs `libOSMesa` installed at runtime.
+/// - Windows and macOS need desktop session, same as windowed apps.
+/// - Context is made current on calling thread and can't be moved between
+///   threads, so tests creating it should use single test thread or create
+///   it per test.
+pub struct HeadlessGraphics<V: GlowVertexAttribs> {
+    // Graphics go first so their GL resources get dropped while context
+    // is still alive.
+    pub graphics: Graphics<V>,
+    context_wrapper: glutin::Context<PossiblyCurrent>,
+    _event_loop: Option<EventLoop<()>>,
+}
+
+impl<V: GlowVertexAttribs> HeadlessGraphics<V> {
+    pub fn new(width: u32, height: u32) -> Result<Self, GlowError> {
+        let size = PhysicalSize::new(width.max(1), height.max(1));
+        let (context_wrapper, event_loop) = Self::build(size)?;
+        let context_wrapper = unsafe { context_wrapper.make_current() }.map_err(|(_, error)| {
+            GlowError::ResourceCreate(format!(
+                "Could not make headless context a current one: {}",
+                error
+            ))
+        })?;
+        let context = unsafe {
+            Context::from_loader_function(|name| context_wrapper.get_proc_address(name) as *const _)
+        };
+        let mut graphics = Graphics::<V>::new(context);
+        graphics.main_camera.screen_size.x = width as _;
+        graphics.main_camera.screen_size.y = height as _;
+        Ok(Self {
+            graphics,
+            context_wrapper,
+            _event_loop: event_loop,
+        })
+    }
+
+    pub fn context_wrapper(&self) -> &glutin::Context<PossiblyCurrent> {
+        &self.context_wrapper
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+    ))]
+    fn build(size: PhysicalSize<u32>) -> Result<HeadlessContext, GlowError> {
+        use glutin::platform::unix::{EventLoopExtUnix, HeadlessContextExt};
+
+        // Event loop creation panics when there is no display to connect to.
+        let has_display =
+            std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+        if has_display {
+            let event_loop = EventLoop::new_any_thread();
+            let context_wrapper = ContextBuilder::new()
+                .build_headless(&event_loop, size)
+                .or_else(|_| ContextBuilder::new().build_surfaceless(&event_loop))
+                .map_err(Self::creation_error)?;
+            Ok((context_wrapper, Some(event_loop)))
+        } else {
+            let context_wrapper = ContextBuilder::new()
+                .build_osmesa(size)
+                .map_err(Self::creation_error)?;
+            Ok((context_wrapper, None))
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn build(size: PhysicalSize<u32>) -> Result<HeadlessContext, GlowError> {
+        use glutin::platform::windows::EventLoopExtWindows;
+
+        let event_loop = EventLoop::new_any_thread();
+        let context_wrapper = ContextBuilder::new()
+            .build_headless(&event_loop, size)
+            .map_err(Self::creation_error)?;
+        Ok((context_wrapper, Some(event_loop)))
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "windows",
+    )))]
+    fn build(size: PhysicalSize<u32>) -> Result<HeadlessContext, GlowError> {
+        let event_loop = EventLoop::new();
+        let context_wrapper = ContextBuilder::new()
+            .build_headless(&event_loop, size)
+            .map_err(Self::creation_error)?;
+        Ok((context_wrapper, Some(event_loop)))
+    }
+
+    fn creation_error(error: glutin::CreationError) -> GlowError {
+        GlowError::Unsupported(format!("Headless GL context: {}", error))
+    }
+}
@@ -1,5 +1,8 @@
 pub mod app;
+pub mod error;
 pub mod graphics;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod headless;
 pub mod renderer;
 
 #[cfg(target_arch = "wasm32")]
@@ -28,6 +31,8 @@ pub mod log {
 }
 
 pub mod prelude {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use crate::headless::*;
     #[allow(unused_imports)]
-    pub use crate::{app::*, graphics::*, log::*, renderer::*};
+    pub use crate::{app::*, error::*, graphics::*, log::*, renderer::*};
 }
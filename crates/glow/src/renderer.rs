@@ -1,17 +1,35 @@
+use crate::error::GlowError;
 use bytemuck::{checked::cast_slice, Pod};
 use glow::{
-    Buffer, Context, HasContext, Program, Texture, VertexArray, ARRAY_BUFFER, BLEND, DST_COLOR,
-    ELEMENT_ARRAY_BUFFER, FLOAT, INT, LINEAR, NEAREST, ONE, ONE_MINUS_SRC_ALPHA, RGB, RGBA,
-    RGBA16F, RGBA32F, SCISSOR_TEST, SRC_ALPHA, STREAM_DRAW, TEXTURE0, TEXTURE_2D_ARRAY,
-    TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TRIANGLES, UNSIGNED_INT, ZERO,
+    Buffer, Context, HasContext, Program, Texture, UniformLocation, VertexArray, ALWAYS,
+    ARRAY_BUFFER, BACK, BLEND, CCW, CLAMP_TO_EDGE, CULL_FACE, CW, DEPTH_TEST, DST_ALPHA, DST_COLOR,
+    ELEMENT_ARRAY_BUFFER, EQUAL, FLOAT, FRONT, GEQUAL, GREATER, HALF_FLOAT, INT, LEQUAL, LESS,
+    LINEAR, LINEAR_MIPMAP_LINEAR, LINES, MIRRORED_REPEAT, NEAREST, NEVER, NOTEQUAL, ONE,
+    ONE_MINUS_DST_ALPHA, ONE_MINUS_DST_COLOR, ONE_MINUS_SRC_ALPHA, ONE_MINUS_SRC_COLOR, POINTS,
+    R16UI, R32UI, R8, RED, RED_INTEGER, REPEAT, RG, RG16UI, RG8, RGB, RGBA, RGBA16F, RGBA32F,
+    RG_INTEGER, SCISSOR_TEST, SRC_ALPHA, SRC_COLOR, STREAM_DRAW, TEXTURE0, TEXTURE_2D_ARRAY,
+    TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER, TRIANGLES, UNSIGNED_BYTE, UNSIGNED_INT, UNSIGNED_SHORT,
+    ZERO,
 };
-use spitfire_core::{Triangle, VertexStream, VertexStreamRenderer};
-use std::{borrow::Cow, collections::HashMap, marker::PhantomData, ops::Range};
+use spitfire_core::{Triangle, TriangleIndex, VertexStream, VertexStreamRenderer};
+use std::{borrow::Cow, collections::HashMap, marker::PhantomData, ops::Range, rc::Rc};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GlowVertexAttrib {
-    Float { channels: u8, normalized: bool },
-    Integer { channels: u8 },
+    Float {
+        channels: u8,
+        normalized: bool,
+    },
+    Integer {
+        channels: u8,
+    },
+    /// Bytes read as float attribute, mapped to 0-1 range when normalized,
+    /// for example `[u8; 4]` packed colors. Keep attributes that follow it
+    /// 4 bytes aligned.
+    UnsignedByte {
+        channels: u8,
+        normalized: bool,
+    },
 }
 
 impl GlowVertexAttrib {
@@ -19,15 +37,121 @@ impl GlowVertexAttrib {
         match self {
             Self::Float { channels, .. } => *channels,
             Self::Integer { channels } => *channels,
+            Self::UnsignedByte { channels, .. } => *channels,
         }
     }
+
+    /// Number of bytes occupied by attribute in vertex.
+    pub fn size(&self) -> u8 {
+        match self {
+            Self::Float { channels, .. } | Self::Integer { channels } => channels * 4,
+            Self::UnsignedByte { channels, .. } => *channels,
+        }
+    }
+
+    /// Number of bytes occupied by all attributes, used as vertex stride.
+    pub fn stride(attribs: &[(&str, Self)]) -> usize {
+        attribs.iter().map(|(_, info)| info.size() as usize).sum()
+    }
+
+    /// Number of bytes preceding named attribute in vertex.
+    pub fn offset_of(attribs: &[(&str, Self)], name: &str) -> Option<usize> {
+        let index = attribs.iter().position(|(id, _)| *id == name)?;
+        Some(Self::stride(&attribs[..index]))
+    }
 }
 
 pub trait GlowVertexAttribs: Pod {
     const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)];
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// Per instance attributes of instanced batch. They take attribute locations
+/// right after vertex attributes, so shader can read them same way. Data is
+/// shared, so batches can be cloned for rendering without copying it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlowInstances {
+    pub attribs: &'static [(&'static str, GlowVertexAttrib)],
+    pub data: Rc<[u8]>,
+    pub count: usize,
+}
+
+impl Default for GlowInstances {
+    fn default() -> Self {
+        Self {
+            attribs: &[],
+            data: Rc::from(&[][..]),
+            count: 0,
+        }
+    }
+}
+
+impl GlowInstances {
+    pub fn new<I: GlowVertexAttribs>(instances: &[I]) -> Self {
+        Self {
+            attribs: I::ATTRIBS,
+            data: cast_slice::<I, u8>(instances).into(),
+            count: instances.len(),
+        }
+    }
+}
+
+unsafe fn apply_vertex_attribs(
+    context: &Context,
+    attribs: &[(&'static str, GlowVertexAttrib)],
+    first_location: usize,
+    divisor: u32,
+) {
+    let mut offset = 0;
+    let stride = GlowVertexAttrib::stride(attribs);
+    for (index, (_, info)) in attribs.iter().enumerate() {
+        let location = (first_location + index) as u32;
+        match info {
+            GlowVertexAttrib::Float {
+                channels,
+                normalized,
+            } => {
+                context.vertex_attrib_pointer_f32(
+                    location,
+                    *channels as _,
+                    FLOAT,
+                    *normalized,
+                    stride as _,
+                    offset as _,
+                );
+            }
+            GlowVertexAttrib::Integer { channels } => {
+                context.vertex_attrib_pointer_i32(
+                    location,
+                    *channels as _,
+                    INT,
+                    stride as _,
+                    offset as _,
+                );
+            }
+            GlowVertexAttrib::UnsignedByte {
+                channels,
+                normalized,
+            } => {
+                context.vertex_attrib_pointer_f32(
+                    location,
+                    *channels as _,
+                    UNSIGNED_BYTE,
+                    *normalized,
+                    stride as _,
+                    offset as _,
+                );
+            }
+        }
+        context.enable_vertex_attrib_array(location);
+        // Not available without instancing support, so only set when used.
+        if divisor > 0 {
+            context.vertex_attrib_divisor(location, divisor);
+        }
+        offset += info.size();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum GlowUniformValue {
     F1(f32),
     F2([f32; 2]),
@@ -40,6 +164,104 @@ pub enum GlowUniformValue {
     I2([i32; 2]),
     I3([i32; 3]),
     I4([i32; 4]),
+    /// Uploaded at once to `uniform float name[N]`.
+    F1Array(Vec<f32>),
+    F2Array(Vec<[f32; 2]>),
+    F3Array(Vec<[f32; 3]>),
+    F4Array(Vec<[f32; 4]>),
+    M4Array(Vec<[f32; 16]>),
+    /// Uploaded at once to `uniform int name[N]`, or to sampler array like
+    /// `uniform sampler2DArray name[N]` with texture unit per element. GLSL
+    /// ES allows indexing sampler arrays only with constant expressions.
+    I1Array(Vec<i32>),
+    I2Array(Vec<[i32; 2]>),
+    I3Array(Vec<[i32; 3]>),
+    I4Array(Vec<[i32; 4]>),
+}
+
+impl GlowUniformValue {
+    fn upload(&self) -> GlowUniformUpload {
+        match self {
+            Self::F1(value) => GlowUniformUpload::Float(1, std::slice::from_ref(value)),
+            Self::F2(value) => GlowUniformUpload::Float(2, value),
+            Self::F3(value) => GlowUniformUpload::Float(3, value),
+            Self::F4(value) => GlowUniformUpload::Float(4, value),
+            Self::M2(value) => GlowUniformUpload::Matrix(2, value),
+            Self::M3(value) => GlowUniformUpload::Matrix(3, value),
+            Self::M4(value) => GlowUniformUpload::Matrix(4, value),
+            Self::I1(value) => GlowUniformUpload::Integer(1, std::slice::from_ref(value)),
+            Self::I2(value) => GlowUniformUpload::Integer(2, value),
+            Self::I3(value) => GlowUniformUpload::Integer(3, value),
+            Self::I4(value) => GlowUniformUpload::Integer(4, value),
+            Self::F1Array(value) => GlowUniformUpload::Float(1, value),
+            Self::F2Array(value) => GlowUniformUpload::Float(2, cast_slice(value)),
+            Self::F3Array(value) => GlowUniformUpload::Float(3, cast_slice(value)),
+            Self::F4Array(value) => GlowUniformUpload::Float(4, cast_slice(value)),
+            Self::M4Array(value) => GlowUniformUpload::Matrix(4, cast_slice(value)),
+            Self::I1Array(value) => GlowUniformUpload::Integer(1, value),
+            Self::I2Array(value) => GlowUniformUpload::Integer(2, cast_slice(value)),
+            Self::I3Array(value) => GlowUniformUpload::Integer(3, cast_slice(value)),
+            Self::I4Array(value) => GlowUniformUpload::Integer(4, cast_slice(value)),
+        }
+    }
+}
+
+/// Single GL call uploading uniform: (components or matrix size, data).
+#[derive(Debug, PartialEq)]
+enum GlowUniformUpload<'a> {
+    Float(u8, &'a [f32]),
+    Integer(u8, &'a [i32]),
+    Matrix(u8, &'a [f32]),
+}
+
+impl GlowUniformUpload<'_> {
+    unsafe fn apply(self, context: &Context, location: &UniformLocation) {
+        let location = Some(location);
+        match self {
+            Self::Float(1, data) => context.uniform_1_f32_slice(location, data),
+            Self::Float(2, data) => context.uniform_2_f32_slice(location, data),
+            Self::Float(3, data) => context.uniform_3_f32_slice(location, data),
+            Self::Float(_, data) => context.uniform_4_f32_slice(location, data),
+            Self::Integer(1, data) => context.uniform_1_i32_slice(location, data),
+            Self::Integer(2, data) => context.uniform_2_i32_slice(location, data),
+            Self::Integer(3, data) => context.uniform_3_i32_slice(location, data),
+            Self::Integer(_, data) => context.uniform_4_i32_slice(location, data),
+            Self::Matrix(2, data) => context.uniform_matrix_2_f32_slice(location, false, data),
+            Self::Matrix(3, data) => context.uniform_matrix_3_f32_slice(location, false, data),
+            Self::Matrix(_, data) => context.uniform_matrix_4_f32_slice(location, false, data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlowBlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+}
+
+impl GlowBlendFactor {
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::Zero => ZERO,
+            Self::One => ONE,
+            Self::SrcColor => SRC_COLOR,
+            Self::OneMinusSrcColor => ONE_MINUS_SRC_COLOR,
+            Self::DstColor => DST_COLOR,
+            Self::OneMinusDstColor => ONE_MINUS_DST_COLOR,
+            Self::SrcAlpha => SRC_ALPHA,
+            Self::OneMinusSrcAlpha => ONE_MINUS_SRC_ALPHA,
+            Self::DstAlpha => DST_ALPHA,
+            Self::OneMinusDstAlpha => ONE_MINUS_DST_ALPHA,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -47,19 +269,150 @@ pub enum GlowBlending {
     #[default]
     None,
     Alpha,
+    /// Alpha blending of colors already multiplied by their alpha, which also
+    /// accumulates alpha correctly when compositing into render targets.
+    PremultipliedAlpha,
     Multiply,
     Additive,
+    /// Separate factors for color and alpha channels.
+    Custom {
+        src_rgb: GlowBlendFactor,
+        dst_rgb: GlowBlendFactor,
+        src_alpha: GlowBlendFactor,
+        dst_alpha: GlowBlendFactor,
+    },
 }
 
 impl GlowBlending {
-    pub fn into_gl(self) -> Option<(u32, u32)> {
+    /// [source rgb, destination rgb, source alpha, destination alpha]?
+    pub fn factors(self) -> Option<[GlowBlendFactor; 4]> {
+        use GlowBlendFactor::*;
         match self {
             Self::None => None,
-            Self::Alpha => Some((SRC_ALPHA, ONE_MINUS_SRC_ALPHA)),
-            Self::Multiply => Some((DST_COLOR, ZERO)),
-            Self::Additive => Some((ONE, ONE)),
+            Self::Alpha => Some([SrcAlpha, OneMinusSrcAlpha, SrcAlpha, OneMinusSrcAlpha]),
+            Self::PremultipliedAlpha => Some([One, OneMinusSrcAlpha, One, OneMinusSrcAlpha]),
+            Self::Multiply => Some([DstColor, Zero, DstColor, Zero]),
+            Self::Additive => Some([One, One, One, One]),
+            Self::Custom {
+                src_rgb,
+                dst_rgb,
+                src_alpha,
+                dst_alpha,
+            } => Some([src_rgb, dst_rgb, src_alpha, dst_alpha]),
+        }
+    }
+
+    pub fn into_gl(self) -> Option<(u32, u32, u32, u32)> {
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.factors()?.map(|f| f.into_gl());
+        Some((src_rgb, dst_rgb, src_alpha, dst_alpha))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlowDepthFunc {
+    Never,
+    #[default]
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+impl GlowDepthFunc {
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::Never => NEVER,
+            Self::Less => LESS,
+            Self::Equal => EQUAL,
+            Self::LessEqual => LEQUAL,
+            Self::Greater => GREATER,
+            Self::NotEqual => NOTEQUAL,
+            Self::GreaterEqual => GEQUAL,
+            Self::Always => ALWAYS,
+        }
+    }
+}
+
+/// Primitive mode of batch. Stream indices are stored in triples, so with
+/// lines or points every triple holds consecutive indices of primitives -
+/// see `VertexStream::line_list` and `VertexStream::point_list`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlowTopology {
+    #[default]
+    Triangles,
+    Lines,
+    Points,
+}
+
+impl GlowTopology {
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::Triangles => TRIANGLES,
+            Self::Lines => LINES,
+            Self::Points => POINTS,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlowCullFace {
+    Front,
+    #[default]
+    Back,
+}
+
+impl GlowCullFace {
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::Front => FRONT,
+            Self::Back => BACK,
+        }
+    }
+}
+
+/// Winding of front facing triangles in normalized device coordinates.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlowWinding {
+    #[default]
+    CounterClockwise,
+    Clockwise,
+}
+
+impl GlowWinding {
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::CounterClockwise => CCW,
+            Self::Clockwise => CW,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GlowCulling {
+    pub face: GlowCullFace,
+    pub front_face: GlowWinding,
+}
+
+impl GlowCulling {
+    pub fn new(face: GlowCullFace, front_face: GlowWinding) -> Self {
+        Self { face, front_face }
+    }
+
+    /// Tells if triangle of given winding gets discarded.
+    pub fn culls(&self, winding: GlowWinding) -> bool {
+        let front = winding == self.front_face;
+        match self.face {
+            GlowCullFace::Front => front,
+            GlowCullFace::Back => !front,
         }
     }
+
+    pub fn into_gl(self) -> (u32, u32) {
+        (self.face.into_gl(), self.front_face.into_gl())
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -67,6 +420,9 @@ pub enum GlowTextureFiltering {
     #[default]
     Nearest,
     Linear,
+    /// Trilinear filtering, requires texture with mipmaps (see
+    /// `Texture::set_mipmaps`), otherwise texture samples as black.
+    LinearMipmap,
 }
 
 impl GlowTextureFiltering {
@@ -74,6 +430,25 @@ impl GlowTextureFiltering {
         match self {
             Self::Nearest => (NEAREST as _, NEAREST as _),
             Self::Linear => (LINEAR as _, LINEAR as _),
+            Self::LinearMipmap => (LINEAR_MIPMAP_LINEAR as _, LINEAR as _),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GlowTextureWrap {
+    #[default]
+    Clamp,
+    Repeat,
+    MirrorRepeat,
+}
+
+impl GlowTextureWrap {
+    pub fn into_gl(self) -> i32 {
+        match self {
+            Self::Clamp => CLAMP_TO_EDGE as _,
+            Self::Repeat => REPEAT as _,
+            Self::MirrorRepeat => MIRRORED_REPEAT as _,
         }
     }
 }
@@ -84,11 +459,25 @@ pub enum GlowTextureFormat {
     Rgba,
     Rgb,
     Monochromatic,
+    /// Half float RGBA, data uses 2 bytes per channel.
     Data16,
+    /// Float RGBA, data uses 4 bytes per channel.
     Data32,
+    /// Single 8 bit normalized channel, sampled as red.
+    R8,
+    /// Two 8 bit normalized channels, sampled as red and green.
+    Rg8,
+    /// Single 16 bit unsigned integer channel. Integer textures must be
+    /// sampled with `usampler2DArray` and nearest filtering.
+    R16Uint,
+    /// Two 16 bit unsigned integer channels.
+    Rg16Uint,
+    /// Single 32 bit unsigned integer channel.
+    R32Uint,
 }
 
 impl GlowTextureFormat {
+    /// Internal format of texture storage.
     pub fn into_gl(self) -> u32 {
         match self {
             Self::Rgba => RGBA,
@@ -99,6 +488,43 @@ impl GlowTextureFormat {
             Self::Monochromatic => glow::LUMINANCE,
             Self::Data16 => RGBA16F,
             Self::Data32 => RGBA32F,
+            Self::R8 => R8,
+            Self::Rg8 => RG8,
+            Self::R16Uint => R16UI,
+            Self::Rg16Uint => RG16UI,
+            Self::R32Uint => R32UI,
+        }
+    }
+
+    /// (format, type) of pixel data passed to uploads.
+    pub fn into_gl_pixel(self) -> (u32, u32) {
+        match self {
+            Self::Data16 => (RGBA, HALF_FLOAT),
+            Self::Data32 => (RGBA, FLOAT),
+            Self::R8 => (RED, UNSIGNED_BYTE),
+            Self::Rg8 => (RG, UNSIGNED_BYTE),
+            Self::R16Uint => (RED_INTEGER, UNSIGNED_SHORT),
+            Self::Rg16Uint => (RG_INTEGER, UNSIGNED_SHORT),
+            Self::R32Uint => (RED_INTEGER, UNSIGNED_INT),
+            _ => (self.into_gl(), UNSIGNED_BYTE),
+        }
+    }
+
+    /// Integer formats can't be filtered, so they can't have mipmaps either.
+    pub fn is_integer(self) -> bool {
+        matches!(self, Self::R16Uint | Self::Rg16Uint | Self::R32Uint)
+    }
+
+    /// Number of bytes of single pixel data.
+    pub fn pixel_size(self) -> usize {
+        match self {
+            Self::Rgba => 4,
+            Self::Rgb => 3,
+            Self::Monochromatic | Self::R8 => 1,
+            Self::Data16 => 8,
+            Self::Data32 => 16,
+            Self::Rg8 | Self::R16Uint => 2,
+            Self::Rg16Uint | Self::R32Uint => 4,
         }
     }
 }
@@ -109,14 +535,31 @@ pub struct GlowBatch {
     pub uniforms: HashMap<Cow<'static, str>, GlowUniformValue>,
     /// [(texture object, texture target, min filter, mag filter)?]
     pub textures: Vec<(Texture, u32, i32, i32)>,
-    /// (source, destination)?
-    pub blending: Option<(u32, u32)>,
+    /// (source rgb, destination rgb, source alpha, destination alpha)?
+    pub blending: Option<(u32, u32, u32, u32)>,
     /// [x, y, width, height]?
     pub scissor: Option<[i32; 4]>,
+    /// Depth comparison function?
+    pub depth_test: Option<u32>,
+    /// (culled face, front face winding)?
+    pub culling: Option<(u32, u32)>,
+    pub topology: GlowTopology,
+    /// Draws batch triangles once per instance when set.
+    pub instances: Option<GlowInstances>,
 }
 
 impl GlowBatch {
     pub fn draw<V: GlowVertexAttribs>(&self, context: &Context, range: Range<usize>, prev: &Self) {
+        self.draw_with_index_type::<V>(context, range, prev, GlowIndexType::U32);
+    }
+
+    pub fn draw_with_index_type<V: GlowVertexAttribs>(
+        &self,
+        context: &Context,
+        range: Range<usize>,
+        prev: &Self,
+        index_type: GlowIndexType,
+    ) {
         unsafe {
             if let Some(program) = self.shader_program {
                 let changed = prev
@@ -134,53 +577,7 @@ impl GlowBatch {
                     {
                         let location = context.get_uniform_location(program, name.as_ref());
                         if let Some(location) = location {
-                            match value {
-                                GlowUniformValue::F1(value) => {
-                                    context.uniform_1_f32(Some(&location), *value);
-                                }
-                                GlowUniformValue::F2(value) => {
-                                    context.uniform_2_f32_slice(Some(&location), value);
-                                }
-                                GlowUniformValue::F3(value) => {
-                                    context.uniform_3_f32_slice(Some(&location), value);
-                                }
-                                GlowUniformValue::F4(value) => {
-                                    context.uniform_4_f32_slice(Some(&location), value);
-                                }
-                                GlowUniformValue::M2(value) => {
-                                    context.uniform_matrix_2_f32_slice(
-                                        Some(&location),
-                                        false,
-                                        value,
-                                    );
-                                }
-                                GlowUniformValue::M3(value) => {
-                                    context.uniform_matrix_3_f32_slice(
-                                        Some(&location),
-                                        false,
-                                        value,
-                                    );
-                                }
-                                GlowUniformValue::M4(value) => {
-                                    context.uniform_matrix_4_f32_slice(
-                                        Some(&location),
-                                        false,
-                                        value,
-                                    );
-                                }
-                                GlowUniformValue::I1(value) => {
-                                    context.uniform_1_i32(Some(&location), *value);
-                                }
-                                GlowUniformValue::I2(value) => {
-                                    context.uniform_2_i32_slice(Some(&location), value);
-                                }
-                                GlowUniformValue::I3(value) => {
-                                    context.uniform_3_i32_slice(Some(&location), value);
-                                }
-                                GlowUniformValue::I4(value) => {
-                                    context.uniform_4_i32_slice(Some(&location), value);
-                                }
-                            }
+                            value.upload().apply(context, &location);
                         }
                     }
                 }
@@ -196,9 +593,9 @@ impl GlowBatch {
                 }
             }
             if self.blending != prev.blending {
-                if let Some((source, destination)) = self.blending {
+                if let Some((src_rgb, dst_rgb, src_alpha, dst_alpha)) = self.blending {
                     context.enable(BLEND);
-                    context.blend_func(source, destination);
+                    context.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
                 } else {
                     context.disable(BLEND);
                 }
@@ -211,12 +608,71 @@ impl GlowBatch {
                     context.disable(SCISSOR_TEST);
                 }
             }
-            context.draw_elements(
-                TRIANGLES,
-                range.len() as i32 * 3,
-                UNSIGNED_INT,
-                (range.start * std::mem::size_of::<u32>() * 3) as i32,
-            );
+            if self.depth_test != prev.depth_test {
+                if let Some(func) = self.depth_test {
+                    context.enable(DEPTH_TEST);
+                    context.depth_func(func);
+                } else {
+                    context.disable(DEPTH_TEST);
+                }
+            }
+            if self.culling != prev.culling {
+                if let Some((face, front_face)) = self.culling {
+                    context.enable(CULL_FACE);
+                    context.cull_face(face);
+                    context.front_face(front_face);
+                } else {
+                    context.disable(CULL_FACE);
+                }
+            }
+            if let Some(instances) = self.instances.as_ref() {
+                context.draw_elements_instanced(
+                    self.topology.into_gl(),
+                    range.len() as i32 * 3,
+                    index_type.into_gl(),
+                    (range.start * index_type.size() * 3) as i32,
+                    instances.count as i32,
+                );
+            } else {
+                context.draw_elements(
+                    self.topology.into_gl(),
+                    range.len() as i32 * 3,
+                    index_type.into_gl(),
+                    (range.start * index_type.size() * 3) as i32,
+                );
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GlowIndexType {
+    U16,
+    #[default]
+    U32,
+}
+
+impl GlowIndexType {
+    /// Index type matching triangle indices of stream.
+    pub fn of<I: TriangleIndex>() -> Self {
+        if std::mem::size_of::<I>() == std::mem::size_of::<u16>() {
+            Self::U16
+        } else {
+            Self::U32
+        }
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Self::U16 => std::mem::size_of::<u16>(),
+            Self::U32 => std::mem::size_of::<u32>(),
+        }
+    }
+
+    pub fn into_gl(self) -> u32 {
+        match self {
+            Self::U16 => UNSIGNED_SHORT,
+            Self::U32 => UNSIGNED_INT,
         }
     }
 }
@@ -226,15 +682,19 @@ struct GlowMesh {
     vertex_array: VertexArray,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
+    instance_buffer: Buffer,
 }
 
 impl GlowMesh {
-    fn new(context: &Context) -> Result<Self, String> {
+    fn new(context: &Context) -> Result<Self, GlowError> {
         unsafe {
             Ok(GlowMesh {
-                vertex_array: context.create_vertex_array()?,
-                vertex_buffer: context.create_buffer()?,
-                index_buffer: context.create_buffer()?,
+                vertex_array: context
+                    .create_vertex_array()
+                    .map_err(GlowError::ResourceCreate)?,
+                vertex_buffer: context.create_buffer().map_err(GlowError::ResourceCreate)?,
+                index_buffer: context.create_buffer().map_err(GlowError::ResourceCreate)?,
+                instance_buffer: context.create_buffer().map_err(GlowError::ResourceCreate)?,
             })
         }
     }
@@ -244,14 +704,15 @@ impl GlowMesh {
             context.delete_vertex_array(self.vertex_array);
             context.delete_buffer(self.vertex_buffer);
             context.delete_buffer(self.index_buffer);
+            context.delete_buffer(self.instance_buffer);
         }
     }
 
-    fn upload<V: GlowVertexAttribs>(
+    fn upload<V: GlowVertexAttribs, I: TriangleIndex>(
         &self,
         context: &Context,
         vertices: &[V],
-        triangles: &[Triangle],
+        triangles: &[Triangle<I>],
     ) {
         unsafe {
             context.bind_vertex_array(Some(self.vertex_array));
@@ -259,38 +720,34 @@ impl GlowMesh {
             context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(vertices), STREAM_DRAW);
             context.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(self.index_buffer));
             context.buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, cast_slice(triangles), STREAM_DRAW);
-            let mut offset = 0;
-            let stride = V::ATTRIBS
-                .iter()
-                .map(|(_, info)| info.channels() * 4)
-                .sum::<u8>();
-            for (location, (_, info)) in V::ATTRIBS.iter().enumerate() {
-                match info {
-                    GlowVertexAttrib::Float {
-                        channels,
-                        normalized,
-                    } => {
-                        context.vertex_attrib_pointer_f32(
-                            location as _,
-                            *channels as _,
-                            FLOAT,
-                            *normalized,
-                            stride as _,
-                            offset as _,
-                        );
-                    }
-                    GlowVertexAttrib::Integer { channels } => {
-                        context.vertex_attrib_pointer_i32(
-                            location as _,
-                            *channels as _,
-                            INT,
-                            stride as _,
-                            offset as _,
-                        );
-                    }
-                }
-                context.enable_vertex_attrib_array(location as _);
-                offset += info.channels() * 4;
+            apply_vertex_attribs(context, V::ATTRIBS, 0, 0);
+        }
+    }
+
+    fn upload_instances(
+        &self,
+        context: &Context,
+        first_location: usize,
+        instances: &GlowInstances,
+    ) {
+        unsafe {
+            context.bind_buffer(ARRAY_BUFFER, Some(self.instance_buffer));
+            context.buffer_data_u8_slice(ARRAY_BUFFER, &instances.data, STREAM_DRAW);
+            apply_vertex_attribs(context, instances.attribs, first_location, 1);
+        }
+    }
+
+    fn disable_instances(
+        &self,
+        context: &Context,
+        first_location: usize,
+        instances: &GlowInstances,
+    ) {
+        unsafe {
+            for index in 0..instances.attribs.len() {
+                let location = (first_location + index) as u32;
+                context.vertex_attrib_divisor(location, 0);
+                context.disable_vertex_attrib_array(location);
             }
         }
     }
@@ -316,7 +773,7 @@ impl GlowState {
         }
     }
 
-    fn mesh(&mut self, context: &Context) -> Result<GlowMesh, String> {
+    fn mesh(&mut self, context: &Context) -> Result<GlowMesh, GlowError> {
         if let Some(mesh) = self.mesh.as_ref().copied() {
             Ok(mesh)
         } else {
@@ -345,22 +802,229 @@ where
     }
 }
 
-impl<V, B> VertexStreamRenderer<V, B> for GlowRenderer<'_, B>
+/// Uploads stream indices as they are, with `UNSIGNED_SHORT` type for `u16`
+/// streams and `UNSIGNED_INT` for `u32` ones.
+impl<V, B, I> VertexStreamRenderer<V, B, I> for GlowRenderer<'_, B>
 where
     V: GlowVertexAttribs,
     B: Into<GlowBatch> + Default + Clone,
+    I: TriangleIndex,
 {
-    type Error = String;
+    type Error = GlowError;
 
-    fn render(&mut self, stream: &mut VertexStream<V, B>) -> Result<(), Self::Error> {
+    fn render(&mut self, stream: &mut VertexStream<V, B, I>) -> Result<(), Self::Error> {
         let mesh = self.state.mesh(self.context)?;
         mesh.upload(self.context, stream.vertices(), stream.triangles());
+        let index_type = GlowIndexType::of::<I>();
         let mut prev = GlowBatch::default();
         for (batch, range) in stream.batches().iter().cloned() {
-            let batch = batch.into();
-            batch.draw::<V>(self.context, range, &prev);
+            let batch: GlowBatch = batch.into();
+            if let Some(instances) = batch.instances.as_ref() {
+                mesh.upload_instances(self.context, V::ATTRIBS.len(), instances);
+            }
+            batch.draw_with_index_type::<V>(self.context, range, &prev, index_type);
+            if let Some(instances) = batch.instances.as_ref() {
+                mesh.disable_instances(self.context, V::ATTRIBS.len(), instances);
+            }
             prev = batch;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_uniform_array_upload() {
+        let value = GlowUniformValue::F4(Default::default());
+        assert_eq!(value.upload(), GlowUniformUpload::Float(4, &[0.0; 4]));
+
+        let batch = GlowBatch {
+            uniforms: [
+                (
+                    "u_tints".into(),
+                    GlowUniformValue::F4Array(vec![[1.0, 0.0, 0.0, 1.0]; 64]),
+                ),
+                (
+                    "u_transforms".into(),
+                    GlowUniformValue::M4Array(vec![[0.0; 16]; 8]),
+                ),
+                ("u_offsets".into(), GlowUniformValue::F1Array(vec![0.5; 3])),
+                (
+                    "u_images".into(),
+                    GlowUniformValue::I1Array(vec![0, 1, 2, 3]),
+                ),
+                (
+                    "u_cells".into(),
+                    GlowUniformValue::I2Array(vec![[1, 2], [3, 4]]),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let uploads = batch
+            .uniforms
+            .values()
+            .map(|value| value.upload())
+            .collect::<Vec<_>>();
+        // Whole array goes with single call per uniform, not per element.
+        assert_eq!(uploads.len(), 5);
+        assert!(uploads.contains(&GlowUniformUpload::Float(
+            4,
+            &[1.0, 0.0, 0.0, 1.0].repeat(64)
+        )));
+        assert!(uploads.contains(&GlowUniformUpload::Matrix(4, &[0.0; 128])));
+        assert!(uploads.contains(&GlowUniformUpload::Float(1, &[0.5; 3])));
+        assert!(uploads.contains(&GlowUniformUpload::Integer(1, &[0, 1, 2, 3])));
+        assert!(uploads.contains(&GlowUniformUpload::Integer(2, &[1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn test_index_type() {
+        assert_eq!(GlowIndexType::of::<u16>(), GlowIndexType::U16);
+        assert_eq!(GlowIndexType::of::<u32>(), GlowIndexType::U32);
+        assert_eq!(GlowIndexType::U16.size(), 2);
+        assert_eq!(GlowIndexType::U32.size(), 4);
+        assert_eq!(GlowIndexType::U16.into_gl(), UNSIGNED_SHORT);
+    }
+
+    #[test]
+    fn test_texture_filtering_into_gl() {
+        assert_eq!(
+            GlowTextureFiltering::Nearest.into_gl(),
+            (NEAREST as _, NEAREST as _)
+        );
+        assert_eq!(
+            GlowTextureFiltering::LinearMipmap.into_gl(),
+            (LINEAR_MIPMAP_LINEAR as _, LINEAR as _)
+        );
+    }
+
+    #[test]
+    fn test_blending_into_gl() {
+        assert_eq!(GlowBlending::None.into_gl(), None);
+        assert_eq!(
+            GlowBlending::Alpha.into_gl(),
+            Some((
+                SRC_ALPHA,
+                ONE_MINUS_SRC_ALPHA,
+                SRC_ALPHA,
+                ONE_MINUS_SRC_ALPHA
+            ))
+        );
+        assert_eq!(
+            GlowBlending::PremultipliedAlpha.into_gl(),
+            Some((ONE, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA))
+        );
+        assert_eq!(
+            GlowBlending::Custom {
+                src_rgb: GlowBlendFactor::SrcAlpha,
+                dst_rgb: GlowBlendFactor::OneMinusSrcAlpha,
+                src_alpha: GlowBlendFactor::One,
+                dst_alpha: GlowBlendFactor::OneMinusSrcAlpha,
+            }
+            .into_gl(),
+            Some((SRC_ALPHA, ONE_MINUS_SRC_ALPHA, ONE, ONE_MINUS_SRC_ALPHA))
+        );
+    }
+
+    #[test]
+    fn test_texture_format_into_gl() {
+        assert_eq!(
+            GlowTextureFormat::Rgba.into_gl_pixel(),
+            (RGBA, UNSIGNED_BYTE)
+        );
+        assert_eq!(GlowTextureFormat::Data32.into_gl(), RGBA32F);
+        assert_eq!(GlowTextureFormat::Data32.into_gl_pixel(), (RGBA, FLOAT));
+        assert_eq!(GlowTextureFormat::R8.into_gl(), R8);
+        assert_eq!(GlowTextureFormat::R8.into_gl_pixel(), (RED, UNSIGNED_BYTE));
+        assert_eq!(GlowTextureFormat::Rg8.into_gl_pixel(), (RG, UNSIGNED_BYTE));
+        assert_eq!(
+            GlowTextureFormat::R16Uint.into_gl_pixel(),
+            (RED_INTEGER, UNSIGNED_SHORT)
+        );
+        assert_eq!(
+            GlowTextureFormat::R32Uint.into_gl_pixel(),
+            (RED_INTEGER, UNSIGNED_INT)
+        );
+        assert_eq!(GlowTextureFormat::Rg16Uint.pixel_size(), 4);
+        assert!(GlowTextureFormat::R32Uint.is_integer());
+        assert!(!GlowTextureFormat::R8.is_integer());
+        assert_eq!(GlowTextureFormat::Data16.pixel_size(), 8);
+    }
+
+    #[test]
+    fn test_vertex_attrib_size() {
+        #[repr(C)]
+        #[derive(Clone, Copy, Pod, Zeroable)]
+        struct PackedVertex {
+            color: [u8; 4],
+            position: [f32; 2],
+            index: i32,
+        }
+
+        impl GlowVertexAttribs for PackedVertex {
+            const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)] = &[
+                (
+                    "a_color",
+                    GlowVertexAttrib::UnsignedByte {
+                        channels: 4,
+                        normalized: true,
+                    },
+                ),
+                (
+                    "a_position",
+                    GlowVertexAttrib::Float {
+                        channels: 2,
+                        normalized: false,
+                    },
+                ),
+                ("a_index", GlowVertexAttrib::Integer { channels: 1 }),
+            ];
+        }
+
+        let attribs = PackedVertex::ATTRIBS;
+        assert_eq!(
+            GlowVertexAttrib::stride(attribs),
+            std::mem::size_of::<PackedVertex>()
+        );
+        assert_eq!(GlowVertexAttrib::offset_of(attribs, "a_color"), Some(0));
+        assert_eq!(GlowVertexAttrib::offset_of(attribs, "a_position"), Some(4));
+        assert_eq!(GlowVertexAttrib::offset_of(attribs, "a_index"), Some(12));
+        assert_eq!(GlowVertexAttrib::offset_of(attribs, "a_uv"), None);
+        assert_eq!(attribs[0].1.channels(), 4);
+    }
+
+    #[test]
+    fn test_instances() {
+        #[derive(Clone, Copy, Pod, Zeroable)]
+        #[repr(C)]
+        struct Instance {
+            offset: [f32; 2],
+        }
+
+        impl GlowVertexAttribs for Instance {
+            const ATTRIBS: &'static [(&'static str, GlowVertexAttrib)] = &[(
+                "i_offset",
+                GlowVertexAttrib::Float {
+                    channels: 2,
+                    normalized: false,
+                },
+            )];
+        }
+
+        let instances = GlowInstances::new(&[
+            Instance { offset: [1.0, 2.0] },
+            Instance { offset: [3.0, 4.0] },
+            Instance { offset: [5.0, 6.0] },
+        ]);
+        assert_eq!(instances.count, 3);
+        assert_eq!(instances.data.len(), 24);
+        assert_eq!(instances.attribs, Instance::ATTRIBS);
+        assert_eq!(&instances.data[8..12], &3.0f32.to_ne_bytes());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{interactions::GuiInteractionsEngine, prelude::GuiRenderer};
+use crate::{
+    interactions::GuiInteractionsEngine,
+    prelude::{GuiProceduralCallback, GuiRenderer},
+};
 #[cfg(target_arch = "wasm32")]
 use instant::Instant;
 use raui_core::prelude::*;
@@ -6,6 +9,7 @@ use raui_immediate::*;
 use spitfire_draw::prelude::*;
 use spitfire_fontdue::*;
 use spitfire_glow::prelude::*;
+use std::collections::HashMap;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
@@ -18,6 +22,7 @@ pub struct GuiContext {
     immediate: ImmediateContext,
     timer: Instant,
     glyphs_texture: Option<Texture>,
+    procedurals: HashMap<String, GuiProceduralCallback>,
 }
 
 impl Default for GuiContext {
@@ -31,6 +36,7 @@ impl Default for GuiContext {
             immediate: Default::default(),
             timer: Instant::now(),
             glyphs_texture: None,
+            procedurals: Default::default(),
         }
     }
 }
@@ -40,6 +46,21 @@ impl GuiContext {
         self.application.mark_dirty();
     }
 
+    /// Registers callback that draws `ImageBoxMaterial::Procedural` images
+    /// with given id. Images with unregistered ids are not drawn.
+    pub fn register_procedural(
+        &mut self,
+        id: impl ToString,
+        callback: impl FnMut(&ImageBoxProcedural, vek::Rect<f32, f32>, &mut DrawContext, &mut Graphics<Vertex>)
+            + 'static,
+    ) {
+        self.procedurals.insert(id.to_string(), Box::new(callback));
+    }
+
+    pub fn unregister_procedural(&mut self, id: &str) -> bool {
+        self.procedurals.remove(id).is_some()
+    }
+
     pub fn begin_frame(&self) {
         ImmediateContext::activate(&self.immediate);
         begin();
@@ -81,6 +102,7 @@ impl GuiContext {
         self.application.consume_signals();
         let mut renderer = GuiRenderer {
             texture_filtering: self.texture_filtering,
+            procedurals: &mut self.procedurals,
             draw,
             graphics,
             colored_shader,
@@ -90,7 +112,7 @@ impl GuiContext {
         let _ = self.application.render(&coords_mapping, &mut renderer);
         let [w, h, d] = self.text_renderer.atlas_size();
         if let Some(texture) = self.glyphs_texture.as_mut() {
-            texture.upload(
+            let _ = texture.upload(
                 w as _,
                 h as _,
                 d as _,
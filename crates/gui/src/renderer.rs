@@ -2,10 +2,18 @@ use fontdue::layout::{HorizontalAlign, VerticalAlign};
 use raui_core::prelude::*;
 use spitfire_draw::prelude::*;
 use spitfire_glow::prelude::*;
+use std::collections::HashMap;
 use vek::{Rgba, Vec2};
 
+/// Draws procedural image box content into given screen space rect.
+pub type GuiProceduralCallback = Box<
+    dyn FnMut(&ImageBoxProcedural, vek::Rect<f32, f32>, &mut DrawContext, &mut Graphics<Vertex>),
+>;
+
 pub struct GuiRenderer<'a> {
     pub texture_filtering: GlowTextureFiltering,
+    /// Callbacks for procedural image materials, by procedural id.
+    pub procedurals: &'a mut HashMap<String, GuiProceduralCallback>,
     pub draw: &'a mut DrawContext,
     pub graphics: &'a mut Graphics<Vertex>,
     pub colored_shader: &'a ShaderRef,
@@ -14,6 +22,22 @@ pub struct GuiRenderer<'a> {
 }
 
 impl GuiRenderer<'_> {
+    /// Pushes scissor covering whole pixels of screen space rect.
+    fn push_clip(draw: &mut DrawContext, graphics: &Graphics<Vertex>, rect: Rect) {
+        // Scissor uses framebuffer coordinates with origin at bottom.
+        let screen_height = graphics.main_camera.screen_size.y;
+        let left = rect.left.floor() as i32;
+        let right = rect.right.ceil() as i32;
+        let bottom = (screen_height - rect.bottom).floor() as i32;
+        let top = (screen_height - rect.top).ceil() as i32;
+        draw.push_scissor(vek::Rect {
+            x: left,
+            y: bottom,
+            w: right - left,
+            h: top - bottom,
+        });
+    }
+
     fn draw_node(&mut self, node: &WidgetUnit, mapping: &CoordsMapping, layout: &Layout) {
         match node {
             WidgetUnit::None | WidgetUnit::PortalBox(_) => {}
@@ -21,9 +45,23 @@ impl GuiRenderer<'_> {
                 self.draw_node(&node.slot, mapping, layout);
             }
             WidgetUnit::ContentBox(node) => {
+                let clip = if node.clipping {
+                    layout
+                        .items
+                        .get(&node.id)
+                        .map(|layout| mapping.virtual_to_real_rect(layout.ui_space, false))
+                } else {
+                    None
+                };
+                if let Some(rect) = clip {
+                    Self::push_clip(self.draw, self.graphics, rect);
+                }
                 for item in &node.items {
                     self.draw_node(&item.slot, mapping, layout);
                 }
+                if clip.is_some() {
+                    self.draw.pop_scissor();
+                }
             }
             WidgetUnit::FlexBox(node) => {
                 for item in &node.items {
@@ -214,10 +252,22 @@ impl GuiRenderer<'_> {
                                 }
                             }
                         }
-                        ImageBoxMaterial::Procedural(_) => {
-                            unimplemented!(
-                                "Procedural images are not yet implemented in this version!"
-                            );
+                        ImageBoxMaterial::Procedural(procedural) => {
+                            if let Some(callback) = self.procedurals.get_mut(&procedural.id) {
+                                Self::push_clip(self.draw, self.graphics, rect);
+                                callback(
+                                    procedural,
+                                    vek::Rect {
+                                        x: rect.left,
+                                        y: rect.top,
+                                        w: rect.width(),
+                                        h: rect.height(),
+                                    },
+                                    self.draw,
+                                    self.graphics,
+                                );
+                                self.draw.pop_scissor();
+                            }
                         }
                     }
                 }
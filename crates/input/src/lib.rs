@@ -1,14 +1,34 @@
+#[cfg(feature = "gamepad")]
+use gilrs::{Event as GamepadEvent, EventType as GamepadEventType, Gilrs};
 #[cfg(not(target_arch = "wasm32"))]
-use glutin::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+use glutin::event::{
+    DeviceEvent, ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode,
+    WindowEvent,
+};
+#[cfg(target_arch = "wasm32")]
+use instant::Instant;
+#[cfg(feature = "gamepad")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gamepad")]
+use std::sync::Mutex;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 use std::{
     borrow::Cow,
     cmp::Ordering,
     collections::HashMap,
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    time::Duration,
 };
 use typid::ID;
 #[cfg(target_arch = "wasm32")]
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+use winit::event::{
+    DeviceEvent, ElementState, MouseButton, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode,
+    WindowEvent,
+};
+
+#[cfg(feature = "gamepad")]
+pub use gilrs::{Axis as GamepadAxis, Button as GamepadButton, GamepadId};
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum InputConsume {
@@ -23,6 +43,12 @@ pub enum VirtualAction {
     KeyButton(VirtualKeyCode),
     MouseButton(MouseButton),
     Axis(u32),
+    #[cfg(feature = "gamepad")]
+    GamepadButton(GamepadButton),
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(GamepadAxis),
+    /// Finger touching screen, keyed by touch id.
+    Touch(u64),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -32,8 +58,22 @@ pub enum VirtualAxis {
     MousePositionY,
     MouseWheelX,
     MouseWheelY,
+    /// Relative mouse motion accumulated since last [`InputContext::maintain`].
+    /// Works with grabbed cursor, but requires device events to be passed to
+    /// [`InputContext::on_device_event`].
+    MouseDeltaX,
+    /// See [`Self::MouseDeltaX`].
+    MouseDeltaY,
     MouseButton(MouseButton),
     Axis(u32),
+    #[cfg(feature = "gamepad")]
+    GamepadButton(GamepadButton),
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(GamepadAxis),
+    /// Horizontal position of finger, keyed by touch id.
+    TouchX(u64),
+    /// Vertical position of finger, keyed by touch id.
+    TouchY(u64),
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -146,6 +186,57 @@ pub type InputAxisRef = InputRef<InputAxis>;
 pub type InputCharactersRef = InputRef<InputCharacters>;
 pub type InputMappingRef = InputRef<InputMapping>;
 
+/// Action reference that also measures how long action is held. Hold time
+/// is advanced by [`InputContext::maintain_dt`] for mappings the action was
+/// bound to with [`InputMapping::action_timed`].
+#[derive(Debug, Default, Clone)]
+pub struct InputActionTimedRef {
+    action: InputActionRef,
+    hold_time: InputRef<f32>,
+}
+
+impl InputActionTimedRef {
+    pub fn new(action: InputActionRef) -> Self {
+        Self {
+            action,
+            hold_time: Default::default(),
+        }
+    }
+
+    pub fn action(&self) -> InputActionRef {
+        self.action.clone()
+    }
+
+    pub fn get(&self) -> InputAction {
+        self.action.get()
+    }
+
+    /// Seconds spent in [`InputAction::Hold`] state. Zero when action is
+    /// pressed and gets reset when action gets released.
+    pub fn hold_time(&self) -> f32 {
+        if self.action.get().is_hold() {
+            self.hold_time.get()
+        } else {
+            0.0
+        }
+    }
+
+    fn reset(&self) {
+        self.action.set(InputAction::Idle);
+        self.hold_time.set(0.0);
+    }
+
+    fn advance(&self, dt: f32) {
+        if let Some(mut hold_time) = self.hold_time.write() {
+            if self.action.get().is_hold() {
+                *hold_time += dt;
+            } else {
+                *hold_time = 0.0;
+            }
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub enum InputActionOrAxisRef {
     #[default]
@@ -237,11 +328,117 @@ impl CardinalInputCombinator {
         }))
     }
 
+    /// Applies response curve to magnitude of combined direction, keeping
+    /// direction itself, so diagonals get same response as cardinals.
+    pub fn curved(self, curve: impl Fn(f32) -> f32 + 'static) -> Self {
+        let inner = self.0;
+        Self(InputCombinator::new(move || {
+            let [x, y] = inner.get();
+            let magnitude = x.hypot(y);
+            if magnitude <= f32::EPSILON {
+                return [0.0, 0.0];
+            }
+            let factor = curve(magnitude) / magnitude;
+            [x * factor, y * factor]
+        }))
+    }
+
     pub fn get(&self) -> [f32; 2] {
         self.0.get()
     }
 }
 
+/// Maps scalar input through response curve, e.g. to make aiming less
+/// sensitive around center. Presets keep sign of input, so they work with
+/// both halves of axis.
+#[derive(Default)]
+pub struct CurvedInputCombinator(InputCombinator<f32>);
+
+impl CurvedInputCombinator {
+    pub fn new(
+        input: impl Into<InputActionOrAxisRef>,
+        curve: impl Fn(f32) -> f32 + 'static,
+    ) -> Self {
+        let input = input.into();
+        Self(InputCombinator::new(move || {
+            curve(input.get_scalar(0.0, 1.0))
+        }))
+    }
+
+    pub fn linear(input: impl Into<InputActionOrAxisRef>) -> Self {
+        Self::new(input, |value| value)
+    }
+
+    pub fn quadratic(input: impl Into<InputActionOrAxisRef>) -> Self {
+        Self::power(input, 2.0)
+    }
+
+    pub fn power(input: impl Into<InputActionOrAxisRef>, exponent: f32) -> Self {
+        Self::new(input, move |value| {
+            value.abs().powf(exponent).copysign(value)
+        })
+    }
+
+    /// Values with magnitude below deadzone map to zero, remaining range
+    /// gets rescaled to `0..1` before applying curve.
+    pub fn deadzone(
+        input: impl Into<InputActionOrAxisRef>,
+        deadzone: f32,
+        curve: impl Fn(f32) -> f32 + 'static,
+    ) -> Self {
+        Self::new(input, move |value| {
+            let magnitude = value.abs();
+            if magnitude <= deadzone {
+                return 0.0;
+            }
+            let magnitude = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+            curve(magnitude).copysign(value)
+        })
+    }
+
+    pub fn get(&self) -> f32 {
+        self.0.get()
+    }
+}
+
+/// Low-pass filters input, so its value approaches target value over time
+/// instead of jumping to it. Since combinators only read inputs when asked,
+/// filter state has to be advanced with [`Self::tick`] every frame.
+#[derive(Default)]
+pub struct SmoothedInputCombinator {
+    input: InputActionOrAxisRef,
+    /// Rate of approaching target, in 1/seconds - after `1 / speed` seconds
+    /// about 63% of remaining distance is covered. Filter is exponential, so
+    /// it never overshoots target.
+    pub speed: f32,
+    value: f32,
+}
+
+impl SmoothedInputCombinator {
+    pub fn new(input: impl Into<InputActionOrAxisRef>, speed: f32) -> Self {
+        Self {
+            input: input.into(),
+            speed,
+            value: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self, dt: f32) {
+        let target = self.input.get_scalar(0.0, 1.0);
+        let factor = 1.0 - (-self.speed * dt.max(0.0)).exp();
+        self.value += (target - self.value) * factor;
+    }
+
+    /// Jumps straight to current target value.
+    pub fn reset(&mut self) {
+        self.value = self.input.get_scalar(0.0, 1.0);
+    }
+
+    pub fn get(&self) -> f32 {
+        self.value
+    }
+}
+
 #[derive(Default)]
 pub struct DualInputCombinator(InputCombinator<f32>);
 
@@ -290,6 +487,43 @@ impl<const N: usize> ArrayInputCombinator<N> {
     }
 }
 
+/// Combines actions into chord (e.g. Ctrl+S) that is down only while all of
+/// them are down. It gets pressed when last missing action gets pressed and
+/// released as soon as any of them gets released.
+#[derive(Default)]
+pub struct ChordInputCombinator(InputCombinator<InputAction>);
+
+impl ChordInputCombinator {
+    pub fn new(inputs: impl IntoIterator<Item = InputActionRef>) -> Self {
+        let inputs = inputs.into_iter().collect::<Vec<_>>();
+        Self(InputCombinator::new(move || {
+            if inputs.is_empty() {
+                return InputAction::Idle;
+            }
+            let states = inputs.iter().map(|input| input.get()).collect::<Vec<_>>();
+            if states.iter().all(|state| state.is_down()) {
+                if states.iter().any(|state| state.is_pressed()) {
+                    InputAction::Pressed
+                } else {
+                    InputAction::Hold
+                }
+            } else if states
+                .iter()
+                .all(|state| matches!(state, InputAction::Hold | InputAction::Released))
+            {
+                // All actions were down in previous frame.
+                InputAction::Released
+            } else {
+                InputAction::Idle
+            }
+        }))
+    }
+
+    pub fn get(&self) -> InputAction {
+        self.0.get()
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct InputCharacters {
     characters: String,
@@ -307,28 +541,306 @@ impl InputCharacters {
     pub fn take(&mut self) -> String {
         std::mem::take(&mut self.characters)
     }
+
+    /// Appends clipboard text same way received characters are appended.
+    /// Returns false if clipboard had no text.
+    pub fn paste(&mut self, clipboard: &mut impl ClipboardProvider) -> bool {
+        if let Some(text) = clipboard.get_text() {
+            self.characters.push_str(&text);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Pastes text from system clipboard.
+    #[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+    pub fn paste_from_clipboard(&mut self) -> bool {
+        NativeClipboard::new()
+            .map(|mut clipboard| self.paste(&mut clipboard))
+            .unwrap_or_default()
+    }
+}
+
+/// Source of clipboard text. Implemented for closures, so platforms without
+/// native clipboard (e.g. web, where clipboard access is asynchronous) can
+/// provide text obtained by other means.
+pub trait ClipboardProvider {
+    fn get_text(&mut self) -> Option<String>;
+}
+
+impl<F: FnMut() -> Option<String>> ClipboardProvider for F {
+    fn get_text(&mut self) -> Option<String> {
+        self()
+    }
+}
+
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+pub struct NativeClipboard(arboard::Clipboard);
+
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+impl NativeClipboard {
+    pub fn new() -> Option<Self> {
+        arboard::Clipboard::new().ok().map(Self)
+    }
+}
+
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+impl ClipboardProvider for NativeClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.0.get_text().ok()
+    }
 }
 
 #[derive(Debug, Default, Clone)]
+struct InputMultiClickState {
+    count: u32,
+    last_press: Option<Instant>,
+    down: bool,
+    active: bool,
+}
+
+/// Action that gets pressed only when input was clicked given number of
+/// times, each click within time window from previous one. Supports key,
+/// mouse button and touch inputs.
+#[derive(Debug, Clone)]
+pub struct InputMultiClick {
+    pub action: InputActionRef,
+    pub clicks: u32,
+    pub window: Duration,
+    state: InputRef<InputMultiClickState>,
+}
+
+impl InputMultiClick {
+    pub fn new(action: InputActionRef, clicks: u32, window: Duration) -> Self {
+        Self {
+            action,
+            clicks,
+            window,
+            state: Default::default(),
+        }
+    }
+
+    fn change(&self, hold: bool, now: Instant) -> bool {
+        let Some(mut state) = self.state.write() else {
+            return false;
+        };
+        if hold == state.down {
+            // Ignore key repeats.
+            return true;
+        }
+        state.down = hold;
+        if hold {
+            let expired = state
+                .last_press
+                .map(|time| now.saturating_duration_since(time) > self.window)
+                .unwrap_or(true);
+            if expired {
+                state.count = 0;
+            }
+            state.count += 1;
+            state.last_press = Some(now);
+            if state.count >= self.clicks.max(1) {
+                state.count = 0;
+                state.last_press = None;
+                state.active = true;
+                if let Some(mut action) = self.action.write() {
+                    *action = action.change(true);
+                }
+            }
+        } else if state.active {
+            state.active = false;
+            if let Some(mut action) = self.action.write() {
+                *action = action.change(false);
+            }
+        }
+        true
+    }
+
+    fn reset(&self) {
+        self.action.set(InputAction::Idle);
+        self.state.set(Default::default());
+    }
+
+    fn maintain(&self, now: Instant) {
+        if let Some(mut action) = self.action.write() {
+            *action = action.update();
+        }
+        if let Some(mut state) = self.state.write() {
+            let expired = state
+                .last_press
+                .map(|time| now.saturating_duration_since(time) > self.window)
+                .unwrap_or_default();
+            if expired {
+                state.count = 0;
+                state.last_press = None;
+            }
+        }
+    }
+}
+
+/// Maps raw input value of axis binding. Accumulated axes (mouse delta)
+/// only get scaled, since offset would add up with every event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputAxisTransform {
+    pub scale: f32,
+    pub offset: f32,
+}
+
+impl Default for InputAxisTransform {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl InputAxisTransform {
+    pub fn apply(&self, value: f32) -> f32 {
+        value * self.scale + self.offset
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct InputMapping {
     pub actions: HashMap<VirtualAction, InputActionRef>,
     pub axes: HashMap<VirtualAxis, InputAxisRef>,
+    pub multiclicks: Vec<(VirtualAction, InputMultiClick)>,
+    pub timed_actions: Vec<InputActionTimedRef>,
+    pub axis_transforms: HashMap<VirtualAxis, InputAxisTransform>,
+    /// Gamepad axis values with magnitude below it are treated as `0.0`.
+    #[cfg(feature = "gamepad")]
+    pub gamepad_deadzone: f32,
+    /// When set, mapping only receives gamepad input from gamepad assigned
+    /// to this slot (see [`InputContext::assign_gamepad_slots`]), otherwise
+    /// it receives input combined from all gamepads.
+    #[cfg(feature = "gamepad")]
+    pub gamepad_slot: Option<usize>,
     pub consume: InputConsume,
     pub layer: isize,
     pub name: Cow<'static, str>,
+    /// Disabled mapping is skipped by input processing, but keeps its place
+    /// in stack.
+    pub enabled: bool,
+}
+
+impl Default for InputMapping {
+    fn default() -> Self {
+        Self {
+            actions: Default::default(),
+            axes: Default::default(),
+            multiclicks: Default::default(),
+            timed_actions: Default::default(),
+            axis_transforms: Default::default(),
+            #[cfg(feature = "gamepad")]
+            gamepad_deadzone: 0.0,
+            #[cfg(feature = "gamepad")]
+            gamepad_slot: None,
+            consume: Default::default(),
+            layer: 0,
+            name: Default::default(),
+            enabled: true,
+        }
+    }
 }
 
 impl InputMapping {
+    pub fn from_bindings(
+        actions: impl IntoIterator<Item = (VirtualAction, InputActionRef)>,
+        axes: impl IntoIterator<Item = (VirtualAxis, InputAxisRef)>,
+    ) -> Self {
+        Self::default().bind_many(actions, axes)
+    }
+
+    pub fn bind_many(
+        mut self,
+        actions: impl IntoIterator<Item = (VirtualAction, InputActionRef)>,
+        axes: impl IntoIterator<Item = (VirtualAxis, InputAxisRef)>,
+    ) -> Self {
+        self.actions.extend(actions);
+        self.axes.extend(axes);
+        self
+    }
+
     pub fn action(mut self, id: VirtualAction, action: InputActionRef) -> Self {
         self.actions.insert(id, action);
         self
     }
 
+    /// Binds action that tracks its hold time.
+    pub fn action_timed(mut self, id: VirtualAction, action: InputActionTimedRef) -> Self {
+        self.actions.insert(id, action.action());
+        self.timed_actions.push(action);
+        self
+    }
+
+    /// Binds axis with its input value transformed into `value * scale + offset`.
+    /// Negative scale inverts axis.
+    pub fn axis_scaled(
+        mut self,
+        id: VirtualAxis,
+        axis: InputAxisRef,
+        scale: f32,
+        offset: f32,
+    ) -> Self {
+        self.axes.insert(id, axis);
+        self.axis_transforms
+            .insert(id, InputAxisTransform { scale, offset });
+        self
+    }
+
+    pub fn axis_transform(&self, id: &VirtualAxis) -> InputAxisTransform {
+        self.axis_transforms.get(id).copied().unwrap_or_default()
+    }
+
+    fn transform_axis(&self, id: &VirtualAxis, value: f32) -> f32 {
+        self.axis_transform(id).apply(value)
+    }
+
+    /// Binds action that gets pressed only after `clicks` clicks of input,
+    /// each within `window` from previous one. Single clicks can still be
+    /// observed with regular action bound to the same input.
+    pub fn action_multiclick(
+        mut self,
+        id: VirtualAction,
+        action: InputActionRef,
+        clicks: u32,
+        window: Duration,
+    ) -> Self {
+        self.multiclicks
+            .push((id, InputMultiClick::new(action, clicks, window)));
+        self
+    }
+
+    fn change_multiclicks(&self, id: VirtualAction, hold: bool) -> bool {
+        let now = Instant::now();
+        let mut hit = false;
+        for (multiclick_id, multiclick) in &self.multiclicks {
+            if *multiclick_id == id && multiclick.change(hold, now) {
+                hit = true;
+            }
+        }
+        hit
+    }
+
     pub fn axis(mut self, id: VirtualAxis, axis: InputAxisRef) -> Self {
         self.axes.insert(id, axis);
         self
     }
 
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_deadzone(mut self, value: f32) -> Self {
+        self.gamepad_deadzone = value.abs();
+        self
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_slot(mut self, value: usize) -> Self {
+        self.gamepad_slot = Some(value);
+        self
+    }
+
     pub fn consume(mut self, consume: InputConsume) -> Self {
         self.consume = consume;
         self
@@ -343,6 +855,28 @@ impl InputMapping {
         self.name = value.into();
         self
     }
+
+    pub fn enabled(mut self, value: bool) -> Self {
+        self.enabled = value;
+        self
+    }
+
+    /// Puts all actions into idle state and zeroes all axes, so input held
+    /// while mapping was not receiving events does not stay stuck.
+    pub fn reset(&self) {
+        for action in self.actions.values() {
+            action.set(InputAction::Idle);
+        }
+        for axis in self.axes.values() {
+            axis.set(InputAxis(0.0));
+        }
+        for (_, multiclick) in &self.multiclicks {
+            multiclick.reset();
+        }
+        for action in &self.timed_actions {
+            action.reset();
+        }
+    }
 }
 
 impl From<InputMapping> for InputMappingRef {
@@ -351,20 +885,143 @@ impl From<InputMapping> for InputMappingRef {
     }
 }
 
+/// Translates raw gamepad buttons and axes into logical ones before they
+/// reach input mappings. Entries missing from the table pass through as is.
+#[cfg(feature = "gamepad")]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamepadRemap {
+    #[serde(default)]
+    pub buttons: HashMap<GamepadButton, GamepadButton>,
+    #[serde(default)]
+    pub axes: HashMap<GamepadAxis, GamepadAxis>,
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadRemap {
+    pub fn button(mut self, raw: GamepadButton, logical: GamepadButton) -> Self {
+        self.buttons.insert(raw, logical);
+        self
+    }
+
+    pub fn axis(mut self, raw: GamepadAxis, logical: GamepadAxis) -> Self {
+        self.axes.insert(raw, logical);
+        self
+    }
+
+    pub fn map_button(&self, button: GamepadButton) -> GamepadButton {
+        self.buttons.get(&button).copied().unwrap_or(button)
+    }
+
+    pub fn map_axis(&self, axis: GamepadAxis) -> GamepadAxis {
+        self.axes.get(&axis).copied().unwrap_or(axis)
+    }
+
+    /// Maps raw button values to logical buttons, keeping strongest value
+    /// when many raw buttons share same logical one.
+    pub fn combine_buttons(
+        &self,
+        values: impl IntoIterator<Item = (GamepadButton, f32)>,
+    ) -> HashMap<GamepadButton, f32> {
+        let mut result = HashMap::<GamepadButton, f32>::new();
+        for (button, value) in values {
+            let entry = result.entry(self.map_button(button)).or_default();
+            *entry = entry.max(value);
+        }
+        result
+    }
+
+    /// Maps raw axis values to logical axes, keeping value farthest from
+    /// rest when many raw axes share same logical one.
+    pub fn combine_axes(
+        &self,
+        values: impl IntoIterator<Item = (GamepadAxis, f32)>,
+    ) -> HashMap<GamepadAxis, f32> {
+        let mut result = HashMap::<GamepadAxis, f32>::new();
+        for (axis, value) in values {
+            let entry = result.entry(self.map_axis(axis)).or_default();
+            if value.abs() > entry.abs() {
+                *entry = value;
+            }
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEventKind {
+    KeyButton(VirtualKeyCode),
+    MouseButton(MouseButton),
+    MousePosition,
+    MouseWheel,
+    MouseMotion,
+    Axis(u32),
+    #[cfg(feature = "gamepad")]
+    GamepadButton(GamepadButton),
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(GamepadAxis),
+    Touch(u64),
+}
+
+/// Describes how single mapping reacted to input event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputEventLog<'a> {
+    pub kind: InputEventKind,
+    pub mapping_name: &'a str,
+    pub mapping_layer: isize,
+    /// Mapping had binding for this event.
+    pub matched: bool,
+    /// Event did not propagate to mappings below this one.
+    pub consumed: bool,
+}
+
+#[derive(Clone)]
+struct InputEventLogger(Arc<dyn Fn(&InputEventLog) + Send + Sync>);
+
+impl std::fmt::Debug for InputEventLogger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputEventLogger").finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct InputContext {
     pub mouse_wheel_line_scale: f32,
+    #[cfg(feature = "gamepad")]
+    pub gamepad_remap: GamepadRemap,
+    /// Analog value above which released gamepad action becomes pressed.
+    #[cfg(feature = "gamepad")]
+    pub gamepad_press_threshold: f32,
+    /// Analog value below which pressed gamepad action becomes released.
+    #[cfg(feature = "gamepad")]
+    pub gamepad_release_threshold: f32,
     /// [(id, mapping)]
     mappings_stack: Vec<(ID<InputMapping>, InputMappingRef)>,
     characters: InputCharactersRef,
+    #[cfg(feature = "gamepad")]
+    gamepads: Option<Arc<Mutex<Gilrs>>>,
+    /// [gamepad id?] indexed by slot
+    #[cfg(feature = "gamepad")]
+    gamepad_slots: Vec<Option<GamepadId>>,
+    event_logger: Option<InputEventLogger>,
 }
 
 impl Default for InputContext {
     fn default() -> Self {
         Self {
             mouse_wheel_line_scale: Self::default_mouse_wheel_line_scale(),
+            #[cfg(feature = "gamepad")]
+            gamepad_remap: Default::default(),
+            #[cfg(feature = "gamepad")]
+            gamepad_press_threshold: Self::default_gamepad_press_threshold(),
+            #[cfg(feature = "gamepad")]
+            gamepad_release_threshold: Self::default_gamepad_release_threshold(),
             mappings_stack: Default::default(),
             characters: Default::default(),
+            #[cfg(feature = "gamepad")]
+            gamepads: None,
+            #[cfg(feature = "gamepad")]
+            gamepad_slots: Default::default(),
+            event_logger: None,
         }
     }
 }
@@ -374,9 +1031,124 @@ impl InputContext {
         10.0
     }
 
+    #[cfg(feature = "gamepad")]
+    fn default_gamepad_press_threshold() -> f32 {
+        0.6
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn default_gamepad_release_threshold() -> f32 {
+        0.4
+    }
+
+    #[cfg(feature = "gamepad")]
+    const GAMEPAD_BUTTONS: [GamepadButton; 19] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::North,
+        GamepadButton::West,
+        GamepadButton::C,
+        GamepadButton::Z,
+        GamepadButton::LeftTrigger,
+        GamepadButton::LeftTrigger2,
+        GamepadButton::RightTrigger,
+        GamepadButton::RightTrigger2,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::Mode,
+        GamepadButton::LeftThumb,
+        GamepadButton::RightThumb,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    #[cfg(feature = "gamepad")]
+    const GAMEPAD_AXES: [GamepadAxis; 8] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::LeftZ,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::RightZ,
+        GamepadAxis::DPadX,
+        GamepadAxis::DPadY,
+    ];
+
+    /// Enables polling connected gamepads in [`Self::maintain`].
+    #[cfg(feature = "gamepad")]
+    pub fn with_gamepads(mut self) -> Self {
+        self.gamepads = Gilrs::new().ok().map(|gilrs| Arc::new(Mutex::new(gilrs)));
+        self
+    }
+
+    /// Assigns connected gamepads to free slots in order of connection and
+    /// frees slots of disconnected ones, so remaining gamepads keep their
+    /// slots. Called by [`Self::maintain`] when gamepads are enabled.
+    #[cfg(feature = "gamepad")]
+    pub fn assign_gamepad_slots(&mut self) -> &[Option<GamepadId>] {
+        if let Some(gamepads) = self.gamepads.clone() {
+            if let Ok(gamepads) = gamepads.lock() {
+                assign_slots(
+                    &mut self.gamepad_slots,
+                    gamepads.gamepads().map(|(id, _)| id),
+                );
+            }
+        }
+        &self.gamepad_slots
+    }
+
+    /// [gamepad id?] indexed by slot
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_slots(&self) -> &[Option<GamepadId>] {
+        &self.gamepad_slots
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_slot_of(&self, id: GamepadId) -> Option<usize> {
+        self.gamepad_slots.iter().position(|slot| *slot == Some(id))
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_remap(mut self, remap: GamepadRemap) -> Self {
+        self.gamepad_remap = remap;
+        self
+    }
+
+    /// Sets hysteresis thresholds used to turn analog gamepad values into
+    /// action edges.
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_thresholds(mut self, press: f32, release: f32) -> Self {
+        self.gamepad_press_threshold = press;
+        self.gamepad_release_threshold = release.min(press);
+        self
+    }
+
+    /// Sets callback reporting every mapping that matched or consumed
+    /// incoming event, in order of stack traversal.
+    pub fn set_event_logger(&mut self, logger: impl Fn(&InputEventLog) + Send + Sync + 'static) {
+        self.event_logger = Some(InputEventLogger(Arc::new(logger)));
+    }
+
+    pub fn clear_event_logger(&mut self) {
+        self.event_logger = None;
+    }
+
     pub fn push_mapping(&mut self, mapping: impl Into<InputMappingRef>) -> ID<InputMapping> {
-        let mapping = mapping.into();
         let id = ID::default();
+        self.push_mapping_with_id(id, mapping);
+        id
+    }
+
+    /// Pushes mapping under explicit id, replacing mapping already using it.
+    pub fn push_mapping_with_id(
+        &mut self,
+        id: ID<InputMapping>,
+        mapping: impl Into<InputMappingRef>,
+    ) -> Option<InputMappingRef> {
+        let mapping = mapping.into();
+        let result = self.remove_mapping(id);
         let layer = mapping.read().unwrap().layer;
         let index = self
             .mappings_stack
@@ -390,7 +1162,7 @@ impl InputContext {
             })
             .unwrap_or_else(|index| index);
         self.mappings_stack.insert(index, (id, mapping));
-        id
+        result
     }
 
     pub fn pop_mapping(&mut self) -> Option<InputMappingRef> {
@@ -415,24 +1187,100 @@ impl InputContext {
             .and_then(|(_, mapping)| mapping.read())
     }
 
-    pub fn stack(&self) -> impl Iterator<Item = &InputMappingRef> {
-        self.mappings_stack.iter().map(|(_, mapping)| mapping)
+    /// Returns false if there is no mapping with given id. Disabling mapping
+    /// resets its actions and axes (see [`InputMapping::reset`]), since it
+    /// won't receive releases of inputs held at that moment.
+    pub fn set_mapping_enabled(&mut self, id: ID<InputMapping>, enabled: bool) -> bool {
+        self.mappings_stack
+            .iter()
+            .find(|(mid, _)| mid == &id)
+            .and_then(|(_, mapping)| mapping.write())
+            .map(|mut mapping| {
+                if mapping.enabled && !enabled {
+                    mapping.reset();
+                }
+                mapping.enabled = enabled;
+            })
+            .is_some()
     }
 
-    pub fn characters(&self) -> InputCharactersRef {
+    /// Returns id of topmost mapping with given name.
+    pub fn find_mapping_by_name(&self, name: &str) -> Option<ID<InputMapping>> {
+        self.mappings_stack
+            .iter()
+            .rev()
+            .find(|(_, mapping)| {
+                mapping
+                    .read()
+                    .map(|mapping| mapping.name == name)
+                    .unwrap_or_default()
+            })
+            .map(|(id, _)| *id)
+    }
+
+    /// Lists actions pressed this frame as (mapping name, action), from top
+    /// of the stack. Disabled mappings are skipped. Consumed events never
+    /// reach mappings below consuming one, so their actions don't show up.
+    pub fn pressed_actions(&self) -> impl Iterator<Item = (Cow<'static, str>, VirtualAction)> {
+        self.mappings_stack
+            .iter()
+            .rev()
+            .filter_map(|(_, mapping)| mapping.read().filter(|mapping| mapping.enabled))
+            .flat_map(|mapping| {
+                mapping
+                    .actions
+                    .iter()
+                    .filter(|(_, action)| action.get().is_pressed())
+                    .map(|(virtual_action, _)| (mapping.name.clone(), *virtual_action))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn any_action_pressed(&self) -> bool {
+        self.pressed_actions().next().is_some()
+    }
+
+    pub fn stack(&self) -> impl Iterator<Item = &InputMappingRef> {
+        self.mappings_stack.iter().map(|(_, mapping)| mapping)
+    }
+
+    pub fn characters(&self) -> InputCharactersRef {
         self.characters.clone()
     }
 
+    /// Same as [`Self::maintain`], but also advances hold time of timed
+    /// actions by `dt` seconds.
+    pub fn maintain_dt(&mut self, dt: f32) {
+        self.maintain();
+        for (_, mapping) in &self.mappings_stack {
+            if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                for action in &mapping.timed_actions {
+                    action.advance(dt);
+                }
+            }
+        }
+    }
+
     pub fn maintain(&mut self) {
+        let now = Instant::now();
         for (_, mapping) in &mut self.mappings_stack {
-            if let Some(mut mapping) = mapping.write() {
+            if let Some(mut mapping) = mapping.write().filter(|mapping| mapping.enabled) {
                 for action in mapping.actions.values_mut() {
                     if let Some(mut action) = action.write() {
                         *action = action.update();
                     }
                 }
+                for (_, multiclick) in &mapping.multiclicks {
+                    multiclick.maintain(now);
+                }
                 for (id, axis) in &mut mapping.axes {
-                    if let VirtualAxis::MouseWheelX | VirtualAxis::MouseWheelY = id {
+                    if let VirtualAxis::MouseWheelX
+                    | VirtualAxis::MouseWheelY
+                    | VirtualAxis::MouseDeltaX
+                    | VirtualAxis::MouseDeltaY = id
+                    {
                         if let Some(mut axis) = axis.write() {
                             axis.0 = 0.0;
                         }
@@ -440,6 +1288,300 @@ impl InputContext {
                 }
             }
         }
+        #[cfg(feature = "gamepad")]
+        self.maintain_gamepads();
+    }
+
+    /// Polls gamepad events and reconciles mappings with current gamepad
+    /// state.
+    #[cfg(feature = "gamepad")]
+    fn maintain_gamepads(&mut self) {
+        let Some(gamepads) = self.gamepads.clone() else {
+            return;
+        };
+        let Ok(mut gamepads) = gamepads.lock() else {
+            return;
+        };
+        let events = std::iter::from_fn(|| gamepads.next_event())
+            .map(|GamepadEvent { id, event, .. }| (id, event))
+            .collect::<Vec<_>>();
+        assign_slots(
+            &mut self.gamepad_slots,
+            gamepads.gamepads().map(|(id, _)| id),
+        );
+        for (id, event) in events {
+            let slot = self.gamepad_slot_of(id);
+            let (button, value) = match event {
+                GamepadEventType::ButtonPressed(button, _) => (button, 1.0),
+                GamepadEventType::ButtonReleased(button, _) => (button, 0.0),
+                GamepadEventType::ButtonChanged(button, value, _) => (button, value),
+                GamepadEventType::AxisChanged(axis, value, _) => {
+                    self.on_gamepad_axis(axis, value);
+                    if let Some(slot) = slot {
+                        self.on_gamepad_axis_slot(slot, axis, value);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            self.on_gamepad_button(button, value);
+            if let Some(slot) = slot {
+                self.on_gamepad_button_slot(slot, button, value);
+            }
+        }
+        // Reconciliation runs every frame, so keep it out of event log.
+        let event_logger = self.event_logger.take();
+        // gilrs does not guarantee event for every change (e.g. axis settling
+        // back to rest), so reconcile with current state once per frame.
+        let buttons = gamepads.gamepads().flat_map(|(_, gamepad)| {
+            Self::GAMEPAD_BUTTONS.map(|button| {
+                let value = gamepad
+                    .button_data(button)
+                    .map(|data| data.value())
+                    .unwrap_or_default();
+                (button, value)
+            })
+        });
+        let axes = gamepads
+            .gamepads()
+            .flat_map(|(_, gamepad)| Self::GAMEPAD_AXES.map(|axis| (axis, gamepad.value(axis))));
+        self.reconcile_gamepad(None, buttons, axes);
+        for (slot, id) in self.gamepad_slots.clone().into_iter().enumerate() {
+            let Some(gamepad) = id.and_then(|id| gamepads.connected_gamepad(id)) else {
+                continue;
+            };
+            let buttons = Self::GAMEPAD_BUTTONS.map(|button| {
+                let value = gamepad
+                    .button_data(button)
+                    .map(|data| data.value())
+                    .unwrap_or_default();
+                (button, value)
+            });
+            let axes = Self::GAMEPAD_AXES.map(|axis| (axis, gamepad.value(axis)));
+            self.reconcile_gamepad(Some(slot), buttons, axes);
+        }
+        self.event_logger = event_logger;
+    }
+
+    /// Dispatches full raw gamepad state once per logical button and axis,
+    /// so raw inputs remapped to same target do not override each other.
+    #[cfg(feature = "gamepad")]
+    fn reconcile_gamepad(
+        &mut self,
+        slot: Option<usize>,
+        buttons: impl IntoIterator<Item = (GamepadButton, f32)>,
+        axes: impl IntoIterator<Item = (GamepadAxis, f32)>,
+    ) {
+        for (button, value) in self.gamepad_remap.combine_buttons(buttons) {
+            self.dispatch_logical_gamepad_button(slot, button, value);
+        }
+        for (axis, value) in self.gamepad_remap.combine_axes(axes) {
+            self.dispatch_logical_gamepad_axis(slot, axis, value);
+        }
+    }
+
+    fn log_event(
+        &self,
+        kind: InputEventKind,
+        mapping: &InputMapping,
+        matched: bool,
+        consumed: bool,
+    ) {
+        if !matched && !consumed {
+            return;
+        }
+        if let Some(logger) = self.event_logger.as_ref() {
+            (logger.0)(&InputEventLog {
+                kind,
+                mapping_name: &mapping.name,
+                mapping_layer: mapping.layer,
+                matched,
+                consumed,
+            });
+        }
+    }
+
+    /// Applies analog value to gamepad action using hysteresis, so it only
+    /// changes state when crossing press or release threshold.
+    #[cfg(feature = "gamepad")]
+    fn change_gamepad_action(&self, data: &InputActionRef, value: f32) -> bool {
+        let Some(mut data) = data.write() else {
+            return false;
+        };
+        let hold = if data.is_down() {
+            value.abs() > self.gamepad_release_threshold
+        } else {
+            value.abs() >= self.gamepad_press_threshold
+        };
+        if hold != data.is_down() {
+            *data = data.change(hold);
+        }
+        true
+    }
+
+    /// Dispatches button of any gamepad to mappings without gamepad slot.
+    #[cfg(feature = "gamepad")]
+    pub fn on_gamepad_button(&mut self, button: GamepadButton, value: f32) {
+        self.dispatch_gamepad_button(None, button, value);
+    }
+
+    /// Dispatches button of gamepad in given slot to mappings bound to it.
+    #[cfg(feature = "gamepad")]
+    pub fn on_gamepad_button_slot(&mut self, slot: usize, button: GamepadButton, value: f32) {
+        self.dispatch_gamepad_button(Some(slot), button, value);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn dispatch_gamepad_button(&mut self, slot: Option<usize>, button: GamepadButton, value: f32) {
+        let button = self.gamepad_remap.map_button(button);
+        self.dispatch_logical_gamepad_button(slot, button, value);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn dispatch_logical_gamepad_button(
+        &mut self,
+        slot: Option<usize>,
+        button: GamepadButton,
+        value: f32,
+    ) {
+        for (_, mapping) in self.mappings_stack.iter().rev() {
+            if let Some(mapping) = mapping
+                .read()
+                .filter(|mapping| mapping.enabled && mapping.gamepad_slot == slot)
+            {
+                let mut hit = false;
+                let mut consume = mapping.consume == InputConsume::All;
+                for (id, data) in &mapping.actions {
+                    if let VirtualAction::GamepadButton(btn) = id {
+                        if button == *btn && self.change_gamepad_action(data, value) {
+                            hit = true;
+                            if mapping.consume == InputConsume::Hit {
+                                consume = true;
+                            }
+                        }
+                    }
+                }
+                for (id, data) in &mapping.axes {
+                    if let VirtualAxis::GamepadButton(btn) = id {
+                        if button == *btn {
+                            if let Some(mut data) = data.write() {
+                                data.0 = mapping.transform_axis(id, value);
+                                hit = true;
+                                if mapping.consume == InputConsume::Hit {
+                                    consume = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                self.log_event(
+                    InputEventKind::GamepadButton(button),
+                    &mapping,
+                    hit,
+                    consume,
+                );
+                if consume {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Dispatches axis of any gamepad to mappings without gamepad slot.
+    #[cfg(feature = "gamepad")]
+    pub fn on_gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.dispatch_gamepad_axis(None, axis, value);
+    }
+
+    /// Dispatches axis of gamepad in given slot to mappings bound to it.
+    #[cfg(feature = "gamepad")]
+    pub fn on_gamepad_axis_slot(&mut self, slot: usize, axis: GamepadAxis, value: f32) {
+        self.dispatch_gamepad_axis(Some(slot), axis, value);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn dispatch_gamepad_axis(&mut self, slot: Option<usize>, axis: GamepadAxis, value: f32) {
+        let axis = self.gamepad_remap.map_axis(axis);
+        self.dispatch_logical_gamepad_axis(slot, axis, value);
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn dispatch_logical_gamepad_axis(
+        &mut self,
+        slot: Option<usize>,
+        axis: GamepadAxis,
+        value: f32,
+    ) {
+        for (_, mapping) in self.mappings_stack.iter().rev() {
+            if let Some(mapping) = mapping
+                .read()
+                .filter(|mapping| mapping.enabled && mapping.gamepad_slot == slot)
+            {
+                let value = if value.abs() < mapping.gamepad_deadzone {
+                    0.0
+                } else {
+                    value
+                };
+                let mut hit = false;
+                let mut consume = mapping.consume == InputConsume::All;
+                for (id, data) in &mapping.actions {
+                    if let VirtualAction::GamepadAxis(index) = id {
+                        if axis == *index && self.change_gamepad_action(data, value) {
+                            hit = true;
+                            if mapping.consume == InputConsume::Hit {
+                                consume = true;
+                            }
+                        }
+                    }
+                }
+                for (id, data) in &mapping.axes {
+                    if let VirtualAxis::GamepadAxis(index) = id {
+                        if axis == *index {
+                            if let Some(mut data) = data.write() {
+                                data.0 = mapping.transform_axis(id, value);
+                                hit = true;
+                                if mapping.consume == InputConsume::Hit {
+                                    consume = true;
+                                }
+                            }
+                        }
+                    }
+                }
+                self.log_event(InputEventKind::GamepadAxis(axis), &mapping, hit, consume);
+                if consume {
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn on_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            for (_, mapping) in self.mappings_stack.iter().rev() {
+                if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                    let mut hit = false;
+                    let mut consume = mapping.consume == InputConsume::All;
+                    for (id, data) in &mapping.axes {
+                        let value = match id {
+                            VirtualAxis::MouseDeltaX => delta.0,
+                            VirtualAxis::MouseDeltaY => delta.1,
+                            _ => continue,
+                        };
+                        if let Some(mut data) = data.write() {
+                            data.0 += value as f32 * mapping.axis_transform(id).scale;
+                            hit = true;
+                            if mapping.consume == InputConsume::Hit {
+                                consume = true;
+                            }
+                        }
+                    }
+                    self.log_event(InputEventKind::MouseMotion, &mapping, hit, consume);
+                    if consume {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
     pub fn on_event(&mut self, event: &WindowEvent) {
@@ -452,7 +1594,8 @@ impl InputContext {
             WindowEvent::KeyboardInput { input, .. } => {
                 if let Some(key) = input.virtual_keycode {
                     for (_, mapping) in self.mappings_stack.iter().rev() {
-                        if let Some(mapping) = mapping.read() {
+                        if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                            let mut hit = false;
                             let mut consume = mapping.consume == InputConsume::All;
                             for (id, data) in &mapping.actions {
                                 if let VirtualAction::KeyButton(button) = id {
@@ -460,6 +1603,7 @@ impl InputContext {
                                         if let Some(mut data) = data.write() {
                                             *data =
                                                 data.change(input.state == ElementState::Pressed);
+                                            hit = true;
                                             if mapping.consume == InputConsume::Hit {
                                                 consume = true;
                                             }
@@ -467,15 +1611,28 @@ impl InputContext {
                                     }
                                 }
                             }
+                            if mapping.change_multiclicks(
+                                VirtualAction::KeyButton(key),
+                                input.state == ElementState::Pressed,
+                            ) {
+                                hit = true;
+                                if mapping.consume == InputConsume::Hit {
+                                    consume = true;
+                                }
+                            }
                             for (id, data) in &mapping.axes {
                                 if let VirtualAxis::KeyButton(button) = id {
                                     if *button == key {
                                         if let Some(mut data) = data.write() {
-                                            data.0 = if input.state == ElementState::Pressed {
-                                                1.0
-                                            } else {
-                                                0.0
-                                            };
+                                            data.0 = mapping.transform_axis(
+                                                id,
+                                                if input.state == ElementState::Pressed {
+                                                    1.0
+                                                } else {
+                                                    0.0
+                                                },
+                                            );
+                                            hit = true;
                                             if mapping.consume == InputConsume::Hit {
                                                 consume = true;
                                             }
@@ -483,6 +1640,7 @@ impl InputContext {
                                     }
                                 }
                             }
+                            self.log_event(InputEventKind::KeyButton(key), &mapping, hit, consume);
                             if consume {
                                 break;
                             }
@@ -492,13 +1650,15 @@ impl InputContext {
             }
             WindowEvent::CursorMoved { position, .. } => {
                 for (_, mapping) in self.mappings_stack.iter().rev() {
-                    if let Some(mapping) = mapping.read() {
+                    if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                        let mut hit = false;
                         let mut consume = mapping.consume == InputConsume::All;
                         for (id, data) in &mapping.axes {
                             match id {
                                 VirtualAxis::MousePositionX => {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = position.x as _;
+                                        data.0 = mapping.transform_axis(id, position.x as _);
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -506,7 +1666,8 @@ impl InputContext {
                                 }
                                 VirtualAxis::MousePositionY => {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = position.y as _;
+                                        data.0 = mapping.transform_axis(id, position.y as _);
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -515,6 +1676,7 @@ impl InputContext {
                                 _ => {}
                             }
                         }
+                        self.log_event(InputEventKind::MousePosition, &mapping, hit, consume);
                         if consume {
                             break;
                         }
@@ -523,16 +1685,21 @@ impl InputContext {
             }
             WindowEvent::MouseWheel { delta, .. } => {
                 for (_, mapping) in self.mappings_stack.iter().rev() {
-                    if let Some(mapping) = mapping.read() {
+                    if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                        let mut hit = false;
                         let mut consume = mapping.consume == InputConsume::All;
                         for (id, data) in &mapping.axes {
                             match id {
                                 VirtualAxis::MouseWheelX => {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = match delta {
-                                            MouseScrollDelta::LineDelta(x, _) => *x,
-                                            MouseScrollDelta::PixelDelta(pos) => pos.x as _,
-                                        };
+                                        data.0 = mapping.transform_axis(
+                                            id,
+                                            match delta {
+                                                MouseScrollDelta::LineDelta(x, _) => *x,
+                                                MouseScrollDelta::PixelDelta(pos) => pos.x as _,
+                                            },
+                                        );
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -540,10 +1707,14 @@ impl InputContext {
                                 }
                                 VirtualAxis::MouseWheelY => {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = match delta {
-                                            MouseScrollDelta::LineDelta(_, y) => *y,
-                                            MouseScrollDelta::PixelDelta(pos) => pos.y as _,
-                                        };
+                                        data.0 = mapping.transform_axis(
+                                            id,
+                                            match delta {
+                                                MouseScrollDelta::LineDelta(_, y) => *y,
+                                                MouseScrollDelta::PixelDelta(pos) => pos.y as _,
+                                            },
+                                        );
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -552,6 +1723,7 @@ impl InputContext {
                                 _ => {}
                             }
                         }
+                        self.log_event(InputEventKind::MouseWheel, &mapping, hit, consume);
                         if consume {
                             break;
                         }
@@ -560,13 +1732,15 @@ impl InputContext {
             }
             WindowEvent::MouseInput { state, button, .. } => {
                 for (_, mapping) in self.mappings_stack.iter().rev() {
-                    if let Some(mapping) = mapping.read() {
+                    if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                        let mut hit = false;
                         let mut consume = mapping.consume == InputConsume::All;
                         for (id, data) in &mapping.actions {
                             if let VirtualAction::MouseButton(btn) = id {
                                 if button == btn {
                                     if let Some(mut data) = data.write() {
                                         *data = data.change(*state == ElementState::Pressed);
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -574,15 +1748,28 @@ impl InputContext {
                                 }
                             }
                         }
+                        if mapping.change_multiclicks(
+                            VirtualAction::MouseButton(*button),
+                            *state == ElementState::Pressed,
+                        ) {
+                            hit = true;
+                            if mapping.consume == InputConsume::Hit {
+                                consume = true;
+                            }
+                        }
                         for (id, data) in &mapping.axes {
                             if let VirtualAxis::MouseButton(btn) = id {
                                 if button == btn {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = if *state == ElementState::Pressed {
-                                            1.0
-                                        } else {
-                                            0.0
-                                        };
+                                        data.0 = mapping.transform_axis(
+                                            id,
+                                            if *state == ElementState::Pressed {
+                                                1.0
+                                            } else {
+                                                0.0
+                                            },
+                                        );
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -590,6 +1777,12 @@ impl InputContext {
                                 }
                             }
                         }
+                        self.log_event(
+                            InputEventKind::MouseButton(*button),
+                            &mapping,
+                            hit,
+                            consume,
+                        );
                         if consume {
                             break;
                         }
@@ -598,13 +1791,15 @@ impl InputContext {
             }
             WindowEvent::AxisMotion { axis, value, .. } => {
                 for (_, mapping) in self.mappings_stack.iter().rev() {
-                    if let Some(mapping) = mapping.read() {
+                    if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                        let mut hit = false;
                         let mut consume = mapping.consume == InputConsume::All;
                         for (id, data) in &mapping.actions {
                             if let VirtualAction::Axis(index) = id {
                                 if axis == index {
                                     if let Some(mut data) = data.write() {
                                         *data = data.change(value.abs() > 0.5);
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
@@ -616,14 +1811,85 @@ impl InputContext {
                             if let VirtualAxis::Axis(index) = id {
                                 if axis == index {
                                     if let Some(mut data) = data.write() {
-                                        data.0 = *value as _;
+                                        data.0 = mapping.transform_axis(id, *value as _);
+                                        hit = true;
+                                        if mapping.consume == InputConsume::Hit {
+                                            consume = true;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        self.log_event(InputEventKind::Axis(*axis), &mapping, hit, consume);
+                        if consume {
+                            break;
+                        }
+                    }
+                }
+            }
+            WindowEvent::Touch(Touch {
+                id: touch_id,
+                phase,
+                location,
+                ..
+            }) => {
+                // Cancelled touch releases action same way lifted finger does.
+                let hold = match phase {
+                    TouchPhase::Started => Some(true),
+                    TouchPhase::Moved => None,
+                    TouchPhase::Ended | TouchPhase::Cancelled => Some(false),
+                };
+                for (_, mapping) in self.mappings_stack.iter().rev() {
+                    if let Some(mapping) = mapping.read().filter(|mapping| mapping.enabled) {
+                        let mut hit = false;
+                        let mut consume = mapping.consume == InputConsume::All;
+                        for (id, data) in &mapping.actions {
+                            if let VirtualAction::Touch(touch) = id {
+                                if touch == touch_id {
+                                    if let Some(mut data) = data.write() {
+                                        if let Some(hold) = hold {
+                                            *data = data.change(hold);
+                                        }
+                                        hit = true;
+                                        if mapping.consume == InputConsume::Hit {
+                                            consume = true;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if let Some(hold) = hold {
+                            if mapping.change_multiclicks(VirtualAction::Touch(*touch_id), hold) {
+                                hit = true;
+                                if mapping.consume == InputConsume::Hit {
+                                    consume = true;
+                                }
+                            }
+                        }
+                        for (id, data) in &mapping.axes {
+                            match id {
+                                VirtualAxis::TouchX(touch) if touch == touch_id => {
+                                    if let Some(mut data) = data.write() {
+                                        data.0 = mapping.transform_axis(id, location.x as _);
+                                        hit = true;
+                                        if mapping.consume == InputConsume::Hit {
+                                            consume = true;
+                                        }
+                                    }
+                                }
+                                VirtualAxis::TouchY(touch) if touch == touch_id => {
+                                    if let Some(mut data) = data.write() {
+                                        data.0 = mapping.transform_axis(id, location.y as _);
+                                        hit = true;
                                         if mapping.consume == InputConsume::Hit {
                                             consume = true;
                                         }
                                     }
                                 }
+                                _ => {}
                             }
                         }
+                        self.log_event(InputEventKind::Touch(*touch_id), &mapping, hit, consume);
                         if consume {
                             break;
                         }
@@ -635,9 +1901,85 @@ impl InputContext {
     }
 }
 
+/// Frees slots of items no longer connected, then puts newly connected
+/// items into first free slots, so connected items never change slots.
+#[cfg(feature = "gamepad")]
+fn assign_slots<T: Copy + PartialEq>(
+    slots: &mut Vec<Option<T>>,
+    connected: impl IntoIterator<Item = T>,
+) {
+    let connected = connected.into_iter().collect::<Vec<_>>();
+    for slot in slots.iter_mut() {
+        if slot.map(|id| !connected.contains(&id)).unwrap_or_default() {
+            *slot = None;
+        }
+    }
+    for id in connected {
+        if slots.contains(&Some(id)) {
+            continue;
+        }
+        if let Some(slot) = slots.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(id);
+        } else {
+            slots.push(Some(id));
+        }
+    }
+    while slots.last().map(|slot| slot.is_none()).unwrap_or_default() {
+        slots.pop();
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{InputContext, InputMapping};
+    #[cfg(feature = "gamepad")]
+    use crate::{assign_slots, GamepadAxis, GamepadButton, GamepadRemap};
+    use crate::{
+        CardinalInputCombinator, ChordInputCombinator, CurvedInputCombinator, InputAction,
+        InputActionRef, InputActionTimedRef, InputAxis, InputAxisRef, InputConsume, InputContext,
+        InputEventKind, InputMapping, SmoothedInputCombinator, VirtualAction, VirtualAxis,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    use glutin::{
+        dpi::PhysicalPosition,
+        event::{
+            DeviceEvent, DeviceId, ElementState, KeyboardInput, MouseButton, MouseScrollDelta,
+            Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+        },
+    };
+    #[cfg(feature = "gamepad")]
+    use std::collections::HashMap;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use typid::ID;
+    #[cfg(target_arch = "wasm32")]
+    use winit::{
+        dpi::PhysicalPosition,
+        event::{
+            DeviceEvent, DeviceId, ElementState, KeyboardInput, MouseButton, MouseScrollDelta,
+            Touch, TouchPhase, VirtualKeyCode, WindowEvent,
+        },
+    };
+
+    fn cursor_moved(context: &mut InputContext, x: f64, y: f64) {
+        #[allow(deprecated)]
+        context.on_event(&WindowEvent::CursorMoved {
+            device_id: unsafe { DeviceId::dummy() },
+            position: PhysicalPosition::new(x, y),
+            modifiers: Default::default(),
+        });
+    }
+
+    fn mouse_input(context: &mut InputContext, button: MouseButton, state: ElementState) {
+        #[allow(deprecated)]
+        context.on_event(&WindowEvent::MouseInput {
+            device_id: unsafe { DeviceId::dummy() },
+            state,
+            button,
+            modifiers: Default::default(),
+        });
+    }
 
     #[test]
     fn test_stack() {
@@ -678,4 +2020,709 @@ mod tests {
             ]
         );
     }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn test_gamepad_remap() {
+        let south = InputActionRef::default();
+        let east = InputActionRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .action(
+                    VirtualAction::GamepadButton(GamepadButton::South),
+                    south.clone(),
+                )
+                .action(
+                    VirtualAction::GamepadButton(GamepadButton::East),
+                    east.clone(),
+                ),
+        );
+
+        context.on_gamepad_button(GamepadButton::South, 1.0);
+        assert_eq!(south.get(), InputAction::Pressed);
+        assert_eq!(east.get(), InputAction::Idle);
+        context.on_gamepad_button(GamepadButton::South, 0.0);
+        context.maintain();
+
+        context.gamepad_remap = GamepadRemap::default()
+            .button(GamepadButton::South, GamepadButton::East)
+            .button(GamepadButton::East, GamepadButton::South);
+        context.on_gamepad_button(GamepadButton::South, 1.0);
+        assert_eq!(south.get(), InputAction::Idle);
+        assert_eq!(east.get(), InputAction::Pressed);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn test_gamepad_remap_many_to_one() {
+        let south = InputActionRef::default();
+        let stick = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .action(
+                    VirtualAction::GamepadButton(GamepadButton::South),
+                    south.clone(),
+                )
+                .axis(
+                    VirtualAxis::GamepadAxis(GamepadAxis::LeftStickX),
+                    stick.clone(),
+                ),
+        );
+        context.gamepad_remap = GamepadRemap::default()
+            .button(GamepadButton::Start, GamepadButton::South)
+            .axis(GamepadAxis::RightStickX, GamepadAxis::LeftStickX);
+
+        let remap = context.gamepad_remap.clone();
+        assert_eq!(
+            remap.combine_buttons([(GamepadButton::South, 1.0), (GamepadButton::Start, 0.0)]),
+            HashMap::from([(GamepadButton::South, 1.0)])
+        );
+        assert_eq!(
+            remap.combine_axes([
+                (GamepadAxis::LeftStickX, 0.25),
+                (GamepadAxis::RightStickX, -0.75)
+            ]),
+            HashMap::from([(GamepadAxis::LeftStickX, -0.75)])
+        );
+
+        // Released source must not release target held by other source,
+        // regardless of order in which raw state is reported.
+        for order in [
+            [(GamepadButton::South, 1.0), (GamepadButton::Start, 0.0)],
+            [(GamepadButton::Start, 0.0), (GamepadButton::South, 1.0)],
+        ] {
+            context.reconcile_gamepad(
+                None,
+                order,
+                [
+                    (GamepadAxis::RightStickX, 0.5),
+                    (GamepadAxis::LeftStickX, 0.0),
+                ],
+            );
+            assert!(south.get().is_down());
+            assert_eq!(stick.get().0, 0.5);
+            context.maintain();
+        }
+        assert_eq!(south.get(), InputAction::Hold);
+
+        context.reconcile_gamepad(
+            None,
+            [(GamepadButton::South, 0.0), (GamepadButton::Start, 1.0)],
+            [],
+        );
+        assert_eq!(south.get(), InputAction::Hold);
+        context.reconcile_gamepad(
+            None,
+            [(GamepadButton::South, 0.0), (GamepadButton::Start, 0.0)],
+            [],
+        );
+        assert_eq!(south.get(), InputAction::Released);
+    }
+
+    #[test]
+    fn test_push_mapping_with_id() {
+        let id = ID::<InputMapping>::new();
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().name("a"));
+        assert!(context
+            .push_mapping_with_id(id, InputMapping::default().name("b"))
+            .is_none());
+        context.push_mapping(InputMapping::default().name("c"));
+        assert_eq!(context.mapping(id).unwrap().name, "b");
+
+        let replaced = context
+            .push_mapping_with_id(id, InputMapping::default().name("d"))
+            .unwrap();
+        assert_eq!(replaced.read().unwrap().name, "b");
+        assert_eq!(context.mapping(id).unwrap().name, "d");
+        assert_eq!(context.stack().count(), 3);
+    }
+
+    #[test]
+    fn test_mapping_enabled() {
+        let top = InputAxisRef::default();
+        let bottom = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .name("bottom")
+                .axis(VirtualAxis::MousePositionX, bottom.clone()),
+        );
+        let id = context.push_mapping(
+            InputMapping::default()
+                .name("top")
+                .layer(1)
+                .consume(InputConsume::All)
+                .axis(VirtualAxis::MousePositionX, top.clone()),
+        );
+
+        cursor_moved(&mut context, 0.5, 0.0);
+        assert_eq!(top.get().0, 0.5);
+        assert_eq!(bottom.get().0, 0.0);
+
+        assert!(context.set_mapping_enabled(id, false));
+        assert_eq!(top.get().0, 0.0);
+        cursor_moved(&mut context, 1.0, 0.0);
+        assert_eq!(top.get().0, 0.0);
+        assert_eq!(bottom.get().0, 1.0);
+
+        assert!(context.set_mapping_enabled(id, true));
+        assert_eq!(context.top_mapping().unwrap().read().unwrap().name, "top");
+        cursor_moved(&mut context, -1.0, 0.0);
+        assert_eq!(top.get().0, -1.0);
+        assert_eq!(bottom.get().0, 1.0);
+        assert!(!context.set_mapping_enabled(ID::new(), false));
+    }
+
+    #[test]
+    fn test_mapping_disabled_while_held() {
+        let jump = InputActionRef::default();
+        let mut context = InputContext::default();
+        let id = context.push_mapping(
+            InputMapping::default()
+                .action(VirtualAction::MouseButton(MouseButton::Left), jump.clone()),
+        );
+
+        mouse_input(&mut context, MouseButton::Left, ElementState::Pressed);
+        context.maintain();
+        assert_eq!(jump.get(), InputAction::Hold);
+
+        assert!(context.set_mapping_enabled(id, false));
+        assert_eq!(jump.get(), InputAction::Idle);
+        mouse_input(&mut context, MouseButton::Left, ElementState::Released);
+        context.maintain();
+        assert!(context.set_mapping_enabled(id, true));
+        context.maintain();
+        assert_eq!(jump.get(), InputAction::Idle);
+
+        mouse_input(&mut context, MouseButton::Left, ElementState::Pressed);
+        assert_eq!(jump.get(), InputAction::Pressed);
+    }
+
+    #[test]
+    fn test_axis_scaled() {
+        let cursor = InputAxisRef::default();
+        let wheel = InputAxisRef::default();
+        let delta = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .axis_scaled(VirtualAxis::MousePositionY, cursor.clone(), -2.0, 0.5)
+                .axis_scaled(VirtualAxis::MouseWheelY, wheel.clone(), 3.0, 0.0)
+                .axis_scaled(VirtualAxis::MouseDeltaX, delta.clone(), -0.5, 10.0),
+        );
+
+        cursor_moved(&mut context, 0.0, 0.5);
+        assert_eq!(cursor.get().0, -0.5);
+        #[allow(deprecated)]
+        context.on_event(&WindowEvent::MouseWheel {
+            device_id: unsafe { DeviceId::dummy() },
+            delta: MouseScrollDelta::LineDelta(0.0, 2.0),
+            phase: TouchPhase::Moved,
+            modifiers: Default::default(),
+        });
+        assert_eq!(wheel.get().0, 6.0);
+        context.on_device_event(&DeviceEvent::MouseMotion { delta: (4.0, 0.0) });
+        context.on_device_event(&DeviceEvent::MouseMotion { delta: (2.0, 0.0) });
+        assert_eq!(delta.get().0, -3.0);
+    }
+
+    #[test]
+    fn test_hold_time() {
+        let action = InputActionTimedRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().action_timed(
+            VirtualAction::MouseButton(MouseButton::Left),
+            action.clone(),
+        ));
+
+        context.maintain_dt(0.5);
+        assert_eq!(action.hold_time(), 0.0);
+        mouse_input(&mut context, MouseButton::Left, ElementState::Pressed);
+        assert_eq!(action.get(), InputAction::Pressed);
+        assert_eq!(action.hold_time(), 0.0);
+        context.maintain_dt(0.25);
+        assert_eq!(action.get(), InputAction::Hold);
+        assert_eq!(action.hold_time(), 0.25);
+        context.maintain_dt(0.5);
+        assert_eq!(action.hold_time(), 0.75);
+        mouse_input(&mut context, MouseButton::Left, ElementState::Released);
+        assert_eq!(action.get(), InputAction::Released);
+        assert_eq!(action.hold_time(), 0.0);
+        context.maintain_dt(0.5);
+        mouse_input(&mut context, MouseButton::Left, ElementState::Pressed);
+        context.maintain_dt(0.125);
+        assert_eq!(action.hold_time(), 0.125);
+    }
+
+    #[test]
+    fn test_paste() {
+        let mut context = InputContext::default();
+        context.on_event(&WindowEvent::ReceivedCharacter('>'));
+        let characters = context.characters();
+        let mut characters = characters.write().unwrap();
+        assert!(characters.paste(&mut || Some("line 1\nzażółć 🔥\n".to_owned())));
+        assert!(!characters.paste(&mut || None));
+        assert_eq!(characters.read(), ">line 1\nzażółć 🔥\n");
+    }
+
+    #[test]
+    fn test_find_mapping_by_name() {
+        let mut context = InputContext::default();
+        let a = context.push_mapping(InputMapping::default().name("a").layer(1));
+        let b = context.push_mapping(InputMapping::default().name("b"));
+        let c = context.push_mapping(InputMapping::default().name("a"));
+        assert_eq!(context.find_mapping_by_name("a"), Some(a));
+        assert_eq!(context.find_mapping_by_name("b"), Some(b));
+        assert_eq!(context.find_mapping_by_name("c"), None);
+
+        context.remove_mapping(a);
+        assert_eq!(context.find_mapping_by_name("a"), Some(c));
+    }
+
+    #[test]
+    fn test_from_bindings() {
+        let actions = vec![
+            (
+                VirtualAction::KeyButton(VirtualKeyCode::A),
+                InputActionRef::default(),
+            ),
+            (
+                VirtualAction::KeyButton(VirtualKeyCode::D),
+                InputActionRef::default(),
+            ),
+            (
+                VirtualAction::MouseButton(MouseButton::Left),
+                InputActionRef::default(),
+            ),
+        ];
+        let axes = vec![
+            (VirtualAxis::MousePositionX, InputAxisRef::default()),
+            (VirtualAxis::MousePositionY, InputAxisRef::default()),
+        ];
+        let mapping = InputMapping::from_bindings(actions.clone(), axes.clone());
+        assert_eq!(mapping.actions.len(), 3);
+        assert_eq!(mapping.axes.len(), 2);
+        for (id, _) in &actions {
+            assert!(mapping.actions.contains_key(id));
+        }
+        for (id, _) in &axes {
+            assert!(mapping.axes.contains_key(id));
+        }
+
+        let mapping = mapping.bind_many(
+            [(
+                VirtualAction::KeyButton(VirtualKeyCode::W),
+                InputActionRef::default(),
+            )],
+            [(VirtualAxis::MouseWheelY, InputAxisRef::default())],
+        );
+        assert_eq!(mapping.actions.len(), 4);
+        assert_eq!(mapping.axes.len(), 3);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn test_gamepad_axis_hysteresis() {
+        let action = InputActionRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().action(
+            VirtualAction::GamepadAxis(GamepadAxis::LeftZ),
+            action.clone(),
+        ));
+
+        let mut pressed = 0;
+        let mut released = 0;
+        for value in [
+            0.0, 0.3, 0.49, 0.51, 0.49, 0.55, 0.7, 1.0, 0.7, 0.51, 0.49, 0.51, 0.3, 0.0,
+        ] {
+            context.on_gamepad_axis(GamepadAxis::LeftZ, value);
+            match action.get() {
+                InputAction::Pressed => pressed += 1,
+                InputAction::Released => released += 1,
+                _ => {}
+            }
+            context.maintain();
+        }
+        assert_eq!(pressed, 1);
+        assert_eq!(released, 1);
+        assert_eq!(action.get(), InputAction::Idle);
+    }
+
+    #[test]
+    fn test_event_logger() {
+        let logs = Arc::new(Mutex::new(Vec::new()));
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().name("gameplay").action(
+            VirtualAction::KeyButton(VirtualKeyCode::Space),
+            InputActionRef::default(),
+        ));
+        context.push_mapping(
+            InputMapping::default()
+                .name("menu")
+                .layer(1)
+                .consume(InputConsume::Hit)
+                .action(
+                    VirtualAction::KeyButton(VirtualKeyCode::Return),
+                    InputActionRef::default(),
+                ),
+        );
+        let logs2 = logs.clone();
+        context.set_event_logger(move |log| {
+            logs2.lock().unwrap().push((
+                log.kind,
+                log.mapping_name.to_owned(),
+                log.mapping_layer,
+                log.consumed,
+            ));
+        });
+
+        #[allow(deprecated)]
+        context.on_event(&WindowEvent::KeyboardInput {
+            device_id: unsafe { DeviceId::dummy() },
+            input: KeyboardInput {
+                scancode: 0,
+                state: ElementState::Pressed,
+                virtual_keycode: Some(VirtualKeyCode::Space),
+                modifiers: Default::default(),
+            },
+            is_synthetic: false,
+        });
+        assert_eq!(
+            logs.lock().unwrap().as_slice(),
+            &[(
+                InputEventKind::KeyButton(VirtualKeyCode::Space),
+                "gameplay".to_owned(),
+                0,
+                false
+            )]
+        );
+    }
+
+    #[test]
+    fn test_touch() {
+        let touch = |id, phase, x, y| {
+            WindowEvent::Touch(Touch {
+                device_id: unsafe { DeviceId::dummy() },
+                phase,
+                location: PhysicalPosition::new(x, y),
+                force: None,
+                id,
+            })
+        };
+        let first = InputActionRef::default();
+        let second = InputActionRef::default();
+        let first_x = InputAxisRef::default();
+        let second_x = InputAxisRef::default();
+        let second_y = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .action(VirtualAction::Touch(0), first.clone())
+                .action(VirtualAction::Touch(1), second.clone())
+                .axis(VirtualAxis::TouchX(0), first_x.clone())
+                .axis(VirtualAxis::TouchX(1), second_x.clone())
+                .axis(VirtualAxis::TouchY(1), second_y.clone()),
+        );
+
+        context.on_event(&touch(0, TouchPhase::Started, 10.0, 20.0));
+        context.on_event(&touch(1, TouchPhase::Started, 30.0, 40.0));
+        assert_eq!(first.get(), InputAction::Pressed);
+        assert_eq!(second.get(), InputAction::Pressed);
+        assert_eq!(first_x.get().0, 10.0);
+        assert_eq!(second_x.get().0, 30.0);
+        assert_eq!(second_y.get().0, 40.0);
+        context.maintain();
+
+        context.on_event(&touch(1, TouchPhase::Moved, 50.0, 60.0));
+        assert_eq!(first.get(), InputAction::Hold);
+        assert_eq!(second.get(), InputAction::Hold);
+        assert_eq!(first_x.get().0, 10.0);
+        assert_eq!(second_x.get().0, 50.0);
+        assert_eq!(second_y.get().0, 60.0);
+
+        context.on_event(&touch(0, TouchPhase::Ended, 10.0, 20.0));
+        context.on_event(&touch(1, TouchPhase::Cancelled, 50.0, 60.0));
+        assert_eq!(first.get(), InputAction::Released);
+        assert_eq!(second.get(), InputAction::Released);
+        context.maintain();
+        assert_eq!(first.get(), InputAction::Idle);
+        assert_eq!(second.get(), InputAction::Idle);
+    }
+
+    #[test]
+    fn test_mouse_delta() {
+        let x = InputAxisRef::default();
+        let y = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .axis(VirtualAxis::MouseDeltaX, x.clone())
+                .axis(VirtualAxis::MouseDeltaY, y.clone()),
+        );
+
+        context.on_device_event(&DeviceEvent::MouseMotion { delta: (2.0, -1.0) });
+        context.on_device_event(&DeviceEvent::MouseMotion { delta: (3.0, -4.0) });
+        assert_eq!(x.get().0, 5.0);
+        assert_eq!(y.get().0, -5.0);
+        context.maintain();
+        assert_eq!(x.get().0, 0.0);
+        assert_eq!(y.get().0, 0.0);
+    }
+
+    #[test]
+    fn test_multiclick() {
+        let click = |context: &mut InputContext, state| {
+            #[allow(deprecated)]
+            context.on_event(&WindowEvent::MouseInput {
+                device_id: unsafe { DeviceId::dummy() },
+                state,
+                button: MouseButton::Left,
+                modifiers: Default::default(),
+            });
+        };
+        let single = InputActionRef::default();
+        let double = InputActionRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .action(
+                    VirtualAction::MouseButton(MouseButton::Left),
+                    single.clone(),
+                )
+                .action_multiclick(
+                    VirtualAction::MouseButton(MouseButton::Left),
+                    double.clone(),
+                    2,
+                    Duration::from_secs(60),
+                ),
+        );
+
+        click(&mut context, ElementState::Pressed);
+        assert_eq!(single.get(), InputAction::Pressed);
+        assert_eq!(double.get(), InputAction::Idle);
+        click(&mut context, ElementState::Released);
+        context.maintain();
+        click(&mut context, ElementState::Pressed);
+        assert_eq!(single.get(), InputAction::Pressed);
+        assert_eq!(double.get(), InputAction::Pressed);
+        context.maintain();
+        assert_eq!(double.get(), InputAction::Hold);
+        click(&mut context, ElementState::Released);
+        assert_eq!(double.get(), InputAction::Released);
+        context.maintain();
+        click(&mut context, ElementState::Pressed);
+        assert_eq!(double.get(), InputAction::Idle);
+        click(&mut context, ElementState::Released);
+
+        let double = InputActionRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().action_multiclick(
+            VirtualAction::MouseButton(MouseButton::Left),
+            double.clone(),
+            2,
+            Duration::from_millis(1),
+        ));
+        click(&mut context, ElementState::Pressed);
+        click(&mut context, ElementState::Released);
+        std::thread::sleep(Duration::from_millis(10));
+        context.maintain();
+        click(&mut context, ElementState::Pressed);
+        assert_eq!(double.get(), InputAction::Idle);
+    }
+
+    #[test]
+    fn test_chord() {
+        let key = |context: &mut InputContext, key, state| {
+            #[allow(deprecated)]
+            context.on_event(&WindowEvent::KeyboardInput {
+                device_id: unsafe { DeviceId::dummy() },
+                input: KeyboardInput {
+                    scancode: 0,
+                    state,
+                    virtual_keycode: Some(key),
+                    modifiers: Default::default(),
+                },
+                is_synthetic: false,
+            });
+        };
+        let control = InputActionRef::default();
+        let s = InputActionRef::default();
+        let chord = ChordInputCombinator::new([control.clone(), s.clone()]);
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .action(VirtualAction::KeyButton(VirtualKeyCode::LControl), control)
+                .action(VirtualAction::KeyButton(VirtualKeyCode::S), s),
+        );
+
+        key(&mut context, VirtualKeyCode::S, ElementState::Pressed);
+        assert_eq!(chord.get(), InputAction::Idle);
+        context.maintain();
+        assert_eq!(chord.get(), InputAction::Idle);
+        key(&mut context, VirtualKeyCode::S, ElementState::Released);
+        context.maintain();
+
+        key(
+            &mut context,
+            VirtualKeyCode::LControl,
+            ElementState::Pressed,
+        );
+        assert_eq!(chord.get(), InputAction::Idle);
+        context.maintain();
+        key(&mut context, VirtualKeyCode::S, ElementState::Pressed);
+        assert_eq!(chord.get(), InputAction::Pressed);
+        context.maintain();
+        assert_eq!(chord.get(), InputAction::Hold);
+        key(&mut context, VirtualKeyCode::S, ElementState::Released);
+        assert_eq!(chord.get(), InputAction::Released);
+        context.maintain();
+        assert_eq!(chord.get(), InputAction::Idle);
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn test_gamepad_deadzone() {
+        let axis = InputAxisRef::default();
+        let mut context = InputContext::default();
+        context.push_mapping(InputMapping::default().gamepad_deadzone(0.15).axis(
+            VirtualAxis::GamepadAxis(GamepadAxis::LeftStickX),
+            axis.clone(),
+        ));
+
+        context.on_gamepad_axis(GamepadAxis::LeftStickX, 0.07);
+        assert_eq!(axis.get().0, 0.0);
+        context.on_gamepad_axis(GamepadAxis::LeftStickX, -0.12);
+        assert_eq!(axis.get().0, 0.0);
+        context.on_gamepad_axis(GamepadAxis::LeftStickX, 0.5);
+        assert_eq!(axis.get().0, 0.5);
+        context.on_gamepad_axis(GamepadAxis::LeftStickX, -0.15);
+        assert_eq!(axis.get().0, -0.15);
+    }
+
+    #[test]
+    fn test_curved() {
+        let axis = InputAxisRef::default();
+        axis.set(InputAxis(0.5));
+        assert_eq!(CurvedInputCombinator::linear(axis.clone()).get(), 0.5);
+        assert_eq!(CurvedInputCombinator::quadratic(axis.clone()).get(), 0.25);
+        assert_eq!(CurvedInputCombinator::power(axis.clone(), 3.0).get(), 0.125);
+        let custom = CurvedInputCombinator::new(axis.clone(), |value| value * 2.0);
+        assert_eq!(custom.get(), 1.0);
+        let deadzone = CurvedInputCombinator::deadzone(axis.clone(), 0.25, |value| value);
+        assert_eq!(deadzone.get(), 1.0 / 3.0);
+        axis.set(InputAxis(-0.5));
+        assert_eq!(CurvedInputCombinator::quadratic(axis.clone()).get(), -0.25);
+        axis.set(InputAxis(0.125));
+        assert_eq!(deadzone.get(), 0.0);
+
+        let right = InputAxisRef::default();
+        let down = InputAxisRef::default();
+        let cardinal = CardinalInputCombinator::new(
+            InputActionRef::default(),
+            right.clone(),
+            InputActionRef::default(),
+            down.clone(),
+        )
+        .curved(|value| value * value);
+        right.set(InputAxis(0.5));
+        assert_eq!(cardinal.get(), [0.25, 0.0]);
+        right.set(InputAxis(0.0));
+        down.set(InputAxis(-0.5));
+        assert_eq!(cardinal.get(), [0.0, -0.25]);
+    }
+
+    #[test]
+    fn test_smoothed() {
+        let axis = InputAxisRef::default();
+        let mut smoothed = SmoothedInputCombinator::new(axis.clone(), 10.0);
+        axis.set(InputAxis(1.0));
+        smoothed.tick(0.0);
+        assert_eq!(smoothed.get(), 0.0);
+        smoothed.tick(0.1);
+        let first = smoothed.get();
+        assert!(first > 0.0 && first < 1.0);
+        smoothed.tick(0.1);
+        assert!(smoothed.get() > first && smoothed.get() < 1.0);
+        for _ in 0..100 {
+            smoothed.tick(0.1);
+        }
+        assert!((smoothed.get() - 1.0).abs() < 1.0e-6);
+        let value = smoothed.get();
+        smoothed.tick(0.0);
+        assert_eq!(smoothed.get(), value);
+
+        axis.set(InputAxis(-0.5));
+        smoothed.reset();
+        assert_eq!(smoothed.get(), -0.5);
+    }
+
+    #[test]
+    fn test_pressed_actions() {
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .name("game")
+                .action(
+                    VirtualAction::MouseButton(MouseButton::Left),
+                    InputActionRef::default(),
+                )
+                .consume(InputConsume::Hit),
+        );
+        context.push_mapping(InputMapping::default().name("menu").action(
+            VirtualAction::MouseButton(MouseButton::Right),
+            InputActionRef::default(),
+        ));
+        assert!(!context.any_action_pressed());
+
+        mouse_input(&mut context, MouseButton::Left, ElementState::Pressed);
+        assert!(context.any_action_pressed());
+        assert_eq!(
+            context.pressed_actions().collect::<Vec<_>>(),
+            vec![("game".into(), VirtualAction::MouseButton(MouseButton::Left))]
+        );
+        context.maintain();
+        assert!(!context.any_action_pressed());
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn test_gamepad_slots() {
+        let mut slots = vec![];
+        assign_slots(&mut slots, [3, 5]);
+        assert_eq!(slots, vec![Some(3), Some(5)]);
+        assign_slots(&mut slots, [5]);
+        assert_eq!(slots, vec![None, Some(5)]);
+        assign_slots(&mut slots, [5, 8, 9]);
+        assert_eq!(slots, vec![Some(8), Some(5), Some(9)]);
+        assign_slots(&mut slots, [8]);
+        assert_eq!(slots, vec![Some(8)]);
+
+        let first = InputActionRef::default();
+        let second = InputActionRef::default();
+        let any = InputActionRef::default();
+        let south = VirtualAction::GamepadButton(GamepadButton::South);
+        let mut context = InputContext::default();
+        context.push_mapping(
+            InputMapping::default()
+                .gamepad_slot(0)
+                .action(south, first.clone()),
+        );
+        context.push_mapping(
+            InputMapping::default()
+                .gamepad_slot(1)
+                .action(south, second.clone()),
+        );
+        context.push_mapping(InputMapping::default().action(south, any.clone()));
+
+        context.on_gamepad_button_slot(1, GamepadButton::South, 1.0);
+        assert_eq!(first.get(), InputAction::Idle);
+        assert_eq!(second.get(), InputAction::Pressed);
+        assert_eq!(any.get(), InputAction::Idle);
+        context.on_gamepad_button(GamepadButton::South, 1.0);
+        assert_eq!(first.get(), InputAction::Idle);
+        assert_eq!(any.get(), InputAction::Pressed);
+    }
 }